@@ -1,6 +1,9 @@
 // Rust guideline compliant 2025-11-29
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::db::schema::FtsTokenizer;
+use crate::scheduler::SchedulerKind;
 
 /// Flashcard web application with multi-deck support
 #[derive(Parser, Debug)]
@@ -8,17 +11,43 @@ use clap::Parser;
 #[command(version)]
 #[command(about = "Flashcard web application with full-text search and multi-deck support", long_about = None)]
 pub struct Cli {
-    /// Rebuild deck by deleting existing DB file before startup
+    /// Rebuild deck by reloading its flashcards from content (review history is kept)
     #[arg(short = 'r', long = "rebuild-deck-id", value_name = "DECK_ID")]
     pub rebuild_deck: Option<String>,
 
     /// Deck to load (directory name under ./static/)
-    #[arg(short = 'd', long = "deck-id", value_name = "DECK_ID")]
+    #[arg(short = 'd', long = "deck-id", value_name = "DECK_ID", global = true)]
     pub deck: Option<String>,
 
     /// Display name for deck in HTML (overrides deck directory name)
     #[arg(short = 'n', long = "deck-display-name", value_name = "DECK_DISPLAY_NAME")]
     pub deck_name: Option<String>,
+
+    /// Spaced repetition algorithm (overrides SCHEDULER env var)
+    #[arg(short = 's', long = "scheduler", value_name = "ALGORITHM", value_enum)]
+    pub scheduler: Option<SchedulerKind>,
+
+    /// Full-text search tokenizer (overrides FTS_TOKENIZER env var)
+    #[arg(long = "fts-tokenizer", value_name = "TOKENIZER", value_enum)]
+    pub fts_tokenizer: Option<FtsTokenizer>,
+
+    /// Watch content directories and reload changed cards while running (authoring mode)
+    #[arg(short = 'w', long = "watch")]
+    pub watch: bool,
+
+    /// With --watch, reload open browser tabs when cards change
+    #[arg(long = "live-reload", requires = "watch")]
+    pub live_reload: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommands (the web server runs when none is given)
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Lint deck markdown (file:line diagnostics) without touching the database; exits with 1 on errors
+    Check,
 }
 
 impl Cli {
@@ -1,6 +1,9 @@
 // Rust guideline compliant 2025-01
 use std::env;
 
+use crate::db::schema::FtsTokenizer;
+use crate::scheduler::SchedulerKind;
+
 /// Application configuration loaded from environment variables.
 ///
 /// Provides deck path resolution and configuration priorities:
@@ -13,6 +16,8 @@ pub struct Config {
     pub deck_display_name: String,
     pub md_path: String,
     pub img_path: String,
+    pub scheduler: SchedulerKind,
+    pub fts_tokenizer: FtsTokenizer,
 }
 
 impl Config {
@@ -20,8 +25,10 @@ impl Config {
     ///
     /// # Configuration Priority
     /// 1. CLI arguments (highest priority)
-    /// 2. Environment variables (DECK_ID, DECK_DISPLAY_NAME, DATABASE_URL, PORT)
-    /// 3. Default values (deck, "Data Science Flashcards", "./deck.db", 8080)
+    /// 2. Environment variables (DECK_ID, DECK_DISPLAY_NAME, DATABASE_URL, PORT, SCHEDULER, FTS_TOKENIZER)
+    /// 3. Default values (deck, "Data Science Flashcards", "./deck.db", 8080, sm2, unicode61)
+    ///
+    /// The `--scheduler` and `--fts-tokenizer` CLI overrides are applied by the caller on the returned config.
     ///
     /// # Examples
     /// ```no_run
@@ -31,7 +38,8 @@ impl Config {
     /// ```
     ///
     /// # Errors
-    /// Returns error if PORT environment variable is invalid u16.
+    /// Returns error if PORT environment variable is invalid u16, SCHEDULER is not a known algorithm
+    /// or FTS_TOKENIZER is not a known tokenizer.
     pub fn from_env(cli_deck: Option<String>, cli_deck_name: Option<String>) -> anyhow::Result<Self> {
         let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string()).parse().expect("PORT must be a valid u16");
 
@@ -43,9 +51,9 @@ impl Config {
 
         // For display name: if deck_id came from CLI, ignore env vars and default to deck_id
         // This ensures --deck-id test_42 shows "test_42", not env DECK_DISPLAY_NAME
-        let deck_display_name = if cli_deck_name.is_some() {
+        let deck_display_name = if let Some(name) = cli_deck_name {
             // Explicit CLI display name provided
-            cli_deck_name.unwrap()
+            name
         } else if cli_deck.is_some() {
             // CLI deck_id provided but no display name → use deck_id
             deck_id.clone()
@@ -70,6 +78,18 @@ impl Config {
         let md_path = format!("./static/{}/md", deck_id);
         let img_path = format!("./static/{}/img", deck_id);
 
+        // Spaced repetition algorithm (defaults to SM-2)
+        let scheduler = match env::var("SCHEDULER") {
+            Ok(value) => value.parse().map_err(anyhow::Error::msg)?,
+            Err(_) => SchedulerKind::default(),
+        };
+
+        // Full-text search tokenizer (defaults to unicode61)
+        let fts_tokenizer = match env::var("FTS_TOKENIZER") {
+            Ok(value) => value.parse().map_err(anyhow::Error::msg)?,
+            Err(_) => FtsTokenizer::default(),
+        };
+
         Ok(Config {
             port,
             database_url,
@@ -77,6 +97,8 @@ impl Config {
             deck_display_name,
             md_path,
             img_path,
+            scheduler,
+            fts_tokenizer,
         })
     }
 }
@@ -105,6 +127,8 @@ mod tests {
                 env::remove_var("DECK_DISPLAY_NAME");
                 env::remove_var("DECK_NAME");
                 env::remove_var("DATABASE_URL");
+                env::remove_var("SCHEDULER");
+                env::remove_var("FTS_TOKENIZER");
             }
             Self { keys: Vec::new() }
         }
@@ -131,6 +155,8 @@ mod tests {
                 env::remove_var("DECK_DISPLAY_NAME");
                 env::remove_var("DECK_NAME");
                 env::remove_var("DATABASE_URL");
+                env::remove_var("SCHEDULER");
+                env::remove_var("FTS_TOKENIZER");
             }
         }
     }
@@ -345,6 +371,74 @@ mod tests {
         let _config = Config::from_env(None, None).unwrap();
     }
 
+    // ========== Tests for Scheduler Selection ==========
+
+    #[test]
+    #[serial]
+    fn test_scheduler_default_sm2() {
+        let _guard = EnvGuard::new();
+
+        let config = Config::from_env(None, None).unwrap();
+
+        assert_eq!(config.scheduler, SchedulerKind::Sm2);
+    }
+
+    #[test]
+    #[serial]
+    fn test_scheduler_from_env_var() {
+        let mut guard = EnvGuard::new();
+        guard.set("SCHEDULER", "fsrs");
+
+        let config = Config::from_env(None, None).unwrap();
+
+        assert_eq!(config.scheduler, SchedulerKind::Fsrs);
+    }
+
+    #[test]
+    #[serial]
+    fn test_scheduler_invalid_is_error() {
+        let mut guard = EnvGuard::new();
+        guard.set("SCHEDULER", "leitner");
+
+        let result = Config::from_env(None, None);
+
+        assert!(result.is_err());
+    }
+
+    // ========== Tests for FTS Tokenizer Selection ==========
+
+    #[test]
+    #[serial]
+    fn test_fts_tokenizer_default_unicode61() {
+        let _guard = EnvGuard::new();
+
+        let config = Config::from_env(None, None).unwrap();
+
+        assert_eq!(config.fts_tokenizer, FtsTokenizer::Unicode61);
+    }
+
+    #[test]
+    #[serial]
+    fn test_fts_tokenizer_from_env_var() {
+        let mut guard = EnvGuard::new();
+        guard.set("FTS_TOKENIZER", "porter");
+
+        let config = Config::from_env(None, None).unwrap();
+
+        assert_eq!(config.fts_tokenizer, FtsTokenizer::Porter);
+    }
+
+    #[test]
+    #[serial]
+    fn test_fts_tokenizer_invalid_is_error() {
+        let mut guard = EnvGuard::new();
+        guard.set("FTS_TOKENIZER", "ascii");
+
+        let result = Config::from_env(None, None);
+
+        assert!(result.is_err());
+    }
+
     // ========== Tests for Backward Compatibility ==========
 
     #[test]
@@ -0,0 +1,167 @@
+// Rust guideline compliant 2025-01
+//! Stable card identities.
+//!
+//! Every flashcard gets a key that survives database rebuilds, used by all
+//! per-card state (spaced repetition, review log, seen cards). The key is either
+//! an explicit `ID:` line written in the question, or a hash of the normalized
+//! question text and the source file, so editing an answer keeps the card's history.
+
+use anyhow::Result;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+
+/// `ID: <key>` line inside a question (case-insensitive, on its own line).
+static ID_LINE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?mi)^[ \t]*ID[ \t]*:[ \t]*(.*?)[ \t]*(?:\r?\n|$)").unwrap());
+
+/// Number of hex characters kept from the SHA-256 digest.
+const HASH_KEY_LEN: usize = 16;
+
+/// Returns true if `key` is usable as an explicit card key.
+///
+/// Keys are non-empty, at most 64 characters, and URL-safe:
+/// ASCII letters, digits, `-`, `_` and `.`.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= 64 && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Extracts an explicit `ID:` line from the question part of a card.
+///
+/// Returns the ID (None if absent or invalid) and the question part with the
+/// `ID:` line removed. Invalid IDs are removed too and logged.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::card_key::extract_explicit_id;
+///
+/// let (id, question) = extract_explicit_id("Rust - Basics - What is a crate?\nID: rust-crate");
+/// assert_eq!(id.as_deref(), Some("rust-crate"));
+/// assert_eq!(question, "Rust - Basics - What is a crate?\n");
+/// ```
+pub fn extract_explicit_id(question_part: &str) -> (Option<String>, String) {
+    let Some(caps) = ID_LINE_REGEX.captures(question_part) else {
+        return (None, question_part.to_string());
+    };
+
+    let id = caps.get(1).map_or("", |m| m.as_str());
+    let stripped = ID_LINE_REGEX.replace(question_part, "").into_owned();
+
+    if is_valid_key(id) {
+        (Some(id.to_string()), stripped)
+    } else {
+        tracing::warn!("Ignoring invalid card ID {:?} (allowed: letters, digits, '-', '_', '.')", id);
+        (None, stripped)
+    }
+}
+
+/// Computes the key of a card without explicit ID.
+///
+/// Hashes the question text, with whitespace runs collapsed, together with the
+/// card's source file path (relative to the deck content directory).
+pub fn hashed_key(question: &str, source: &str) -> String {
+    let normalized = question.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(normalized.as_bytes());
+
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect::<String>()[..HASH_KEY_LEN].to_string()
+}
+
+/// Makes `key` unique against `existing` keys by appending `-2`, `-3`...
+///
+/// Duplicated keys (repeated question in a file, reused explicit ID) are logged.
+///
+/// # Errors
+/// Returns the first error of `exists`.
+pub fn disambiguate(key: String, source: &str, exists: impl Fn(&str) -> Result<bool>) -> Result<String> {
+    if !exists(&key)? {
+        return Ok(key);
+    }
+
+    for n in 2.. {
+        let candidate = format!("{}-{}", key, n);
+        if !exists(&candidate)? {
+            tracing::warn!("Duplicate card key {:?} in {}, using {:?}", key, source, candidate);
+            return Ok(candidate);
+        }
+    }
+    unreachable!("unbounded suffix range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("rust-crate", true)]
+    #[case("Q_42.v2", true)]
+    #[case("", false)]
+    #[case("with space", false)]
+    #[case("slash/inside", false)]
+    #[case("accentué", false)]
+    fn test_is_valid_key(#[case] key: &str, #[case] expected: bool) {
+        assert_eq!(is_valid_key(key), expected);
+    }
+
+    #[test]
+    fn test_is_valid_key_rejects_long_keys() {
+        assert!(is_valid_key(&"a".repeat(64)));
+        assert!(!is_valid_key(&"a".repeat(65)));
+    }
+
+    #[rstest]
+    #[case("Cat - Sub - Q?\nID: my-card", Some("my-card"), "Cat - Sub - Q?\n")]
+    #[case("Cat - Sub - Q?\n  id :  my-card  \nmore", Some("my-card"), "Cat - Sub - Q?\nmore")]
+    #[case("Cat - Sub - Q?", None, "Cat - Sub - Q?")]
+    #[case("Cat - Sub - Q?\nID: not valid", None, "Cat - Sub - Q?\n")]
+    #[case("Cat - Sub - What is an ID: number?", None, "Cat - Sub - What is an ID: number?")]
+    fn test_extract_explicit_id(#[case] input: &str, #[case] expected_id: Option<&str>, #[case] expected_question: &str) {
+        let (id, question) = extract_explicit_id(input);
+
+        assert_eq!(id.as_deref(), expected_id);
+        assert_eq!(question, expected_question);
+    }
+
+    #[test]
+    fn test_hashed_key_is_stable_and_short() {
+        let key = hashed_key("What is a crate?", "rust/basics.md");
+
+        assert_eq!(key.len(), HASH_KEY_LEN);
+        assert!(is_valid_key(&key));
+        assert_eq!(key, hashed_key("What is a crate?", "rust/basics.md"));
+    }
+
+    #[test]
+    fn test_hashed_key_ignores_whitespace_changes() {
+        assert_eq!(
+            hashed_key("What is\na  crate?", "basics.md"),
+            hashed_key("  What is a crate? ", "basics.md")
+        );
+    }
+
+    #[rstest]
+    #[case("What is a module?", "basics.md")]
+    #[case("What is a crate?", "advanced.md")]
+    fn test_hashed_key_depends_on_question_and_source(#[case] question: &str, #[case] source: &str) {
+        assert_ne!(hashed_key("What is a crate?", "basics.md"), hashed_key(question, source));
+    }
+
+    #[test]
+    fn test_disambiguate_appends_suffix() {
+        let existing = ["card".to_string(), "card-2".to_string()];
+        let exists = |k: &str| Ok(existing.iter().any(|e| e == k));
+
+        assert_eq!(disambiguate("other".to_string(), "test.md", exists).unwrap(), "other");
+        assert_eq!(disambiguate("card".to_string(), "test.md", exists).unwrap(), "card-3");
+    }
+
+    #[test]
+    fn test_disambiguate_propagates_errors() {
+        let result = disambiguate("card".to_string(), "test.md", |_| Err(anyhow::anyhow!("database is locked")));
+
+        assert_eq!(result.unwrap_err().to_string(), "database is locked");
+    }
+}
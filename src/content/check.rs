@@ -0,0 +1,465 @@
+// Rust guideline compliant 2025-01
+//! Deck content linting (`check` subcommand).
+//!
+//! Parses a deck's markdown files without touching the database and reports
+//! diagnostics with file and line, so content can be reviewed before merging.
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+use syntect::parsing::SyntaxSet;
+use walkdir::WalkDir;
+
+use super::card_key;
+use super::cloze;
+use super::front_matter;
+use super::links::{Link, LinkResolver};
+use super::markdown::{markdown_options, parse_cards, RawCard};
+use super::typed_answer::ExpectedAnswer;
+
+/// `src` attribute of an HTML `<img>` tag.
+static IMG_SRC_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["']"#).unwrap());
+
+/// Severity of a diagnostic. Errors make `check` exit with a non-zero code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// Rule broken by deck content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// `Question :` block without `Answer :` (the block is not loaded).
+    MissingAnswer,
+    /// Question without `Category - Subcategory - ` prefix.
+    MissingCategory,
+    /// `Answer :` marker followed by nothing.
+    EmptyAnswer,
+    /// `Cloze :` block without `{{c1::...}}` deletion (the block is not loaded).
+    MissingCloze,
+    /// `Hint :` marker followed by nothing (the hint is not loaded).
+    EmptyHint,
+    /// `Expected:` line with an invalid regex or no answer (typed-answer mode is not offered).
+    InvalidExpected,
+    /// `Distractors:` line without any option (quiz options are drawn from other cards).
+    EmptyDistractors,
+    /// Same question text as an earlier card of the deck.
+    DuplicateQuestion,
+    /// `ID:` already used by an earlier card (the card gets a suffixed key).
+    DuplicateId,
+    /// Question repeated in its file: both cards hash to the same key (the later one gets a suffixed key).
+    DuplicateKey,
+    /// Code fence language without syntax highlighting.
+    UnknownCodeLanguage,
+    /// Image reference to a file that does not exist.
+    BrokenImage,
+    /// Front matter that is not valid YAML or has unknown keys (defaults are ignored).
+    InvalidFrontMatter,
+}
+
+impl Rule {
+    /// Identifier printed in diagnostics.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rule::MissingAnswer => "missing-answer",
+            Rule::MissingCategory => "missing-category",
+            Rule::EmptyAnswer => "empty-answer",
+            Rule::MissingCloze => "missing-cloze",
+            Rule::EmptyHint => "empty-hint",
+            Rule::InvalidExpected => "invalid-expected",
+            Rule::EmptyDistractors => "empty-distractors",
+            Rule::DuplicateQuestion => "duplicate-question",
+            Rule::DuplicateId => "duplicate-id",
+            Rule::DuplicateKey => "duplicate-key",
+            Rule::UnknownCodeLanguage => "unknown-code-language",
+            Rule::BrokenImage => "broken-image",
+            Rule::InvalidFrontMatter => "invalid-front-matter",
+        }
+    }
+
+    /// Severity of the rule.
+    pub fn severity(self) -> Severity {
+        match self {
+            Rule::DuplicateQuestion | Rule::DuplicateKey | Rule::UnknownCodeLanguage => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// Problem found in deck content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// File path (below the markdown directory).
+    pub file: String,
+    /// Line in the file (1-based).
+    pub line: usize,
+    pub rule: Rule,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    /// Formats as `file:line: severity[rule]: message`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}[{}]: {}",
+            self.file,
+            self.line,
+            self.rule.severity(),
+            self.rule.as_str(),
+            self.message
+        )
+    }
+}
+
+/// Checks every markdown file of `md_dir`.
+///
+/// Image references are resolved relative to their file, or from `root` (the
+/// project root, for `static/<deck>/...` paths), as when loading cards (see
+/// [`LinkResolver`]). Diagnostics are sorted by file and line.
+pub fn check_markdown_dir(md_dir: &str, root: &Path) -> Vec<Diagnostic> {
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let mut checker = Checker::new(root, &syntaxes);
+
+    for entry in WalkDir::new(md_dir)
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().map(|ext| ext == "md").unwrap_or(false))
+    {
+        let file = entry.path().display().to_string();
+        match fs::read_to_string(entry.path()) {
+            Ok(content) => checker.check_file(&file, &content),
+            Err(e) => tracing::warn!("Failed to read {:?}: {}", entry.path(), e),
+        }
+    }
+
+    checker.into_diagnostics()
+}
+
+/// Checks a single markdown file (duplicates are only detected within it).
+pub fn check_markdown(file: &str, content: &str, root: &Path) -> Vec<Diagnostic> {
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let mut checker = Checker::new(root, &syntaxes);
+    checker.check_file(file, content);
+    checker.into_diagnostics()
+}
+
+/// Diagnostics collected over a deck, with state for cross-file rules.
+struct Checker<'a> {
+    root: &'a Path,
+    syntaxes: &'a SyntaxSet,
+    /// First location of each normalized question text.
+    questions: HashMap<String, (String, usize)>,
+    /// First location of each card key.
+    keys: HashMap<String, (String, usize)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(root: &'a Path, syntaxes: &'a SyntaxSet) -> Self {
+        Self {
+            root,
+            syntaxes,
+            questions: HashMap::new(),
+            keys: HashMap::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Returns diagnostics sorted by file and line.
+    fn into_diagnostics(mut self) -> Vec<Diagnostic> {
+        self.diagnostics.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        self.diagnostics
+    }
+
+    fn report(&mut self, file: &str, line: usize, rule: Rule, message: String) {
+        self.diagnostics.push(Diagnostic {
+            file: file.to_string(),
+            line,
+            rule,
+            message,
+        });
+    }
+
+    fn check_file(&mut self, file: &str, content: &str) {
+        if let (Some(yaml), _) = front_matter::split(content)
+            && let Err(e) = front_matter::parse(yaml)
+        {
+            // YAML starts on the line after the opening "---"
+            let line = 1 + e.location().map_or(1, |location| location.line());
+            self.report(file, line, Rule::InvalidFrontMatter, e.to_string());
+        }
+
+        for card in parse_cards(content) {
+            self.check_card(file, &card);
+        }
+    }
+
+    fn check_card(&mut self, file: &str, card: &RawCard) {
+        if card.category.is_none() {
+            self.report(
+                file,
+                card.line,
+                Rule::MissingCategory,
+                "question has no 'Category - Subcategory - ' prefix".to_string(),
+            );
+        }
+
+        // Same keys as when loading: a duplicate key is suffixed, in file order
+        let key = card.explicit_id.clone().unwrap_or_else(|| card_key::hashed_key(&card.question_md, file));
+        let repeated_in_file = match self.keys.get(&key) {
+            Some((first_file, first_line)) => {
+                let (rule, message) = match &card.explicit_id {
+                    Some(id) => (Rule::DuplicateId, format!("ID '{}' already used at {}:{}", id, first_file, first_line)),
+                    None => (
+                        Rule::DuplicateKey,
+                        format!("same card key as {}:{} (add an 'ID:' line to keep its history)", first_file, first_line),
+                    ),
+                };
+                self.report(file, card.line, rule, message);
+                rule == Rule::DuplicateKey
+            }
+            None => {
+                self.keys.insert(key, (file.to_string(), card.line));
+                false
+            }
+        };
+
+        // Same normalization as hashed card keys, ignoring case (a repeated key is already reported)
+        let normalized = card.question_md.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if !normalized.is_empty() && !repeated_in_file {
+            match self.questions.get(&normalized) {
+                Some((first_file, first_line)) => {
+                    let message = format!("same question as {}:{}", first_file, first_line);
+                    self.report(file, card.line, Rule::DuplicateQuestion, message);
+                }
+                None => {
+                    self.questions.insert(normalized, (file.to_string(), card.line));
+                }
+            }
+        }
+
+        self.check_markdown_body(file, &card.question_md, card.line);
+
+        for (hint, hint_line) in &card.hints {
+            if hint.is_empty() {
+                self.report(file, *hint_line, Rule::EmptyHint, "hint is empty".to_string());
+            } else {
+                self.check_markdown_body(file, hint, *hint_line);
+            }
+        }
+
+        match card.expected.as_deref().map(ExpectedAnswer::parse) {
+            Some(Err(e)) => self.report(file, card.line, Rule::InvalidExpected, format!("invalid expected answer: {}", e)),
+            Some(Ok(None)) => self.report(file, card.line, Rule::InvalidExpected, "expected answer is empty".to_string()),
+            _ => {}
+        }
+
+        if card.distractors.as_ref().is_some_and(Vec::is_empty) {
+            self.report(file, card.line, Rule::EmptyDistractors, "distractors line is empty".to_string());
+        }
+
+        if card.cloze && cloze::indexes(&card.question_md).is_empty() {
+            self.report(
+                file,
+                card.line,
+                Rule::MissingCloze,
+                "cloze has no '{{c1::...}}' deletion (card is not loaded)".to_string(),
+            );
+        }
+
+        // The answer of a cloze is optional extra text
+        match &card.answer {
+            None if card.cloze => {}
+            Some((answer, _)) if card.cloze && answer.is_empty() => {}
+            None => self.report(
+                file,
+                card.line,
+                Rule::MissingAnswer,
+                "question has no 'Answer :' (card is not loaded)".to_string(),
+            ),
+            Some((answer, _)) if answer.is_empty() => {
+                self.report(file, card.line, Rule::EmptyAnswer, "answer is empty".to_string());
+            }
+            Some((answer, answer_line)) => self.check_markdown_body(file, answer, *answer_line),
+        }
+    }
+
+    /// Checks code fences and image references of a question or answer starting at `first_line`.
+    fn check_markdown_body(&mut self, file: &str, markdown: &str, first_line: usize) {
+        let line_at = |offset: usize| first_line + markdown[..offset].matches('\n').count();
+
+        for (event, range) in Parser::new_ext(markdown, markdown_options()).into_offset_iter() {
+            match event {
+                // Same lookup as syntax highlighting when rendering
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang)))
+                    if !lang.is_empty() && self.syntaxes.find_syntax_by_token(&lang).is_none() =>
+                {
+                    let message = format!("unknown code fence language '{}' (rendered as plain text)", lang);
+                    self.report(file, line_at(range.start), Rule::UnknownCodeLanguage, message);
+                }
+                Event::Start(Tag::Image { dest_url, .. }) => {
+                    self.check_image(file, line_at(range.start), &dest_url);
+                }
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    for caps in IMG_SRC_REGEX.captures_iter(&html) {
+                        let line = line_at(range.start) + html[..caps.get(0).unwrap().start()].matches('\n').count();
+                        self.check_image(file, line, &caps[1]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_image(&mut self, file: &str, line: usize, src: &str) {
+        // Remote images cannot be checked offline, only missing files are reported
+        let links = LinkResolver::new(&self.root.join("static"), Path::new(file));
+        if links.resolve(src) == Link::Missing {
+            self.report(file, line, Rule::BrokenImage, format!("image not found: {}", src));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn rules(content: &str) -> Vec<(usize, Rule)> {
+        check_markdown("test.md", content, Path::new("."))
+            .into_iter()
+            .map(|d| (d.line, d.rule))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_card_has_no_diagnostics() {
+        assert!(rules("Question : Cat - Sub - Q?\nAnswer : A").is_empty());
+    }
+
+    #[rstest]
+    #[case("Question : Cat - Sub - Q?\nNo answer here", vec![(1, Rule::MissingAnswer)])]
+    #[case("Question : Q without category?\nAnswer : A", vec![(1, Rule::MissingCategory)])]
+    #[case("Question : Cat - Sub - Q?\nAnswer :\n", vec![(1, Rule::EmptyAnswer)])]
+    #[case(
+        "Question : Cat - Sub - Q?\nAnswer : A\n\nQuestion : Other - Sub - q?\nAnswer : B",
+        vec![(4, Rule::DuplicateQuestion)]
+    )]
+    #[case(
+        "Question : Cat - Sub - Q?\nAnswer : A\n\nQuestion : Cat - Sub - Q?\nAnswer : B",
+        vec![(4, Rule::DuplicateKey)]
+    )]
+    #[case(
+        "Question : Cat - Sub - Q?\nID: q\nAnswer : A\n\nQuestion : Cat - Sub - Other?\nID: q\nAnswer : B",
+        vec![(5, Rule::DuplicateId)]
+    )]
+    #[case(
+        "Question : Cat - Sub - Q?\nID: q\nAnswer : A\n\nQuestion : Cat - Sub - Q?\nID: q2\nAnswer : B",
+        vec![(5, Rule::DuplicateQuestion)]
+    )]
+    #[case(
+        "Question : Cat - Sub - Q?\nAnswer :\n\n```notalanguage\ncode\n```",
+        vec![(4, Rule::UnknownCodeLanguage)]
+    )]
+    #[case("Question : Cat - Sub - Q?\nAnswer :\n```rust\nfn main() {}\n```", vec![])]
+    #[case("Question : Cat - Sub - Q?\nAnswer :\n![kitten](static/nope/kitten.png)", vec![(3, Rule::BrokenImage)])]
+    #[case(
+        "Question : Cat - Sub - Q?\nAnswer :\n\n<p align=\"center\">\n<img src=\"/static/nope/kitten.png\">\n</p>",
+        vec![(5, Rule::BrokenImage)]
+    )]
+    #[case("Question : Cat - Sub - Q?\nAnswer :\n![remote](https://example.com/a.png)", vec![])]
+    #[case("Cloze : Cat - Sub - Each value has a single {{c1::owner}}.", vec![])]
+    #[case("Cloze : Cat - Sub - Each value has a single {{c1::owner}}.\nAnswer :\n", vec![])]
+    #[case("Cloze : Cat - Sub - Each value has a single owner.", vec![(1, Rule::MissingCloze)])]
+    #[case("Question : Cat - Sub - Q?\nHint : H\nHint :\nAnswer : A", vec![(3, Rule::EmptyHint)])]
+    #[case("Question : Cat - Sub - Q?\nExpected: FTS5 | /a/\nAnswer : A", vec![])]
+    #[case("Question : Cat - Sub - Q?\nExpected: /(unclosed/\nAnswer : A", vec![(1, Rule::InvalidExpected)])]
+    #[case("Question : Cat - Sub - Q?\nExpected: |\nAnswer : A", vec![(1, Rule::InvalidExpected)])]
+    #[case("Question : Cat - Sub - Q?\nDistractors: B | C\nAnswer : A", vec![])]
+    #[case("Question : Cat - Sub - Q?\nDistractors:\nAnswer : A", vec![(1, Rule::EmptyDistractors)])]
+    #[case("Question : Cat - Sub - Q?\nHint :\n![kitten](static/nope/kitten.png)\nAnswer : A", vec![(3, Rule::BrokenImage)])]
+    fn test_rules(#[case] content: &str, #[case] expected: Vec<(usize, Rule)>) {
+        assert_eq!(rules(content), expected);
+    }
+
+    #[test]
+    fn test_front_matter_category_satisfies_category_rule() {
+        let content = "---\ncategory: Cat\nsubcategory: Sub\n---\nQuestion : Q?\nAnswer : A";
+        assert!(rules(content).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_front_matter() {
+        let content = "---\ncategory: Cat\ncategroy: Typo\n---\nQuestion : Cat - Sub - Q?\nAnswer : A";
+        assert_eq!(rules(content), vec![(3, Rule::InvalidFrontMatter)]);
+    }
+
+    #[test]
+    fn test_line_numbers_ignore_comments() {
+        let content = "<!--\nheader\n-->\nQuestion : no category\nAnswer : A";
+        assert_eq!(rules(content), vec![(4, Rule::MissingCategory)]);
+    }
+
+    #[test]
+    fn test_existing_image_is_accepted() {
+        let root = TempDir::new().unwrap();
+        fs::create_dir_all(root.path().join("static/deck/md")).unwrap();
+        fs::write(root.path().join("static/deck/md/kitten.png"), b"png").unwrap();
+
+        let content = "Question : Cat - Sub - Q?\nAnswer :\n![kitten](static/deck/md/kitten.png)";
+
+        assert!(check_markdown("test.md", content, root.path()).is_empty());
+    }
+
+    #[test]
+    fn test_image_relative_to_file_is_accepted() {
+        let root = TempDir::new().unwrap();
+        let md_dir = root.path().join("static/deck/md");
+        fs::create_dir_all(md_dir.join("assets")).unwrap();
+        fs::write(md_dir.join("assets/kitten.png"), b"png").unwrap();
+        let content = "Question : Cat - Sub - Q?\nAnswer :\n![kitten](assets/kitten.png)\n<img src=\"../md/assets/kitten.png\">";
+        fs::write(md_dir.join("cards.md"), content).unwrap();
+
+        assert!(check_markdown_dir(md_dir.to_str().unwrap(), root.path()).is_empty());
+
+        let file = md_dir.join("cards.md").display().to_string();
+        let diagnostics = check_markdown(&file, "Question : Cat - Sub - Q?\nAnswer :\n![kitten](kitten.png)", root.path());
+        assert_eq!(diagnostics.iter().map(|d| d.rule).collect::<Vec<_>>(), vec![Rule::BrokenImage]);
+    }
+
+    #[test]
+    fn test_duplicates_detected_across_files() {
+        let md_dir = TempDir::new().unwrap();
+        fs::write(md_dir.path().join("a.md"), "Question : Cat - Sub - Q?\nAnswer : A").unwrap();
+        fs::write(md_dir.path().join("b.md"), "\nQuestion : Cat - Sub - Q?\nAnswer : A").unwrap();
+
+        let diagnostics = check_markdown_dir(md_dir.path().to_str().unwrap(), Path::new("."));
+
+        let a = md_dir.path().join("a.md").display().to_string();
+        let b = md_dir.path().join("b.md").display().to_string();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            format!("{}:2: warning[duplicate-question]: same question as {}:1", b, a)
+        );
+    }
+}
@@ -0,0 +1,109 @@
+// Rust guideline compliant 2025-01
+//! Cloze deletions.
+//!
+//! A `Cloze :` block holds a text where spans to recall are marked with their
+//! index, Anki style:
+//!
+//! ```markdown
+//! Cloze : Rust - Ownership - Each value has a single {{c1::owner}}, and is {{c2::dropped}} when it goes out of scope.
+//! ```
+//!
+//! The block gives one card per index: its spans are blanked in the question
+//! and revealed in the answer, the spans of other indexes are shown as plain text.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// `{{c<index>::<text>}}` span (index, text).
+static CLOZE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)\{\{c(\d+)::(.*?)\}\}").unwrap());
+
+/// Placeholder of a blanked span.
+const BLANK: &str = "[...]";
+
+/// Side of a cloze card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Spans of the card's index blanked.
+    Question,
+    /// Spans of the card's index revealed (highlighted).
+    Answer,
+}
+
+/// Returns the cloze indexes of a text, sorted and without repeats.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::cloze::indexes;
+///
+/// assert_eq!(indexes("{{c2::a}} {{c1::b}} {{c2::c}}"), vec![1, 2]);
+/// assert!(indexes("no cloze").is_empty());
+/// ```
+pub fn indexes(markdown: &str) -> Vec<u32> {
+    let mut indexes: Vec<u32> = CLOZE_REGEX.captures_iter(markdown).filter_map(|caps| caps[1].parse().ok()).collect();
+    indexes.sort_unstable();
+    indexes.dedup();
+    indexes
+}
+
+/// Renders one side of the card of cloze `index` as markdown.
+///
+/// Spans of `index` are wrapped in a `cloze` span, blanked on the question side.
+pub fn render(markdown: &str, index: u32, side: Side) -> String {
+    CLOZE_REGEX
+        .replace_all(markdown, |caps: &regex::Captures| {
+            let text = &caps[2];
+            if caps[1].parse() != Ok(index) {
+                return text.to_string();
+            }
+            match side {
+                Side::Question => format!("<span class=\"cloze\">{}</span>", BLANK),
+                Side::Answer => format!("<span class=\"cloze\">{}</span>", text),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const TEXT: &str = "Each value has a single {{c1::owner}}, and is {{c2::dropped}} at the end of its {{c1::scope}}.";
+
+    #[rstest]
+    #[case(TEXT, vec![1, 2])]
+    #[case("{{c10::a}} {{c3::b}}", vec![3, 10])]
+    #[case("{{c1::multi\nline}}", vec![1])]
+    #[case("{{c::a}} {{x1::b}} {c1::c}", vec![])]
+    fn test_indexes(#[case] markdown: &str, #[case] expected: Vec<u32>) {
+        assert_eq!(indexes(markdown), expected);
+    }
+
+    #[rstest]
+    #[case(
+        1,
+        Side::Question,
+        "Each value has a single <span class=\"cloze\">[...]</span>, and is dropped at the end of its <span class=\"cloze\">[...]</span>."
+    )]
+    #[case(
+        1,
+        Side::Answer,
+        "Each value has a single <span class=\"cloze\">owner</span>, and is dropped at the end of its <span class=\"cloze\">scope</span>."
+    )]
+    #[case(
+        2,
+        Side::Question,
+        "Each value has a single owner, and is <span class=\"cloze\">[...]</span> at the end of its scope."
+    )]
+    fn test_render(#[case] index: u32, #[case] side: Side, #[case] expected: &str) {
+        assert_eq!(render(TEXT, index, side), expected);
+    }
+
+    #[test]
+    fn test_render_keeps_markdown_of_spans() {
+        assert_eq!(
+            render("A {{c1::`Box<T>`}} owns its value", 1, Side::Answer),
+            "A <span class=\"cloze\">`Box<T>`</span> owns its value"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+// Rust guideline compliant 2025-01
+//! Distractors of quiz questions.
+//!
+//! In quiz mode, a card is asked as a multiple-choice question: its answer is
+//! mixed with wrong options drawn from the answers of other cards of its
+//! subcategory. A card may declare its own wrong options instead, separated by
+//! `|` on a `Distractors:` line of its question part:
+//!
+//! ```markdown
+//! Question : SQLite - Search - Which full-text search engine does SQLite ship?
+//! Expected: FTS5
+//! Distractors: Lucene | Xapian | Tantivy
+//! ```
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// `Distractors: <a> | <b>` line inside a question (case-insensitive, on its own line).
+static DISTRACTORS_LINE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?mi)^[ \t]*Distractors[ \t]*:[ \t]*(.*?)[ \t]*(?:\r?\n|$)").unwrap());
+
+/// Extracts an inline `Distractors:` line from the question part of a card.
+///
+/// Returns the distractors (None if absent, empty if the line has none) and the
+/// question part with the line removed.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::distractors::extract_distractors;
+///
+/// let (distractors, question) = extract_distractors("Which FTS engine?\nDistractors: Lucene | Xapian");
+/// assert_eq!(distractors, Some(vec!["Lucene".to_string(), "Xapian".to_string()]));
+/// assert_eq!(question, "Which FTS engine?\n");
+/// ```
+pub fn extract_distractors(question_part: &str) -> (Option<Vec<String>>, String) {
+    match DISTRACTORS_LINE_REGEX.captures(question_part) {
+        Some(caps) => {
+            let distractors = caps[1]
+                .split('|')
+                .map(str::trim)
+                .filter(|distractor| !distractor.is_empty())
+                .map(String::from)
+                .collect();
+            (Some(distractors), DISTRACTORS_LINE_REGEX.replace(question_part, "").into_owned())
+        }
+        None => (None, question_part.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("Q?\nDistractors: a | `b` |\n", Some(vec!["a", "`b`"]), "Q?\n")]
+    #[case("Q?\ndistractors :", Some(vec![]), "Q?\n")]
+    #[case("Q?\nAnswer has Distractors: here", None, "Q?\nAnswer has Distractors: here")]
+    fn test_extract_distractors(
+        #[case] question_part: &str,
+        #[case] expected: Option<Vec<&str>>,
+        #[case] expected_rest: &str,
+    ) {
+        let expected = expected.map(|list| list.into_iter().map(String::from).collect());
+        assert_eq!(extract_distractors(question_part), (expected, expected_rest.to_string()));
+    }
+}
@@ -0,0 +1,314 @@
+// Rust guideline compliant 2025-01
+//! Per-file card metadata defaults (YAML front matter).
+//!
+//! A markdown file may start with a YAML block between `---` lines declaring
+//! defaults for all of its questions:
+//!
+//! ```markdown
+//! ---
+//! category: Python
+//! subcategory: Matplotlib
+//! tags: [plotting, snippets]
+//! difficulty: easy
+//! reverse: true
+//! ---
+//! Question : How to set the size of a figure?
+//! ```
+//!
+//! An inline `Category - Subcategory - ` prefix, `Difficulty:` or `Reverse:` line
+//! overrides them, and an inline `Tags:` line (comma separated) adds tags to the file's ones.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+/// `Difficulty: <level>` line inside a question (case-insensitive, on its own line).
+static DIFFICULTY_LINE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?mi)^[ \t]*Difficulty[ \t]*:[ \t]*(.*?)[ \t]*(?:\r?\n|$)").unwrap());
+
+/// `Tags: <tag>, <tag>` line inside a question (case-insensitive, on its own line).
+static TAGS_LINE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?mi)^[ \t]*Tags[ \t]*:[ \t]*(.*?)[ \t]*(?:\r?\n|$)").unwrap());
+
+/// `Reverse: <true|false>` line inside a question (case-insensitive, on its own line).
+static REVERSE_LINE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?mi)^[ \t]*Reverse[ \t]*:[ \t]*(.*?)[ \t]*(?:\r?\n|$)").unwrap());
+
+/// Difficulty of a card, as rated by its author.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Name stored in the database and shown on cards.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// Parses a difficulty name (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<String> for Difficulty {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Difficulty::parse(&name).ok_or_else(|| format!("unknown difficulty '{}' (allowed: easy, medium, hard)", name))
+    }
+}
+
+/// Defaults declared in the front matter of a markdown file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontMatter {
+    pub category: Option<String>,
+    pub subcategory: Option<String>,
+    pub tags: Vec<String>,
+    pub difficulty: Option<Difficulty>,
+    /// Whether questions also give a card with question and answer swapped.
+    pub reverse: Option<bool>,
+}
+
+/// Splits the front matter block from the start of `content`.
+///
+/// The block starts with a `---` first line and ends with a `---` (or `...`)
+/// line. Returns its YAML (None without front matter) and the content with the
+/// block replaced by its newlines, so line numbers stay accurate.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::front_matter::split;
+///
+/// let (yaml, body) = split("---\ncategory: Rust\n---\nQuestion : Q?");
+/// assert_eq!(yaml, Some("category: Rust\n"));
+/// assert_eq!(body, "\n\n\nQuestion : Q?");
+/// ```
+pub fn split(content: &str) -> (Option<&str>, Cow<'_, str>) {
+    let mut lines = content.split_inclusive('\n');
+    let Some(first) = lines.next() else {
+        return (None, Cow::Borrowed(content));
+    };
+    if first.trim_start_matches('\u{feff}').trim_end() != "---" {
+        return (None, Cow::Borrowed(content));
+    }
+
+    let mut offset = first.len();
+    for line in lines {
+        if matches!(line.trim_end(), "---" | "...") {
+            let end = offset + line.len();
+            let blank = "\n".repeat(content[..end].matches('\n').count());
+            return (Some(&content[first.len()..offset]), Cow::Owned(blank + &content[end..]));
+        }
+        offset += line.len();
+    }
+
+    // Never closed: a thematic break, not front matter
+    (None, Cow::Borrowed(content))
+}
+
+/// Parses front matter YAML.
+///
+/// # Errors
+/// Returns error if the YAML is invalid or has unknown keys (e.g. a typo).
+pub fn parse(yaml: &str) -> Result<FrontMatter, serde_yaml::Error> {
+    if yaml.trim().is_empty() {
+        return Ok(FrontMatter::default());
+    }
+    serde_yaml::from_str(yaml)
+}
+
+/// Normalizes tags: trimmed, lowercase, without empty or repeated ones.
+pub fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Removes the first line matching `regex` from `question_part`, returning its value.
+fn extract_line(regex: &Regex, question_part: &str) -> (Option<String>, String) {
+    match regex.captures(question_part) {
+        Some(caps) => {
+            let value = caps.get(1).map_or("", |m| m.as_str()).to_string();
+            (Some(value), regex.replace(question_part, "").into_owned())
+        }
+        None => (None, question_part.to_string()),
+    }
+}
+
+/// Extracts an inline `Difficulty:` line from the question part of a card.
+///
+/// Returns the difficulty (None if absent or unknown) and the question part
+/// with the line removed. Unknown difficulties are removed too and logged.
+pub fn extract_difficulty(question_part: &str) -> (Option<Difficulty>, String) {
+    let (name, stripped) = extract_line(&DIFFICULTY_LINE_REGEX, question_part);
+    let Some(name) = name else {
+        return (None, stripped);
+    };
+
+    let difficulty = Difficulty::parse(&name);
+    if difficulty.is_none() {
+        tracing::warn!("Ignoring unknown difficulty {:?} (allowed: easy, medium, hard)", name);
+    }
+    (difficulty, stripped)
+}
+
+/// Extracts an inline `Reverse:` line from the question part of a card.
+///
+/// Returns the flag (None if absent or neither `true` nor `false`) and the
+/// question part with the line removed. Invalid values are removed too and logged.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::front_matter::extract_reverse;
+///
+/// let (reverse, question) = extract_reverse("What does `Box<T>` do?\nReverse: true");
+/// assert_eq!(reverse, Some(true));
+/// assert_eq!(question, "What does `Box<T>` do?\n");
+/// ```
+pub fn extract_reverse(question_part: &str) -> (Option<bool>, String) {
+    let (value, stripped) = extract_line(&REVERSE_LINE_REGEX, question_part);
+    let Some(value) = value else {
+        return (None, stripped);
+    };
+
+    let reverse = match value.to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => {
+            tracing::warn!("Ignoring invalid reverse flag {:?} (allowed: true, false)", value);
+            None
+        }
+    };
+    (reverse, stripped)
+}
+
+/// Extracts an inline `Tags:` line from the question part of a card.
+///
+/// Returns the normalized tags (empty if absent) and the question part with the line removed.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::front_matter::extract_tags;
+///
+/// let (tags, question) = extract_tags("How to speed up groupby?\nTags: Pandas, performance");
+/// assert_eq!(tags, vec!["pandas", "performance"]);
+/// assert_eq!(question, "How to speed up groupby?\n");
+/// ```
+pub fn extract_tags(question_part: &str) -> (Vec<String>, String) {
+    let (list, stripped) = extract_line(&TAGS_LINE_REGEX, question_part);
+    let tags = list.map(|list| normalize_tags(list.split(','))).unwrap_or_default();
+    (tags, stripped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_split_keeps_line_numbers() {
+        let (yaml, body) = split("---\ncategory: A\nsubcategory: B\n---\nQuestion : Q?\n");
+
+        assert_eq!(yaml, Some("category: A\nsubcategory: B\n"));
+        assert_eq!(body, "\n\n\n\nQuestion : Q?\n");
+    }
+
+    #[rstest]
+    #[case("Question : Q?\n---\n", "no front matter")]
+    #[case("---\nQuestion : Q?\n", "unclosed block")]
+    #[case("\n---\ncategory: A\n---\n", "block not on first line")]
+    #[case("", "empty file")]
+    fn test_split_without_front_matter(#[case] content: &str, #[case] description: &str) {
+        let (yaml, body) = split(content);
+
+        assert_eq!(yaml, None, "Failed for case: {}", description);
+        assert_eq!(body, content, "Failed for case: {}", description);
+    }
+
+    #[test]
+    fn test_split_accepts_dots_terminator() {
+        assert_eq!(split("---\ncategory: A\n...\n").0, Some("category: A\n"));
+    }
+
+    #[test]
+    fn test_parse_all_fields() {
+        let front_matter = parse("category: Python\nsubcategory: Pandas\ntags: [groupby, perf]\ndifficulty: Hard\nreverse: true\n");
+
+        assert_eq!(
+            front_matter.unwrap(),
+            FrontMatter {
+                category: Some("Python".to_string()),
+                subcategory: Some("Pandas".to_string()),
+                tags: vec!["groupby".to_string(), "perf".to_string()],
+                difficulty: Some(Difficulty::Hard),
+                reverse: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_block() {
+        assert_eq!(parse("\n").unwrap(), FrontMatter::default());
+    }
+
+    #[rstest]
+    #[case("categroy: Python\n", "unknown key")]
+    #[case("difficulty: extreme\n", "unknown difficulty")]
+    #[case("category: [unclosed\n", "invalid YAML")]
+    #[case("reverse: sometimes\n", "invalid reverse flag")]
+    fn test_parse_rejects(#[case] yaml: &str, #[case] description: &str) {
+        assert!(parse(yaml).is_err(), "Failed for case: {}", description);
+    }
+
+    #[rstest]
+    #[case("Q?\nTags: a, b\n", vec!["a", "b"], "Q?\n")]
+    #[case("Q?\ntags : A,, b , a", vec!["a", "b"], "Q?\n")]
+    #[case("Q?\nTags:\n", vec![], "Q?\n")]
+    #[case("Which Tags: here?", vec![], "Which Tags: here?")]
+    fn test_extract_tags(#[case] question_part: &str, #[case] expected: Vec<&str>, #[case] expected_rest: &str) {
+        assert_eq!(extract_tags(question_part), (expected.into_iter().map(String::from).collect(), expected_rest.to_string()));
+    }
+
+    #[rstest]
+    #[case("Q?\nDifficulty: hard\n", Some(Difficulty::Hard), "Q?\n")]
+    #[case("Q?\ndifficulty : Easy", Some(Difficulty::Easy), "Q?\n")]
+    #[case("Q?\nDifficulty: extreme\n", None, "Q?\n")]
+    #[case("What is the Difficulty: here?", None, "What is the Difficulty: here?")]
+    fn test_extract_difficulty(
+        #[case] question_part: &str,
+        #[case] expected: Option<Difficulty>,
+        #[case] expected_rest: &str,
+    ) {
+        assert_eq!(extract_difficulty(question_part), (expected, expected_rest.to_string()));
+    }
+
+    #[rstest]
+    #[case("Q?\nReverse: true\n", Some(true), "Q?\n")]
+    #[case("Q?\nreverse : False", Some(false), "Q?\n")]
+    #[case("Q?\nReverse: maybe\n", None, "Q?\n")]
+    #[case("Q?", None, "Q?")]
+    fn test_extract_reverse(#[case] question_part: &str, #[case] expected: Option<bool>, #[case] expected_rest: &str) {
+        assert_eq!(extract_reverse(question_part), (expected, expected_rest.to_string()));
+    }
+}
@@ -0,0 +1,581 @@
+// Rust guideline compliant 2025-01
+//! Web variants of image files.
+//!
+//! Images of `img/` are served as they are, along with resized copies written
+//! to the cache directory of the deck, with the same layout as `img/`: narrower
+//! variants for `srcset`, and a thumbnail. `img/maths/bayes.png` gets
+//! `cache/img/maths/bayes.png.480w.jpg`, `cache/img/maths/bayes.png.960w.jpg`...
+//! and `cache/img/maths/bayes.png.thumb.jpg`.
+//!
+//! Copies are JPEG, or lossless WebP for images with transparency. SVG and
+//! AVIF images are not resized, GIF images only get a thumbnail (variants
+//! would lose their animation).
+
+use super::links;
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use percent_encoding::{AsciiSet, utf8_percent_encode};
+use regex::Regex;
+use std::ffi::OsString;
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+/// Extensions of image files (lowercase).
+pub const IMAGE_EXTENSIONS: [&str; 7] = ["png", "webp", "jpg", "jpeg", "gif", "svg", "avif"];
+
+/// Widths of the resized variants, in pixels (only those narrower than the image).
+const VARIANT_WIDTHS: [u32; 3] = [480, 960, 1440];
+
+/// Width of thumbnails, in pixels.
+const THUMBNAIL_WIDTH: u32 = 160;
+
+/// JPEG quality of resized copies.
+const JPEG_QUALITY: u8 = 80;
+
+/// Displayed width of answer images, for browsers to pick a variant.
+const SIZES: &str = "(max-width: 600px) 100vw, 600px";
+
+/// Characters escaped in segments of image URLs: those of links, and commas,
+/// which separate `srcset` candidates.
+const URL_SEGMENT: &AsciiSet = &links::SEGMENT.add(b',');
+
+/// Root `<svg>` tag of an SVG file.
+static SVG_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<svg\b[^>]*>").unwrap());
+
+/// `width`, `height` and `viewBox` attributes of an SVG tag.
+static SVG_ATTRIBUTE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\s(width|height|viewBox)\s*=\s*["']([^"']*)["']"#).unwrap());
+
+/// Image file served at `url`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebFile {
+    pub url: String,
+    /// Width and height in pixels, if known.
+    pub size: Option<(u32, u32)>,
+}
+
+/// Image with its web variants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebImage {
+    pub original: WebFile,
+    /// Resized variants, narrowest first.
+    pub variants: Vec<WebFile>,
+    /// Thumbnail (the original itself, when it could not be resized).
+    pub thumbnail: WebFile,
+}
+
+impl WebImage {
+    /// Image served without variants, its thumbnail scaled down by the browser.
+    fn alone(original: WebFile) -> Self {
+        let thumbnail = WebFile {
+            url: original.url.clone(),
+            size: original.size.map(|size| scaled(size, THUMBNAIL_WIDTH.min(size.0))),
+        };
+        WebImage { original, variants: Vec::new(), thumbnail }
+    }
+
+    /// Returns the `<img>` tag of the image, with its dimensions and `srcset` when known.
+    pub fn img_tag(&self) -> String {
+        let mut tag = format!("<img src='{}'", self.original.url);
+        if let Some((width, height)) = self.original.size {
+            tag.push_str(&format!(" width='{}' height='{}'", width, height));
+            if !self.variants.is_empty() {
+                let srcset: Vec<String> = self
+                    .variants
+                    .iter()
+                    .chain([&self.original])
+                    .filter_map(|file| file.size.map(|(width, _)| format!("{} {}w", file.url, width)))
+                    .collect();
+                tag.push_str(&format!(" srcset='{}' sizes='{}'", srcset.join(", "), SIZES));
+            }
+        }
+        tag + " class='img-fluid'>"
+    }
+
+    /// Returns the `<img>` tag of the thumbnail.
+    pub fn thumbnail_tag(&self) -> String {
+        let (width, height) = self.thumbnail.size.unwrap_or((THUMBNAIL_WIDTH, 0));
+        let height = if height > 0 { format!(" height='{}'", height) } else { String::new() };
+        format!("<img src='{}' width='{}'{} class='img-thumbnail' loading='lazy'>", self.thumbnail.url, width, height)
+    }
+}
+
+/// Returns the cache directory of the images of `img_dir` (`static/<deck>/cache/img`
+/// for `static/<deck>/img`).
+pub fn cache_dir(img_dir: &Path) -> PathBuf {
+    img_dir.parent().unwrap_or(Path::new(".")).join("cache").join("img")
+}
+
+/// Returns the size of `size` scaled to `width`, keeping its aspect ratio.
+fn scaled((width, height): (u32, u32), target_width: u32) -> (u32, u32) {
+    let target_height = (u64::from(height) * u64::from(target_width) + u64::from(width) / 2) / u64::from(width.max(1));
+    (target_width, target_height.max(1) as u32)
+}
+
+/// Parses an SVG length in pixels (`600`, `600px`); other units are unknown.
+fn svg_length(value: &str) -> Option<u32> {
+    let value = value.trim().trim_end_matches("px");
+    value.parse::<f64>().ok().filter(|length| *length >= 1.0).map(|length| length.round() as u32)
+}
+
+/// Returns the size of an SVG image from its `width`/`height` attributes, or its `viewBox`.
+fn svg_size(content: &str) -> Option<(u32, u32)> {
+    let tag = SVG_TAG_REGEX.find(content)?.as_str();
+    let (mut width, mut height, mut view_box) = (None, None, None);
+    for caps in SVG_ATTRIBUTE_REGEX.captures_iter(tag) {
+        match &caps[1] {
+            "width" => width = svg_length(&caps[2]),
+            "height" => height = svg_length(&caps[2]),
+            _ => view_box = caps.get(2).map(|value| value.as_str()),
+        }
+    }
+
+    if let (Some(width), Some(height)) = (width, height) {
+        return Some((width, height));
+    }
+    let numbers: Vec<&str> = view_box?.split([' ', ',']).filter(|s| !s.is_empty()).collect();
+    match numbers[..] {
+        [_, _, width, height] => Some((svg_length(width)?, svg_length(height)?)),
+        _ => None,
+    }
+}
+
+/// Returns the size of an image file, if it can be read from its header.
+fn image_size(path: &Path) -> Option<(u32, u32)> {
+    let is_svg = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    let size = if is_svg {
+        fs::read_to_string(path).ok().and_then(|content| svg_size(&content))
+    } else {
+        imagesize::size(path).ok().map(|size| (size.width as u32, size.height as u32))
+    };
+    if size.is_none() {
+        tracing::warn!("Unknown size of image {:?}, serving it without variants", path);
+    }
+    size
+}
+
+/// Returns `path` with `suffix` appended (`bayes.png` -> `bayes.png.thumb.jpg`).
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(".");
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Returns true if `copy` exists and is newer than `source`.
+fn is_fresh(copy: &Path, source: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    matches!((modified(copy), modified(source)), (Some(copy), Some(source)) if copy >= source)
+}
+
+/// Writes `image` resized to `size` at `path`, as lossless WebP or JPEG.
+fn write_resized(image: &DynamicImage, size: (u32, u32), lossless: bool, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create cache directory {:?}", parent))?;
+    }
+    let resized = image.resize_exact(size.0, size.1, FilterType::CatmullRom);
+    let writer = BufWriter::new(fs::File::create(path).with_context(|| format!("Failed to create {:?}", path))?);
+
+    let written = if lossless {
+        DynamicImage::ImageRgba8(resized.to_rgba8()).write_with_encoder(WebPEncoder::new_lossless(writer))
+    } else {
+        DynamicImage::ImageRgb8(resized.to_rgb8()).write_with_encoder(JpegEncoder::new_with_quality(writer, JPEG_QUALITY))
+    };
+    written.with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Returns true if some pixels of `image` are not opaque.
+fn is_transparent(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel.0[3] < u8::MAX)
+}
+
+/// Writes the resized copies of an image missing from the cache.
+///
+/// `(suffix, width)` are the copies to make; those newer than the image are
+/// kept. Returns the path of each copy, None for copies heavier than the image.
+fn resized_copies(
+    path: &Path,
+    cache_path: &Path,
+    size: (u32, u32),
+    copies: &[(String, u32)],
+) -> Result<Vec<Option<PathBuf>>> {
+    let image_len = fs::metadata(path)?.len();
+    let mut image = None;
+    let mut paths = Vec::new();
+
+    for (suffix, width) in copies {
+        let fresh = ["jpg", "webp"]
+            .iter()
+            .map(|extension| with_suffix(cache_path, &format!("{}.{}", suffix, extension)))
+            .find(|copy_path| is_fresh(copy_path, path));
+        let copy_path = match fresh {
+            Some(copy_path) => copy_path,
+            None => {
+                // Decoded once, only if some copy is missing
+                let image = match image {
+                    Some(ref image) => image,
+                    None => image.insert(ImageReader::open(path)?.with_guessed_format()?.decode()?),
+                };
+                let lossless = is_transparent(image);
+                let copy_path = with_suffix(cache_path, &format!("{}.{}", suffix, if lossless { "webp" } else { "jpg" }));
+                write_resized(image, scaled(size, *width), lossless, &copy_path)?;
+                copy_path
+            }
+        };
+        paths.push((fs::metadata(&copy_path)?.len() < image_len).then_some(copy_path));
+    }
+    Ok(paths)
+}
+
+/// Returns the `/static/` URL of `path`, a `/`-separated path in the static directory.
+fn static_url(path: &str) -> String {
+    let segments: Vec<String> = path.split('/').map(|s| utf8_percent_encode(s, URL_SEGMENT).to_string()).collect();
+    format!("/static/{}", segments.join("/"))
+}
+
+/// Returns an image with its web variants, writing those missing to the cache.
+///
+/// `relative_path` is the path of the image in `img_dir`, served under
+/// `/static/<deck_id>/`. Images that cannot be resized are served alone.
+pub fn web_image(path: &Path, img_dir: &Path, deck_id: &str, relative_path: &str) -> WebImage {
+    let original = WebFile { url: static_url(&format!("{}/img/{}", deck_id, relative_path)), size: image_size(path) };
+    let Some(size) = original.size else {
+        return WebImage::alone(original);
+    };
+
+    let format = ImageFormat::from_path(path).ok();
+    if !matches!(format, Some(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif)) {
+        return WebImage::alone(original);
+    }
+
+    // Narrower variants (not for GIF), then the thumbnail
+    let mut copies: Vec<(String, u32)> = VARIANT_WIDTHS
+        .iter()
+        .filter(|width| **width < size.0 && format != Some(ImageFormat::Gif))
+        .map(|width| (format!("{}w", width), *width))
+        .collect();
+    if size.0 > THUMBNAIL_WIDTH {
+        copies.push(("thumb".to_string(), THUMBNAIL_WIDTH));
+    }
+    if copies.is_empty() {
+        return WebImage::alone(original);
+    }
+
+    let cache_path = cache_dir(img_dir).join(relative_path);
+    let paths = match resized_copies(path, &cache_path, size, &copies) {
+        Ok(paths) => paths,
+        Err(e) => {
+            tracing::warn!("Failed to resize image {:?}, serving it without variants: {:#}", path, e);
+            return WebImage::alone(original);
+        }
+    };
+
+    let mut files: Vec<Option<WebFile>> = copies
+        .iter()
+        .zip(paths)
+        .map(|((_, width), copy_path)| {
+            let file_name = copy_path?.file_name()?.to_string_lossy().into_owned();
+            let copy_path = match relative_path.rsplit_once('/') {
+                Some((dir, _)) => format!("{}/cache/img/{}/{}", deck_id, dir, file_name),
+                None => format!("{}/cache/img/{}", deck_id, file_name),
+            };
+            Some(WebFile { url: static_url(&copy_path), size: Some(scaled(size, *width)) })
+        })
+        .collect();
+
+    // Copies heavier than the image are left out
+    let thumbnail = match size.0 > THUMBNAIL_WIDTH {
+        true => files.pop().flatten(),
+        false => None,
+    };
+    let alone = WebImage::alone(original);
+    WebImage {
+        variants: files.into_iter().flatten().collect(),
+        thumbnail: thumbnail.unwrap_or(alone.thumbnail),
+        original: alone.original,
+    }
+}
+
+/// Deletes the cached copies of images no longer in `img_dir`.
+///
+/// Returns the number of files deleted.
+///
+/// # Errors
+/// Returns error if a stale copy cannot be deleted.
+pub fn prune(img_dir: &Path) -> Result<usize> {
+    let cache_dir = cache_dir(img_dir);
+    let mut removed = 0;
+
+    for entry in WalkDir::new(&cache_dir).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        let Ok(relative) = entry.path().strip_prefix(&cache_dir) else {
+            continue;
+        };
+        // bayes.png.480w.jpg is a copy of bayes.png
+        let source = relative.with_extension("").with_extension("");
+        if !img_dir.join(&source).is_file() {
+            fs::remove_file(entry.path()).with_context(|| format!("Failed to delete {:?}", entry.path()))?;
+            removed += 1;
+        }
+    }
+
+    if removed > 0 {
+        tracing::info!("Deleted {} cached copies of removed images", removed);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    /// Creates `static/deck/img` in a temporary directory.
+    fn setup_img_dir() -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let img_dir = temp_dir.path().join("static").join("deck").join("img");
+        fs::create_dir_all(&img_dir).unwrap();
+        (temp_dir, img_dir)
+    }
+
+    /// Writes a PNG of noise (which does not compress, unlike plain colors).
+    fn write_png(path: &Path, width: u32, height: u32, alpha: bool) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let noise = |x: u32, y: u32| (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)).to_le_bytes();
+        if alpha {
+            RgbaImage::from_fn(width, height, |x, y| {
+                let [r, g, b, _] = noise(x, y);
+                Rgba([r, g, b, 128])
+            })
+            .save(path)
+            .unwrap();
+        } else {
+            RgbImage::from_fn(width, height, |x, y| {
+                let [r, g, b, _] = noise(x, y);
+                Rgb([r, g, b])
+            })
+            .save(path)
+            .unwrap();
+        }
+    }
+
+    #[rstest]
+    #[case((1000, 500), 480, (480, 240))]
+    #[case((1000, 333), 160, (160, 53))]
+    #[case((3, 1000), 1, (1, 333))]
+    #[case((1000, 1), 160, (160, 1))]
+    fn test_scaled(#[case] size: (u32, u32), #[case] width: u32, #[case] expected: (u32, u32)) {
+        assert_eq!(scaled(size, width), expected);
+    }
+
+    #[rstest]
+    #[case(r#"<svg xmlns="http://www.w3.org/2000/svg" width="600" height="400">"#, Some((600, 400)))]
+    #[case(r#"<svg width='600px' height='400.4px'>"#, Some((600, 400)))]
+    #[case(r#"<svg viewBox="0 0 300 150">"#, Some((300, 150)))]
+    #[case(r#"<svg width="100%" height="100%" viewBox="0,0,300,150">"#, Some((300, 150)))]
+    #[case(r#"<svg width="10cm" height="5cm">"#, None)]
+    #[case("<?xml version=\"1.0\"?>\n<svg\n  width=\"64\"\n  height=\"32\">", Some((64, 32)))]
+    #[case("<html></html>", None)]
+    fn test_svg_size(#[case] content: &str, #[case] expected: Option<(u32, u32)>) {
+        assert_eq!(svg_size(content), expected);
+    }
+
+    #[test]
+    fn test_web_image_writes_variants_and_thumbnail() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("maths").join("bayes.png");
+        write_png(&path, 1000, 500, false);
+
+        let image = web_image(&path, &img_dir, "deck", "maths/bayes.png");
+
+        assert_eq!(image.original.size, Some((1000, 500)));
+        assert_eq!(
+            image.variants,
+            vec![
+                WebFile { url: "/static/deck/cache/img/maths/bayes.png.480w.jpg".to_string(), size: Some((480, 240)) },
+                WebFile { url: "/static/deck/cache/img/maths/bayes.png.960w.jpg".to_string(), size: Some((960, 480)) },
+            ]
+        );
+        assert_eq!(image.thumbnail.url, "/static/deck/cache/img/maths/bayes.png.thumb.jpg");
+
+        let cache = cache_dir(&img_dir).join("maths");
+        assert_eq!(image::image_dimensions(cache.join("bayes.png.480w.jpg")).unwrap(), (480, 240));
+        assert_eq!(image::image_dimensions(cache.join("bayes.png.thumb.jpg")).unwrap(), (160, 80));
+    }
+
+    #[test]
+    fn test_web_image_keeps_transparency() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("logo.png");
+        write_png(&path, 600, 300, true);
+
+        let image = web_image(&path, &img_dir, "deck", "logo.png");
+
+        assert_eq!(image.variants[0].url, "/static/deck/cache/img/logo.png.480w.webp");
+        let copy = image::open(cache_dir(&img_dir).join("logo.png.480w.webp")).unwrap();
+        assert!(copy.color().has_alpha());
+    }
+
+    #[test]
+    fn test_web_image_encodes_urls() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("maths 101").join("my image, v2.webp");
+        write_png(&path, 600, 300, false);
+
+        let image = web_image(&path, &img_dir, "deck", "maths 101/my image, v2.webp");
+
+        assert_eq!(image.original.url, "/static/deck/img/maths%20101/my%20image%2C%20v2.webp");
+        assert_eq!(image.variants[0].url, "/static/deck/cache/img/maths%20101/my%20image%2C%20v2.webp.480w.jpg");
+        assert_eq!(image.thumbnail.url, "/static/deck/cache/img/maths%20101/my%20image%2C%20v2.webp.thumb.jpg");
+        assert!(image.img_tag().contains(
+            "srcset='/static/deck/cache/img/maths%20101/my%20image%2C%20v2.webp.480w.jpg 480w, \
+             /static/deck/img/maths%20101/my%20image%2C%20v2.webp 600w'"
+        ));
+    }
+
+    #[test]
+    fn test_web_image_opaque_alpha_is_jpeg() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("chart.png");
+        fs::create_dir_all(&img_dir).unwrap();
+        RgbaImage::from_fn(600, 300, |x, y| Rgba([x as u8, y as u8, (x ^ y) as u8, 255])).save(&path).unwrap();
+
+        let image = web_image(&path, &img_dir, "deck", "chart.png");
+
+        assert_eq!(image.thumbnail.url, "/static/deck/cache/img/chart.png.thumb.jpg");
+    }
+
+    #[test]
+    fn test_web_image_leaves_out_heavier_copies() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("bayes.png");
+        write_png(&path, 600, 300, false);
+        web_image(&path, &img_dir, "deck", "bayes.png");
+
+        // Copy heavier than the image (a plain PNG may compress better than JPEG)
+        let heavy = vec![0; fs::metadata(&path).unwrap().len() as usize + 1];
+        fs::write(cache_dir(&img_dir).join("bayes.png.480w.jpg"), heavy).unwrap();
+        let image = web_image(&path, &img_dir, "deck", "bayes.png");
+
+        assert!(image.variants.is_empty());
+        assert_eq!(image.thumbnail.url, "/static/deck/cache/img/bayes.png.thumb.jpg");
+    }
+
+    #[test]
+    fn test_web_image_small_image_is_served_alone() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("icon.png");
+        write_png(&path, 100, 50, false);
+
+        let image = web_image(&path, &img_dir, "deck", "icon.png");
+
+        assert!(image.variants.is_empty());
+        assert_eq!(image.thumbnail, WebFile { url: "/static/deck/img/icon.png".to_string(), size: Some((100, 50)) });
+        assert!(!cache_dir(&img_dir).exists());
+    }
+
+    #[rstest]
+    #[case::svg("chart.svg", r#"<svg width="800" height="400"></svg>"#, Some((800, 400)), Some((160, 80)))]
+    #[case::unreadable("broken.png", "not an image", None, None)]
+    fn test_web_image_not_resized(
+        #[case] file_name: &str,
+        #[case] content: &str,
+        #[case] size: Option<(u32, u32)>,
+        #[case] thumbnail_size: Option<(u32, u32)>,
+    ) {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join(file_name);
+        fs::write(&path, content).unwrap();
+
+        let image = web_image(&path, &img_dir, "deck", file_name);
+
+        assert_eq!(image.original.size, size);
+        assert!(image.variants.is_empty());
+        assert_eq!(image.thumbnail.url, format!("/static/deck/img/{}", file_name));
+        assert_eq!(image.thumbnail.size, thumbnail_size);
+    }
+
+    #[test]
+    fn test_web_image_gif_only_gets_thumbnail() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("anim.gif");
+        RgbaImage::from_pixel(800, 400, Rgba([10, 20, 30, 255])).save(&path).unwrap();
+
+        let image = web_image(&path, &img_dir, "deck", "anim.gif");
+
+        assert!(image.variants.is_empty());
+        assert_eq!(image.thumbnail.size, Some((160, 80)));
+    }
+
+    #[test]
+    fn test_web_image_keeps_fresh_copies() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let path = img_dir.join("bayes.png");
+        write_png(&path, 600, 300, false);
+        web_image(&path, &img_dir, "deck", "bayes.png");
+
+        // A fresh copy is not written again
+        let thumb = cache_dir(&img_dir).join("bayes.png.thumb.jpg");
+        fs::write(&thumb, b"kept").unwrap();
+        web_image(&path, &img_dir, "deck", "bayes.png");
+
+        assert_eq!(fs::read(&thumb).unwrap(), b"kept");
+    }
+
+    #[test]
+    fn test_img_tag() {
+        let image = WebImage {
+            original: WebFile { url: "/static/deck/img/a.png".to_string(), size: Some((1000, 500)) },
+            variants: vec![WebFile { url: "/static/deck/cache/img/a.png.480w.jpg".to_string(), size: Some((480, 240)) }],
+            thumbnail: WebFile { url: "/static/deck/cache/img/a.png.thumb.jpg".to_string(), size: Some((160, 80)) },
+        };
+
+        assert_eq!(
+            image.img_tag(),
+            "<img src='/static/deck/img/a.png' width='1000' height='500' \
+             srcset='/static/deck/cache/img/a.png.480w.jpg 480w, /static/deck/img/a.png 1000w' \
+             sizes='(max-width: 600px) 100vw, 600px' class='img-fluid'>"
+        );
+        assert_eq!(
+            image.thumbnail_tag(),
+            "<img src='/static/deck/cache/img/a.png.thumb.jpg' width='160' height='80' class='img-thumbnail' loading='lazy'>"
+        );
+    }
+
+    #[test]
+    fn test_img_tag_unknown_size() {
+        let image = WebImage::alone(WebFile { url: "/static/deck/img/a.png".to_string(), size: None });
+
+        assert_eq!(image.img_tag(), "<img src='/static/deck/img/a.png' class='img-fluid'>");
+        assert_eq!(
+            image.thumbnail_tag(),
+            "<img src='/static/deck/img/a.png' width='160' class='img-thumbnail' loading='lazy'>"
+        );
+    }
+
+    #[test]
+    fn test_prune_deletes_copies_of_removed_images() {
+        let (_temp_dir, img_dir) = setup_img_dir();
+        let kept = img_dir.join("maths").join("kept.png");
+        let removed = img_dir.join("maths").join("removed.png");
+        write_png(&kept, 600, 300, false);
+        write_png(&removed, 600, 300, false);
+        web_image(&kept, &img_dir, "deck", "maths/kept.png");
+        web_image(&removed, &img_dir, "deck", "maths/removed.png");
+        fs::remove_file(&removed).unwrap();
+
+        assert_eq!(prune(&img_dir).unwrap(), 2);
+
+        let cache = cache_dir(&img_dir).join("maths");
+        assert!(cache.join("kept.png.480w.jpg").exists());
+        assert!(!cache.join("removed.png.480w.jpg").exists());
+        assert_eq!(prune(&img_dir).unwrap(), 0);
+    }
+}
@@ -2,55 +2,84 @@ use anyhow::Result;
 use std::path::Path;
 use walkdir::WalkDir;
 
+use super::card_key;
+use super::front_matter::Difficulty;
+use super::image_cache::{self, IMAGE_EXTENSIONS};
+use super::links::LinkResolver;
+use super::markdown::{markdown_to_html, markdown_to_text};
+use super::sidecar;
+use super::sync::{self, SyncReport};
 use crate::db::connection::DbPool;
+use crate::db::models::{CardKind, NewFlashcard};
 use crate::db::queries;
 
-pub fn load_images(pool: &DbPool, png_dir: &str) -> Result<()> {
+/// Prefix of image files in `content_files` and `flashcards.source_path`.
+const SOURCE_PREFIX: &str = "img/";
+
+/// Loads image flashcards from `png_dir`, incrementally.
+///
+/// Only images added or changed since the last load (or whose sidecar changed,
+/// see [`sidecar`]) are processed; cards of removed images are deleted
+/// (see [`sync::sync_files`]), with their web variants (see [`image_cache`]).
+///
+/// # Errors
+/// Returns error if database query fails or stale web variants cannot be deleted.
+pub fn load_images(pool: &DbPool, png_dir: &str) -> Result<SyncReport> {
     tracing::info!("Loading image flashcards from {}", png_dir);
 
-    let mut count = 0;
-
-    // Walk through all .png and .webp files recursively
-    for entry in WalkDir::new(png_dir)
+    // All image files recursively (see IMAGE_EXTENSIONS)
+    let files = WalkDir::new(png_dir)
         .follow_links(true)
+        .sort_by_file_name()
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| {
             e.path()
                 .extension()
                 .and_then(|ext| ext.to_str())
-                .map(|ext| {
-                    let ext_lower = ext.to_ascii_lowercase();
-                    ext_lower == "png" || ext_lower == "webp"
-                })
+                .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
                 .unwrap_or(false)
         })
-    {
-        let path = entry.path();
+        .map(|e| e.into_path())
+        .collect();
+
+    let report = sync::sync_files(pool, png_dir, SOURCE_PREFIX, files, sidecar::find, |path| {
         tracing::debug!("Processing image file: {:?}", path);
+        process_image_file(pool, path, png_dir).map(|()| 1)
+    })?;
+    image_cache::prune(Path::new(png_dir))?;
+
+    tracing::info!(
+        "Image files: {} added, {} updated, {} removed, {} unchanged",
+        report.added,
+        report.updated,
+        report.removed,
+        report.unchanged
+    );
+    Ok(report)
+}
 
-        match process_image_file(pool, path, png_dir) {
-            Ok(()) => {
-                count += 1;
-                tracing::debug!("Loaded image flashcard from {:?}", path);
-            }
-            Err(e) => {
-                tracing::warn!("Failed to process {:?}: {}", path, e);
-            }
-        }
-    }
+/// Returns the category and subcategory given by the directories of an image.
+///
+/// `relative_path` is relative to the image directory: `maths/probability/bayes.webp`
+/// is in category `maths`, subcategory `probability`. Deeper directories are ignored.
+fn directory_categories(relative_path: &str) -> (Option<String>, Option<String>) {
+    let mut dirs = relative_path.split('/').rev().skip(1).collect::<Vec<_>>().into_iter().rev();
+    (dirs.next().map(String::from), dirs.next().map(String::from))
+}
 
-    tracing::info!("Loaded {} image flashcards", count);
-    Ok(())
+/// Returns the searchable words of an image file name (`bayes_theorem.webp` -> `bayes theorem`).
+fn file_stem_words(path: &Path) -> String {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let words = stem.split(['_', '-', '.', ' ']).filter(|word| !word.is_empty()).collect::<Vec<_>>();
+    // Never empty: image cards without text are taken for cards of older versions
+    if words.is_empty() { stem.into_owned() } else { words.join(" ") }
 }
 
 fn process_image_file(pool: &DbPool, path: &Path, base_dir: &str) -> Result<()> {
     // Convert absolute path to relative path from base_dir
-    let relative_path = path
-        .strip_prefix(base_dir)
-        .unwrap_or(path)
-        .to_string_lossy()
-        .replace('\\', "/");
+    let relative_path = sync::relative_path(path, base_dir);
+    let source_path = format!("{}{}", SOURCE_PREFIX, relative_path);
 
     // Extract deck_id from base_dir path (e.g., "./static/deck/img" -> "deck")
     let deck_id = Path::new(base_dir)
@@ -59,17 +88,66 @@ fn process_image_file(pool: &DbPool, path: &Path, base_dir: &str) -> Result<()>
         .and_then(|n| n.to_str())
         .unwrap_or("deck");
 
-    // Question is empty (just the header as HTML)
-    let question_html = "<h3>Question:</h3>\n".to_string();
-
-    // Answer contains the image with deck-aware path, Bootstrap class, centered
-    let answer_html = format!(
-        "<h3>Answer:</h3>\n<p align=\"center\"><img src='/static/{}/img/{}' class='img-fluid'></p>",
-        deck_id, relative_path
-    );
-
-    // Insert into database - Images: category and subcategory = None
-    queries::insert_flashcard(pool, None, None, &question_html, &answer_html)?;
+    // Invalid sidecars are reported, the image is loaded without them
+    let sidecar_path = sidecar::find(path);
+    let metadata = sidecar_path
+        .as_deref()
+        .map(|sidecar_path| {
+            sidecar::read(sidecar_path).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring sidecar: {:#}", e);
+                Default::default()
+            })
+        })
+        .unwrap_or_default();
+
+    // Sidecar category wins, else the directories of the image
+    let (dir_category, dir_subcategory) = directory_categories(&relative_path);
+    let subcategory = match metadata.category {
+        Some(_) => metadata.subcategory,
+        None => metadata.subcategory.or(dir_subcategory),
+    };
+    let category = metadata.category.or(dir_category);
+
+    // Question from the sidecar, if any (just the header otherwise), links relative to the sidecar
+    let links = LinkResolver::for_deck_file(Path::new(base_dir), sidecar_path.as_deref().unwrap_or(path));
+    let (question_html, question_text) = if metadata.question_md.is_empty() {
+        ("<h3>Question:</h3>\n".to_string(), String::new())
+    } else {
+        (
+            markdown_to_html(&format!("### Question:\n{}", metadata.question_md), Some(&links))?,
+            markdown_to_text(&metadata.question_md),
+        )
+    };
+
+    // Answer contains the image with deck-aware path, its web variants, Bootstrap class, centered
+    let image = image_cache::web_image(path, Path::new(base_dir), deck_id, &relative_path);
+    let answer_html = format!("<h3>Answer:</h3>\n<p align=\"center\">{}</p>", image.img_tag());
+
+    // The image path is the identity of image cards, whatever their question
+    let card_key = card_key::hashed_key("", &source_path);
+
+    queries::insert_flashcard(
+        pool,
+        &NewFlashcard {
+            card_key: &card_key,
+            source_path: &source_path,
+            category: category.as_deref(),
+            subcategory: subcategory.as_deref(),
+            difficulty: metadata.difficulty.map(Difficulty::as_str),
+            kind: CardKind::Image,
+            sibling_key: None,
+            expected_answer: None,
+            tags: &metadata.tags,
+            hints_html: &[],
+            distractors_html: &[],
+            question_html: &question_html,
+            answer_html: &answer_html,
+            question_text: &question_text,
+            // The file name stands for the image content
+            answer_text: &file_stem_words(path),
+            thumbnail_html: Some(&image.thumbnail_tag()),
+        },
+    )?;
 
     Ok(())
 }
@@ -85,45 +163,38 @@ mod tests {
     fn setup_test_db() -> DbPool {
         let manager = r2d2_sqlite::SqliteConnectionManager::memory();
         let pool = r2d2::Pool::builder().max_size(1).build(manager).unwrap();
-
-        let conn = pool.get().unwrap();
-        conn.execute(
-            "CREATE TABLE flashcards (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                category TEXT,
-                subcategory TEXT,
-                question_html TEXT NOT NULL,
-                answer_html TEXT NOT NULL
-            )",
-            [],
-        )
-        .unwrap();
-
+        crate::db::init_database(&pool).unwrap();
         pool
     }
 
     // ========== Tests for process_image_file ==========
 
     #[rstest]
-    #[case("./static/deck/img", "test.png", "deck", "/static/deck/img/test.png")]
-    #[case("./static/rust/img", "test.webp", "rust", "/static/rust/img/test.webp")]
+    #[case("./static/deck/img", "test.png", "deck", "/static/deck/img/test.png", None, None)]
+    #[case("./static/rust/img", "test.webp", "rust", "/static/rust/img/test.webp", None, None)]
     #[case(
         "./static/py_deck/img",
         "subdir/image.png",
         "py_deck",
-        "/static/py_deck/img/subdir/image.png"
+        "/static/py_deck/img/subdir/image.png",
+        Some("subdir"),
+        None
     )]
     #[case(
         "./static/test_42/img",
         "foo/bar/baz.webp",
         "test_42",
-        "/static/test_42/img/foo/bar/baz.webp"
+        "/static/test_42/img/foo/bar/baz.webp",
+        Some("foo"),
+        Some("bar")
     )]
     fn test_process_image_file_path_generation(
         #[case] base_dir: &str,
         #[case] relative_file: &str,
         #[case] expected_deck_id: &str,
         #[case] expected_img_path: &str,
+        #[case] expected_category: Option<&str>,
+        #[case] expected_subcategory: Option<&str>,
     ) {
         let pool = setup_test_db();
         let temp_dir = TempDir::new().unwrap();
@@ -142,17 +213,19 @@ mod tests {
 
         // Verify database insertion
         let conn = pool.get().unwrap();
-        let (cat, subcat, q_html, a_html): (Option<String>, Option<String>, String, String) = conn
+        let (kind, cat, subcat, q_html, a_html): (String, Option<String>, Option<String>, String, String) = conn
             .query_row(
-                "SELECT category, subcategory, question_html, answer_html FROM flashcards WHERE id = 1",
+                "SELECT kind, category, subcategory, question_html, answer_html FROM flashcards WHERE id = 1",
                 [],
-                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
             )
             .unwrap();
 
-        // Images have no category/subcategory
-        assert_eq!(cat, None);
-        assert_eq!(subcat, None);
+        assert_eq!(kind, "image");
+
+        // Without sidecar, directories are category/subcategory
+        assert_eq!(cat.as_deref(), expected_category);
+        assert_eq!(subcat.as_deref(), expected_subcategory);
 
         // Question is just the header
         assert_eq!(q_html, "<h3>Question:</h3>\n");
@@ -294,6 +367,134 @@ mod tests {
         assert!(a_html.contains("</p>"));
     }
 
+    #[rstest]
+    #[case("bayes.webp", None, None)]
+    #[case("maths/bayes.webp", Some("maths"), None)]
+    #[case("maths/probability/bayes.webp", Some("maths"), Some("probability"))]
+    #[case("maths/probability/extra/bayes.webp", Some("maths"), Some("probability"))]
+    fn test_directory_categories(
+        #[case] relative_path: &str,
+        #[case] category: Option<&str>,
+        #[case] subcategory: Option<&str>,
+    ) {
+        let (cat, subcat) = directory_categories(relative_path);
+
+        assert_eq!(cat.as_deref(), category);
+        assert_eq!(subcat.as_deref(), subcategory);
+    }
+
+    #[rstest]
+    #[case("bayes_theorem.webp", "bayes theorem")]
+    #[case("big-o.notation.png", "big o notation")]
+    #[case("diagram.png", "diagram")]
+    #[case("__.png", "__")]
+    fn test_file_stem_words(#[case] file_name: &str, #[case] expected: &str) {
+        assert_eq!(file_stem_words(Path::new(file_name)), expected);
+    }
+
+    /// Loads one image (and its sidecar, if any) from `maths/probability`, returns its
+    /// category, subcategory, question html, question text, answer text and tags.
+    fn load_image_with_sidecar(
+        sidecar: Option<(&str, &str)>,
+    ) -> (Option<String>, Option<String>, String, String, String, Vec<String>) {
+        let pool = setup_test_db();
+        let temp_dir = TempDir::new().unwrap();
+        let img_dir = temp_dir.path().join("static").join("deck").join("img");
+        let dir = img_dir.join("maths").join("probability");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("bayes_theorem.webp"), b"fake").unwrap();
+        if let Some((file_name, content)) = sidecar {
+            fs::write(dir.join(file_name), content).unwrap();
+        }
+
+        load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let (category, subcategory, q_html, q_text, a_text, card_key) = conn
+            .query_row(
+                "SELECT category, subcategory, question_html, question_text, answer_text, card_key FROM flashcards",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get::<_, String>(5)?)),
+            )
+            .unwrap();
+        drop(conn);
+        let tags = queries::get_card_tags(&pool, &card_key).unwrap();
+        (category, subcategory, q_html, q_text, a_text, tags)
+    }
+
+    #[rstest]
+    #[case::markdown(
+        "bayes_theorem.md",
+        "---\ncategory: Stats\nsubcategory: Bayes\ntags: [probability]\n---\nWhat does **Bayes' theorem** state?\n"
+    )]
+    #[case::toml(
+        "bayes_theorem.toml",
+        "question = \"What does **Bayes' theorem** state?\"\ncategory = \"Stats\"\nsubcategory = \"Bayes\"\ntags = [\"probability\"]\n"
+    )]
+    fn test_load_images_reads_sidecar(#[case] file_name: &str, #[case] content: &str) {
+        let (category, subcategory, q_html, q_text, a_text, tags) =
+            load_image_with_sidecar(Some((file_name, content)));
+
+        assert_eq!(category.as_deref(), Some("Stats"));
+        assert_eq!(subcategory.as_deref(), Some("Bayes"));
+        assert!(q_html.starts_with("<h3>Question:</h3>"));
+        assert!(q_html.contains("<strong>Bayes' theorem</strong>"));
+        assert_eq!(q_text, "What does Bayes' theorem state?");
+        assert_eq!(a_text, "bayes theorem");
+        assert_eq!(tags, vec!["probability"]);
+    }
+
+    #[rstest]
+    #[case::no_sidecar(None, Some("maths"), Some("probability"))]
+    #[case::subcategory_only(Some(("bayes_theorem.md", "---\nsubcategory: Bayes\n---\n")), Some("maths"), Some("Bayes"))]
+    #[case::category_only(Some(("bayes_theorem.toml", "category = \"Stats\"")), Some("Stats"), None)]
+    #[case::invalid_sidecar(Some(("bayes_theorem.toml", "categroy = \"Stats\"")), Some("maths"), Some("probability"))]
+    fn test_load_images_directory_categories(
+        #[case] sidecar: Option<(&str, &str)>,
+        #[case] category: Option<&str>,
+        #[case] subcategory: Option<&str>,
+    ) {
+        let (cat, subcat, q_html, _, _, _) = load_image_with_sidecar(sidecar);
+
+        assert_eq!(cat.as_deref(), category);
+        assert_eq!(subcat.as_deref(), subcategory);
+        assert_eq!(q_html, "<h3>Question:</h3>\n");
+    }
+
+    #[test]
+    fn test_load_images_finds_image_by_file_name() {
+        let pool = setup_test_db();
+        let temp_dir = TempDir::new().unwrap();
+        let img_dir = temp_dir.path().join("img");
+        fs::create_dir_all(&img_dir).unwrap();
+        fs::write(img_dir.join("bayes_theorem.webp"), b"fake").unwrap();
+
+        load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+        queries::populate_fts_table(&pool).unwrap();
+
+        let hits = queries::search_flashcards(&pool, "\"bayes\"", 10, 0).unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[test]
+    fn test_load_images_reloads_image_when_sidecar_changes() {
+        let pool = setup_test_db();
+        let temp_dir = TempDir::new().unwrap();
+        let img_dir = temp_dir.path().join("img");
+        fs::create_dir_all(&img_dir).unwrap();
+        fs::write(img_dir.join("bayes.webp"), b"fake").unwrap();
+        load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+
+        fs::write(img_dir.join("bayes.toml"), "category = \"Stats\"").unwrap();
+        let report = load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+
+        assert_eq!((report.added, report.updated, report.removed), (0, 1, 0));
+        let conn = pool.get().unwrap();
+        let category: Option<String> =
+            conn.query_row("SELECT category FROM flashcards", [], |row| row.get(0)).unwrap();
+        assert_eq!(category.as_deref(), Some("Stats"));
+    }
+
     // ========== Tests for load_images ==========
 
     #[test]
@@ -365,6 +566,64 @@ mod tests {
         assert_eq!(count, 4);
     }
 
+    #[test]
+    fn test_load_images_all_formats() {
+        let pool = setup_test_db();
+        let temp_dir = TempDir::new().unwrap();
+
+        let img_dir = temp_dir.path().join("img");
+        fs::create_dir_all(&img_dir).unwrap();
+
+        for file_name in ["a.png", "b.webp", "c.jpg", "d.JPEG", "e.gif", "f.svg", "g.avif"] {
+            fs::write(img_dir.join(file_name), b"fake").unwrap();
+        }
+
+        let report = load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+
+        assert_eq!(report.added, 7);
+    }
+
+    #[test]
+    fn test_load_images_answer_has_dimensions_and_thumbnail() {
+        let pool = setup_test_db();
+        let temp_dir = TempDir::new().unwrap();
+        let img_dir = temp_dir.path().join("static").join("deck").join("img");
+        fs::create_dir_all(&img_dir).unwrap();
+        // Noise, so that resized copies are lighter than the image
+        let noise = |x: u32, y: u32| {
+            let [r, g, b, _] = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)).to_le_bytes();
+            image::Rgb([r, g, b])
+        };
+        image::RgbImage::from_fn(1000, 500, noise).save(img_dir.join("screenshot.png")).unwrap();
+
+        load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let (a_html, thumbnail_html): (String, String) = conn
+            .query_row("SELECT answer_html, thumbnail_html FROM flashcards", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert!(a_html.contains("<img src='/static/deck/img/screenshot.png' width='1000' height='500' srcset="));
+        assert!(a_html.contains("/static/deck/cache/img/screenshot.png.480w.jpg 480w"));
+        assert!(thumbnail_html.contains("src='/static/deck/cache/img/screenshot.png.thumb.jpg' width='160' height='80'"));
+    }
+
+    #[test]
+    fn test_load_images_deletes_variants_of_removed_images() {
+        let pool = setup_test_db();
+        let temp_dir = TempDir::new().unwrap();
+        let img_dir = temp_dir.path().join("static").join("deck").join("img");
+        fs::create_dir_all(&img_dir).unwrap();
+        image::RgbImage::new(600, 300).save(img_dir.join("screenshot.png")).unwrap();
+        load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+        let thumbnail = image_cache::cache_dir(&img_dir).join("screenshot.png.thumb.jpg");
+        assert!(thumbnail.exists());
+
+        fs::remove_file(img_dir.join("screenshot.png")).unwrap();
+        load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+
+        assert!(!thumbnail.exists());
+    }
+
     #[test]
     fn test_load_images_skips_non_image_files() {
         let pool = setup_test_db();
@@ -376,7 +635,7 @@ mod tests {
         // Create valid and invalid files
         fs::write(img_dir.join("valid.png"), b"fake").unwrap();
         fs::write(img_dir.join("readme.txt"), b"text").unwrap();
-        fs::write(img_dir.join("image.jpg"), b"jpeg").unwrap(); // Not PNG/WEBP
+        fs::write(img_dir.join("image.bmp"), b"bitmap").unwrap(); // Not a web image format
 
         load_images(&pool, img_dir.to_str().unwrap()).unwrap();
 
@@ -0,0 +1,245 @@
+// Rust guideline compliant 2025-01
+//! Links of markdown cards to images and files.
+//!
+//! Authors reference files relative to the markdown file, the way editors
+//! preview them (`![](assets/kitten.png)`, `../img/chart.webp`). Cards are
+//! served from other URLs (`/practice`, `/card/...`), so these links are
+//! resolved against the directory of the file and mapped to the `/static/`
+//! URL space. Paths from the project root (`static/<deck>/...`) and `/static/`
+//! URLs keep working.
+
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Characters escaped in URL path segments.
+pub(crate) const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'\'')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// HTML tag.
+static HTML_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// `src` or `href` attribute of an HTML tag (quote, value).
+static LINK_ATTRIBUTE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)(\s(?:src|href)\s*=\s*)(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+/// URL scheme (`https:`, `mailto:`, `data:`...).
+static SCHEME_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").unwrap());
+
+/// Target of a link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    /// Remote URL or anchor, left as is.
+    External,
+    /// File served at `url`.
+    Found { url: String },
+    /// No file served at this path.
+    Missing,
+}
+
+/// Resolves the links of one content file.
+#[derive(Debug, Clone)]
+pub struct LinkResolver {
+    /// Directory served as `/static/`.
+    static_dir: PathBuf,
+    /// Content file the links come from.
+    file: PathBuf,
+}
+
+impl LinkResolver {
+    /// Resolver of the links of `file`, `static_dir` being served as `/static/`.
+    pub fn new(static_dir: &Path, file: &Path) -> Self {
+        LinkResolver { static_dir: normalize(static_dir), file: file.to_path_buf() }
+    }
+
+    /// Resolver of the links of a deck file, found below `static/<deck>/<md or img>/`.
+    pub fn for_deck_file(content_dir: &Path, file: &Path) -> Self {
+        let static_dir = content_dir.parent().and_then(Path::parent).unwrap_or(Path::new("."));
+        Self::new(static_dir, file)
+    }
+
+    /// Returns the `/static/` URL of `path` if it is a file served from the static directory.
+    fn url_of(&self, path: &Path) -> Option<String> {
+        let relative = normalize(path).strip_prefix(&self.static_dir).ok()?.to_path_buf();
+        if !path.is_file() || relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return None;
+        }
+        let segments: Vec<String> = relative
+            .components()
+            .map(|c| utf8_percent_encode(&c.as_os_str().to_string_lossy(), SEGMENT).to_string())
+            .collect();
+        Some(format!("/static/{}", segments.join("/")))
+    }
+
+    /// Resolves a link: relative to the file first, else from the project root.
+    pub fn resolve(&self, link: &str) -> Link {
+        if link.is_empty() || link.starts_with('#') || link.starts_with("//") || SCHEME_REGEX.is_match(link) {
+            return Link::External;
+        }
+
+        // Query and fragment are kept as they are
+        let split = link.find(['?', '#']).unwrap_or(link.len());
+        let (path, suffix) = link.split_at(split);
+        let path = percent_decode_str(path).decode_utf8_lossy();
+        let root = self.static_dir.parent().unwrap_or(Path::new("."));
+
+        let candidates = match path.strip_prefix('/') {
+            // URL: below the project root
+            Some(absolute) => vec![root.join(absolute)],
+            None => {
+                let dir = self.file.parent().unwrap_or(Path::new("."));
+                vec![dir.join(path.as_ref()), root.join(path.as_ref())]
+            }
+        };
+        match candidates.iter().find_map(|candidate| self.url_of(candidate)) {
+            Some(url) => Link::Found { url: url + suffix },
+            None => Link::Missing,
+        }
+    }
+
+    /// Returns the URL a link is served at, None to keep it as is.
+    ///
+    /// Links to missing files are reported and kept.
+    pub fn rewrite(&self, link: &str) -> Option<String> {
+        match self.resolve(link) {
+            Link::Found { url } => (url != link).then_some(url),
+            Link::External => None,
+            Link::Missing => {
+                tracing::warn!("Link target not found in {:?}: {}", self.file, link);
+                None
+            }
+        }
+    }
+
+    /// Rewrites the `src` and `href` attributes of the tags of an HTML fragment.
+    pub fn rewrite_html(&self, html: &str) -> String {
+        HTML_TAG_REGEX
+            .replace_all(html, |tag: &regex::Captures| {
+                LINK_ATTRIBUTE_REGEX
+                    .replace_all(&tag[0], |attr: &regex::Captures| {
+                        let (value, quote) = match attr.get(2) {
+                            Some(value) => (value.as_str(), '"'),
+                            None => (attr.get(3).map_or("", |value| value.as_str()), '\''),
+                        };
+                        let value = self.rewrite(value).unwrap_or_else(|| value.to_string());
+                        format!("{}{}{}{}", &attr[1], quote, value, quote)
+                    })
+                    .into_owned()
+            })
+            .into_owned()
+    }
+}
+
+/// Removes `.` and `..` components of a path, without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Creates `static/deck/{md,img}` with a few files, returns the project root.
+    fn setup_deck() -> TempDir {
+        let root = TempDir::new().unwrap();
+        for file in ["static/deck/md/assets/kitten.png", "static/deck/img/chart one.webp", "static/deck/md/other.md"] {
+            let path = root.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"x").unwrap();
+        }
+        fs::write(root.path().join("README.md"), b"x").unwrap();
+        root
+    }
+
+    fn resolve(root: &Path, link: &str) -> Link {
+        let md_dir = root.join("static/deck/md");
+        LinkResolver::for_deck_file(&md_dir, &md_dir.join("cards.md")).resolve(link)
+    }
+
+    fn found(url: &str) -> Link {
+        Link::Found { url: url.to_string() }
+    }
+
+    #[rstest]
+    #[case("assets/kitten.png", found("/static/deck/md/assets/kitten.png"))]
+    #[case("./assets/kitten.png", found("/static/deck/md/assets/kitten.png"))]
+    #[case("../img/chart%20one.webp", found("/static/deck/img/chart%20one.webp"))]
+    #[case("../img/chart one.webp", found("/static/deck/img/chart%20one.webp"))]
+    #[case("other.md#section", found("/static/deck/md/other.md#section"))]
+    #[case("assets/kitten.png?v=2", found("/static/deck/md/assets/kitten.png?v=2"))]
+    #[case::project_root("static/deck/md/assets/kitten.png", found("/static/deck/md/assets/kitten.png"))]
+    #[case::url("/static/deck/md/assets/kitten.png", found("/static/deck/md/assets/kitten.png"))]
+    #[case("https://example.com/a.png", Link::External)]
+    #[case("data:image/png;base64,AAAA", Link::External)]
+    #[case("mailto:someone@example.com", Link::External)]
+    #[case("#footnote", Link::External)]
+    #[case("//cdn.example.com/a.png", Link::External)]
+    #[case("assets/missing.png", Link::Missing)]
+    #[case("/static/deck/md/missing.png", Link::Missing)]
+    #[case::not_served("../../../README.md", Link::Missing)]
+    #[case::directory("assets", Link::Missing)]
+    fn test_resolve(#[case] link: &str, #[case] expected: Link) {
+        let root = setup_deck();
+        assert_eq!(resolve(root.path(), link), expected);
+    }
+
+    #[test]
+    fn test_resolve_from_subdirectory() {
+        let root = setup_deck();
+        let md_dir = root.path().join("static/deck/md");
+        let resolver = LinkResolver::for_deck_file(&md_dir, &md_dir.join("assets").join("cards.md"));
+
+        assert_eq!(resolver.resolve("kitten.png"), found("/static/deck/md/assets/kitten.png"));
+        assert_eq!(resolver.resolve("../../img/chart one.webp"), found("/static/deck/img/chart%20one.webp"));
+    }
+
+    #[rstest]
+    #[case(
+        r#"<p align="center"><img src="assets/kitten.png" width="200"></p>"#,
+        r#"<p align="center"><img src="/static/deck/md/assets/kitten.png" width="200"></p>"#
+    )]
+    #[case("<a href='other.md'>other</a>", "<a href='/static/deck/md/other.md'>other</a>")]
+    #[case(r#"<img src="https://example.com/a.png">"#, r#"<img src="https://example.com/a.png">"#)]
+    #[case(r#"<img src="missing.png">"#, r#"<img src="missing.png">"#)]
+    #[case("src=\"assets/kitten.png\" outside of tags", "src=\"assets/kitten.png\" outside of tags")]
+    fn test_rewrite_html(#[case] html: &str, #[case] expected: &str) {
+        let root = setup_deck();
+        let md_dir = root.path().join("static/deck/md");
+        let resolver = LinkResolver::for_deck_file(&md_dir, &md_dir.join("cards.md"));
+
+        assert_eq!(resolver.rewrite_html(html), expected);
+    }
+
+    #[rstest]
+    #[case("static/deck/md/../img/a.png", "static/deck/img/a.png")]
+    #[case("./static/./deck", "static/deck")]
+    #[case("../a/b", "../a/b")]
+    #[case("a/../../b", "../b")]
+    fn test_normalize(#[case] path: &str, #[case] expected: &str) {
+        assert_eq!(normalize(Path::new(path)), PathBuf::from(expected));
+    }
+}
@@ -1,126 +1,396 @@
 use anyhow::{Context, Result};
-use pulldown_cmark::{Options, Parser, html};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd, html};
 use regex::Regex;
 use std::fs;
+use std::ops::Range;
 use std::path::Path;
+use std::sync::LazyLock;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
 use walkdir::WalkDir;
 
+use super::card_key;
+use super::cloze::{self, Side};
+use super::distractors;
+use super::front_matter::{self, Difficulty};
+use super::links::LinkResolver;
+use super::sync::{self, SyncReport};
+use super::typed_answer::{self, ExpectedAnswer};
 use crate::db::connection::DbPool;
+use crate::db::models::{CardKind, NewFlashcard};
 use crate::db::queries;
 
-pub fn load_markdown(pool: &DbPool, md_dir: &str) -> Result<()> {
-    tracing::info!("Loading markdown files from {}", md_dir);
+/// Prefix of markdown files in `content_files` and `flashcards.source_path`.
+const SOURCE_PREFIX: &str = "md/";
 
-    // Clear existing flashcards
-    queries::clear_flashcards(pool)?;
+/// HTML tag (raw HTML of cards is indexed without its markup).
+static HTML_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
 
-    let mut count = 0;
+/// Loads markdown flashcards from `md_dir`, incrementally.
+///
+/// Only files added or changed since the last load are parsed; cards of
+/// removed files are deleted (see [`sync::sync_files`]).
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn load_markdown(pool: &DbPool, md_dir: &str) -> Result<SyncReport> {
+    tracing::info!("Loading markdown files from {}", md_dir);
 
-    // Walk through all .md files recursively
-    for entry in WalkDir::new(md_dir)
+    // All .md files recursively (sorted, so duplicate card keys get stable suffixes)
+    let files = WalkDir::new(md_dir)
         .follow_links(true)
+        .sort_by_file_name()
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.path().extension().map(|ext| ext == "md").unwrap_or(false))
-    {
-        let path = entry.path();
-        tracing::debug!("Processing markdown file: {:?}", path);
-
-        match process_markdown_file(pool, path) {
-            Ok(n) => {
-                count += n;
-                tracing::debug!("Loaded {} flashcards from {:?}", n, path);
-            }
-            Err(e) => {
-                tracing::warn!("Failed to process {:?}: {:?}", path, e);
-            }
-        }
-    }
+        .map(|e| e.into_path())
+        .collect();
 
-    tracing::info!("Loaded {} flashcards from markdown files", count);
-    Ok(())
+    let report = sync::sync_files(pool, md_dir, SOURCE_PREFIX, files, |_| None, |path| {
+        tracing::debug!("Processing markdown file: {:?}", path);
+        process_markdown_file(pool, path, md_dir)
+    })?;
+
+    tracing::info!(
+        "Markdown files: {} added, {} updated, {} removed, {} unchanged ({} flashcards loaded)",
+        report.added,
+        report.updated,
+        report.removed,
+        report.unchanged,
+        report.cards_loaded
+    );
+    Ok(report)
 }
 
-fn process_markdown_file(pool: &DbPool, path: &Path) -> Result<usize> {
-    let content =
-        fs::read_to_string(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+/// Question/answer or cloze block found in a markdown file, before rendering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawCard {
+    /// Line of the `Question :` or `Cloze :` marker (1-based).
+    pub line: usize,
+    /// Whether the block is a `Cloze :` one (see [`cloze`]): its answer is optional extra text.
+    pub cloze: bool,
+    /// Explicit `ID:` of the card, if any.
+    pub explicit_id: Option<String>,
+    /// Category (None if the question has no `Category - Subcategory - ` prefix
+    /// and the file's front matter no default).
+    pub category: Option<String>,
+    pub subcategory: Option<String>,
+    /// Tags of the file's front matter and of the inline `Tags:` line (normalized).
+    pub tags: Vec<String>,
+    /// Inline `Difficulty:` line, else the file's front matter default.
+    pub difficulty: Option<Difficulty>,
+    /// Inline `Reverse:` line, else the file's front matter default: whether the
+    /// card also gives a reversed sibling (not for clozes).
+    pub reverse: bool,
+    /// Inline `Expected:` line: short answer of typed-answer mode (see [`typed_answer`]).
+    pub expected: Option<String>,
+    /// Inline `Distractors:` line: wrong options of quiz mode (see [`distractors`]).
+    pub distractors: Option<Vec<String>>,
+    /// Question (or cloze text) markdown, without category prefix, `ID:`, `Difficulty:`, `Tags:`,
+    /// `Reverse:`, `Expected:` and `Distractors:` lines.
+    pub question_md: String,
+    /// Answer markdown and the line it starts on (None without `Answer :` marker).
+    pub answer: Option<(String, usize)>,
+    /// Markdown of the `Hint :` sections between question and answer, in order, and the line each starts on.
+    pub hints: Vec<(String, usize)>,
+}
 
+/// Splits markdown content into question/answer and cloze blocks.
+///
+/// Front matter defaults apply to every card (see [`front_matter`]). HTML
+/// comments are ignored, and so are markers inside code blocks (fenced or
+/// indented) and HTML blocks, so snippets may contain `Answer:` lines. Line
+/// numbers refer to the original content.
+pub fn parse_cards(content: &str) -> Vec<RawCard> {
     // Compile all regex patterns once (performance optimization)
     // Strip HTML comments (with DOTALL for multiline comments)
     let comment_regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
 
-    // Split by "Question :" at line start (case-insensitive, multiline mode)
+    // Split by "Question :" or "Cloze :" at line start (case-insensitive, multiline mode)
     // (?m) enables multiline mode where ^ matches line start
     // (?i) enables case-insensitive matching
     // ^\s* allows optional leading whitespace before Question
-    let question_regex = Regex::new(r"(?mi)^\s*Question\s*:").unwrap();
+    let question_regex = Regex::new(r"(?mi)^\s*(Question|Cloze)\s*:").unwrap();
 
     // Find where Answer starts (case-insensitive, multiline mode, line-anchored)
     let answer_regex = Regex::new(r"(?mi)^\s*Answer\s*:").unwrap();
 
+    // Find where each Hint starts, between question and answer (same rules)
+    let hint_regex = Regex::new(r"(?mi)^\s*Hint\s*:").unwrap();
+
     // Regex to extract CATEGORY - SUBCATEGORY - QUESTION
     // Use lookahead to match " - " (space-dash-space) to allow hyphens in category names
-    // Note: No leading ":" since question_regex.split() removes "Question:" entirely
+    // Note: No leading ":" since the "Question:" marker is not part of the block
     let category_regex = Regex::new(r"^\s*(.+?)\s-\s(.+?)\s-\s(.+)").unwrap();
 
-    let cleaned = comment_regex.replace_all(&content, "");
+    // Invalid front matter is reported by `check`, cards are loaded without defaults
+    let (yaml, body) = front_matter::split(content);
+    let defaults = yaml
+        .map(|yaml| {
+            front_matter::parse(yaml).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid front matter: {}", e);
+                Default::default()
+            })
+        })
+        .unwrap_or_default();
+
+    // Comments are replaced by their newlines so line numbers stay accurate
+    let cleaned = comment_regex.replace_all(&body, |caps: &regex::Captures| "\n".repeat(caps[0].matches('\n').count()));
+    let line_at = |offset: usize| cleaned[..offset].matches('\n').count() + 1;
 
-    // Split by "Question:" keyword to get individual Q&A blocks
-    let parts: Vec<&str> = question_regex.split(&cleaned).collect();
+    // A marker is only one if its colon is outside literal blocks
+    let literals = literal_ranges(&cleaned);
+    let is_marker = |end: usize| !literals.iter().any(|range| range.contains(&(end - 1)));
 
-    let mut count = 0;
+    // Each block runs from a "Question:" or "Cloze:" marker to the next one
+    let markers: Vec<_> = question_regex
+        .captures_iter(&cleaned)
+        .filter(|caps| is_marker(caps.get(0).unwrap().end()))
+        .map(|caps| (caps.get(0).unwrap(), caps[1].eq_ignore_ascii_case("cloze")))
+        .collect();
+    let mut cards = Vec::new();
 
-    for part in parts.iter().skip(1) {
-        // Each part should start with " : " followed by question text,
-        // then contain "Answer  :" (with variable spaces) followed by answer text
+    for (i, &(marker, cloze)) in markers.iter().enumerate() {
+        let block_end = markers.get(i + 1).map_or(cleaned.len(), |(next, _)| next.start());
+        let block = &cleaned[marker.end()..block_end];
 
         // Find where Answer starts
-        if let Some(answer_match) = answer_regex.find(part) {
-            let question_part = part[..answer_match.start()].trim();
-            let answer_md = part[answer_match.end()..].trim();
-
-            // Extract category, subcategory, and question
-            let (category, subcategory, question_md) =
-                if let Some(caps) = category_regex.captures(question_part) {
-                    (
-                        Some(caps.get(1).unwrap().as_str().trim().to_string()),
-                        Some(caps.get(2).unwrap().as_str().trim().to_string()),
-                        caps.get(3).unwrap().as_str().trim(),
-                    )
-                } else {
-                    // Question non-conforme: catégorie = None
-                    tracing::warn!(
-                        "Non-compliant question format in {:?}: {}",
-                        path,
-                        question_part
-                    );
-                    (None, None, question_part)
-                };
-
-            // Skip empty Q&A pairs
-            if question_md.is_empty() && answer_md.is_empty() {
-                continue;
+        let answer_marker = answer_regex.find_iter(block).find(|m| is_marker(marker.end() + m.end()));
+        let (question_raw, answer) = match answer_marker {
+            Some(answer_match) => {
+                let answer_raw = &block[answer_match.end()..];
+                let leading = answer_raw.len() - answer_raw.trim_start().len();
+                let answer_line = line_at(marker.end() + answer_match.end() + leading);
+                (&block[..answer_match.start()], Some((answer_raw.trim().to_string(), answer_line)))
+            }
+            None => (block, None),
+        };
+
+        // Each "Hint:" section runs to the next one, the last one to the answer
+        let hint_markers: Vec<_> =
+            hint_regex.find_iter(question_raw).filter(|m| is_marker(marker.end() + m.end())).collect();
+        let hints = hint_markers
+            .iter()
+            .enumerate()
+            .map(|(j, hint_match)| {
+                let hint_end = hint_markers.get(j + 1).map_or(question_raw.len(), |next| next.start());
+                let hint_raw = &question_raw[hint_match.end()..hint_end];
+                // Line of the hint text, or of its marker if empty
+                let leading = hint_raw.len() - hint_raw.trim_start().len();
+                let leading = if leading == hint_raw.len() { 0 } else { leading };
+                (hint_raw.trim().to_string(), line_at(marker.end() + hint_match.end() + leading))
+            })
+            .collect();
+        let question_raw = hint_markers.first().map_or(question_raw, |first| &question_raw[..first.start()]);
+
+        // Explicit "ID:", "Difficulty:", "Tags:", "Reverse:", "Expected:" and "Distractors:" lines, if any,
+        // are not part of the question
+        let (explicit_id, question_part) = card_key::extract_explicit_id(question_raw);
+        let (difficulty, question_part) = front_matter::extract_difficulty(&question_part);
+        let (inline_tags, question_part) = front_matter::extract_tags(&question_part);
+        let (reverse, question_part) = front_matter::extract_reverse(&question_part);
+        let (expected, question_part) = typed_answer::extract_expected(&question_part);
+        let (distractors, question_part) = distractors::extract_distractors(&question_part);
+        let question_part = question_part.trim();
+
+        // Extract category, subcategory, and question (inline prefix overrides front matter)
+        let (category, subcategory, question_md) = match category_regex.captures(question_part) {
+            Some(caps) => (
+                Some(caps[1].trim().to_string()),
+                Some(caps[2].trim().to_string()),
+                caps[3].trim().to_string(),
+            ),
+            None => (defaults.category.clone(), defaults.subcategory.clone(), question_part.to_string()),
+        };
+
+        cards.push(RawCard {
+            line: line_at(marker.end()),
+            cloze,
+            explicit_id,
+            category,
+            subcategory,
+            tags: front_matter::normalize_tags(defaults.tags.iter().chain(&inline_tags).map(String::as_str)),
+            difficulty: difficulty.or(defaults.difficulty),
+            reverse: reverse.or(defaults.reverse).unwrap_or(false),
+            expected,
+            distractors,
+            question_md,
+            answer,
+            hints,
+        });
+    }
+
+    cards
+}
+
+/// Byte ranges of code blocks and HTML blocks, whose lines are content, not markers.
+///
+/// Blockquotes are not included: a `>` line never matches a marker, and an
+/// `Answer :` line right after a quote would be a lazy continuation of it.
+fn literal_ranges(content: &str) -> Vec<Range<usize>> {
+    Parser::new_ext(content, markdown_options())
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::HtmlBlock) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
+fn process_markdown_file(pool: &DbPool, path: &Path, base_dir: &str) -> Result<usize> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+
+    // Source file relative to base_dir, part of hashed card keys
+    let source = sync::relative_path(path, base_dir);
+    let source_path = format!("{}{}", SOURCE_PREFIX, source);
+    let links = LinkResolver::for_deck_file(Path::new(base_dir), path);
+
+    let mut count = 0;
+
+    for card in parse_cards(&content) {
+        // Blocks without "Answer:" are not cards, cloze blocks without clozes neither (see `check` for diagnostics)
+        let answer_md = match (&card.answer, card.cloze) {
+            (Some((answer_md, _)), _) => answer_md.as_str(),
+            (None, true) => "",
+            (None, false) => continue,
+        };
+        let indexes = cloze::indexes(&card.question_md);
+        if card.cloze && indexes.is_empty() {
+            tracing::warn!("Cloze without {{{{c1::...}}}} deletion in {:?}: {}", path, card.question_md);
+            continue;
+        }
+
+        if card.category.is_none() {
+            // Question non-conforme: catégorie = None
+            tracing::warn!(
+                "Non-compliant question format in {:?}: {}",
+                path,
+                card.question_md
+            );
+        }
+
+        // Skip empty Q&A pairs
+        if card.question_md.is_empty() && answer_md.is_empty() {
+            continue;
+        }
+
+        // Cards of the block: (key suffix, kind, question, answer) markdown, one per index for clozes
+        let sides: Vec<(String, CardKind, String, String)> = if card.cloze {
+            if card.reverse {
+                tracing::warn!("Ignoring Reverse: of cloze in {:?}: {}", path, card.question_md);
+            }
+            indexes
+                .iter()
+                .map(|&index| {
+                    let question = cloze::render(&card.question_md, index, Side::Question);
+                    let answer = cloze::render(&card.question_md, index, Side::Answer);
+                    let answer = if answer_md.is_empty() { answer } else { format!("{}\n\n{}", answer, answer_md) };
+                    (format!(".c{}", index), CardKind::Cloze, question, answer)
+                })
+                .collect()
+        } else {
+            let mut sides = vec![(String::new(), CardKind::Markdown, card.question_md.clone(), answer_md.to_string())];
+            if card.reverse {
+                sides.push((".reverse".to_string(), CardKind::Reversed, answer_md.to_string(), card.question_md.clone()));
             }
+            sides
+        };
+
+        // Stable identity: explicit ID, else hash of question and source file,
+        // made unique for all the cards of the block
+        let key = card
+            .explicit_id
+            .clone()
+            .unwrap_or_else(|| card_key::hashed_key(&card.question_md, &source));
+        let key = card_key::disambiguate(key, &source, |k| {
+            for (suffix, ..) in &sides {
+                if queries::card_key_exists(pool, &format!("{}{}", k, suffix))? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        })?;
+
+        // Typed answers only apply to the question as written: invalid ones are reported by `check`
+        let expected_answer = match (&card.expected, card.cloze) {
+            (Some(_), true) => {
+                tracing::warn!("Ignoring Expected: of cloze in {:?}: {}", path, card.question_md);
+                None
+            }
+            (Some(spec), false) => match ExpectedAnswer::parse(spec) {
+                Ok(Some(_)) => Some(spec.as_str()),
+                Ok(None) => None,
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid Expected: in {:?}: {}", path, e);
+                    None
+                }
+            },
+            (None, _) => None,
+        };
+
+        // Wrong options of the question as written, rendered inline like answers
+        let distractors_html = match (&card.distractors, card.cloze) {
+            (Some(_), true) => {
+                tracing::warn!("Ignoring Distractors: of cloze in {:?}: {}", path, card.question_md);
+                Vec::new()
+            }
+            (Some(distractors), false) => distractors
+                .iter()
+                .map(|distractor_md| markdown_to_html(distractor_md, Some(&links)))
+                .collect::<Result<Vec<_>>>()?,
+            (None, _) => Vec::new(),
+        };
+
+        // Cards of a same block are siblings, never practiced in a row
+        let sibling_key = (sides.len() > 1).then_some(key.as_str());
+
+        // Hints lead to the answer: a reversed card has none
+        let hints_html = card
+            .hints
+            .iter()
+            .filter(|(hint_md, _)| !hint_md.is_empty())
+            .map(|(hint_md, _)| markdown_to_html(hint_md, Some(&links)))
+            .collect::<Result<Vec<_>>>()?;
+
+        for (suffix, kind, question_md, answer_md) in sides {
+            let card_key = format!("{}{}", key, suffix);
 
             // Prepend headers to markdown BEFORE conversion
             let question_with_header = format!("### Question:\n{}", question_md);
             let answer_with_header = format!("### Answer:\n{}", answer_md);
 
-            // Convert markdown to HTML with syntax highlighting
-            let q_html = markdown_to_html(&question_with_header)?;
-            let a_html = markdown_to_html(&answer_with_header)?;
+            // Convert markdown to HTML with syntax highlighting, links relative to the file
+            let q_html = markdown_to_html(&question_with_header, Some(&links))?;
+            let a_html = markdown_to_html(&answer_with_header, Some(&links))?;
+
+            // Plain text (without headers), indexed for search
+            let q_text = markdown_to_text(&question_md);
+            let a_text = markdown_to_text(&answer_md);
 
             // Insert into database with category and subcategory
             queries::insert_flashcard(
                 pool,
-                category.as_deref(),
-                subcategory.as_deref(),
-                &q_html,
-                &a_html,
+                &NewFlashcard {
+                    card_key: &card_key,
+                    source_path: &source_path,
+                    category: card.category.as_deref(),
+                    subcategory: card.subcategory.as_deref(),
+                    difficulty: card.difficulty.map(Difficulty::as_str),
+                    kind,
+                    sibling_key,
+                    expected_answer: if kind == CardKind::Markdown { expected_answer } else { None },
+                    tags: &card.tags,
+                    hints_html: if kind == CardKind::Reversed { &[] } else { &hints_html },
+                    distractors_html: if kind == CardKind::Markdown { &distractors_html } else { &[] },
+                    question_html: &q_html,
+                    answer_html: &a_html,
+                    question_text: &q_text,
+                    answer_text: &a_text,
+                    thumbnail_html: None,
+                },
             )?;
             count += 1;
         }
@@ -129,14 +399,22 @@ fn process_markdown_file(pool: &DbPool, path: &Path) -> Result<usize> {
     Ok(count)
 }
 
-fn markdown_to_html(markdown: &str) -> Result<String> {
+/// Markdown extensions enabled for cards.
+pub(crate) fn markdown_options() -> Options {
     // Enable markdown extensions to match Python's "extra" extension
     let mut options = Options::empty();
     options.insert(Options::ENABLE_TABLES);
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_FOOTNOTES);
+    options
+}
 
-    let parser = Parser::new_ext(markdown, options);
+/// Converts card markdown to HTML, highlighting code blocks.
+///
+/// With `links`, relative links to images and files are rewritten to their URL
+/// (see [`LinkResolver`]).
+pub(crate) fn markdown_to_html(markdown: &str, links: Option<&LinkResolver>) -> Result<String> {
+    let parser = Parser::new_ext(markdown, markdown_options());
 
     // Load syntax highlighting
     let ss = SyntaxSet::load_defaults_newlines();
@@ -174,6 +452,16 @@ fn markdown_to_html(markdown: &str) -> Result<String> {
                     events.push(pulldown_cmark::Event::Text(text));
                 }
             }
+            Event::Start(Tag::Image { link_type, dest_url, title, id }) if !in_code_block => {
+                let dest_url = links.and_then(|links| links.rewrite(&dest_url)).map_or(dest_url, Into::into);
+                events.push(Event::Start(Tag::Image { link_type, dest_url, title, id }));
+            }
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) if !in_code_block => {
+                let dest_url = links.and_then(|links| links.rewrite(&dest_url)).map_or(dest_url, Into::into);
+                events.push(Event::Start(Tag::Link { link_type, dest_url, title, id }));
+            }
+            Event::Html(html) if !in_code_block => events.push(Event::Html(rewrite_html(links, html))),
+            Event::InlineHtml(html) if !in_code_block => events.push(Event::InlineHtml(rewrite_html(links, html))),
             _ => {
                 if !in_code_block {
                     events.push(event);
@@ -189,6 +477,44 @@ fn markdown_to_html(markdown: &str) -> Result<String> {
     Ok(html_output)
 }
 
+/// Rewrites the links of raw HTML, if links are resolved.
+fn rewrite_html<'a>(links: Option<&LinkResolver>, html: CowStr<'a>) -> CowStr<'a> {
+    match links {
+        Some(links) => links.rewrite_html(&html).into(),
+        None => html,
+    }
+}
+
+/// Converts markdown to the plain text indexed for search.
+///
+/// Keeps the words of text, code and image descriptions, drops markup (including
+/// tags of raw HTML) so searches do not match tag names or highlighting styles.
+pub(crate) fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::new();
+
+    for event in Parser::new_ext(markdown, markdown_options()) {
+        match event {
+            Event::Text(t) | Event::Code(t) | Event::InlineMath(t) | Event::DisplayMath(t) => text.push_str(&t),
+            Event::Html(h) | Event::InlineHtml(h) => text.push_str(&HTML_TAG_REGEX.replace_all(&h, " ")),
+            // Separate words of consecutive blocks and lines
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::Start(Tag::Item)
+            | Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::TableCell
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_),
+            ) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 fn highlight_code(
     code: &str,
     lang: &str,
@@ -233,30 +559,7 @@ mod tests {
         let manager = r2d2_sqlite::SqliteConnectionManager::memory();
         let pool = r2d2::Pool::builder().max_size(1).build(manager).unwrap();
 
-        let conn = pool.get().unwrap();
-        conn.execute(
-            "CREATE TABLE flashcards (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                category TEXT,
-                subcategory TEXT,
-                question_html TEXT NOT NULL,
-                answer_html TEXT NOT NULL
-            )",
-            [],
-        )
-        .unwrap();
-
-        conn.execute(
-            "CREATE VIRTUAL TABLE flashcards_fts USING fts5(
-                id UNINDEXED,
-                category,
-                subcategory,
-                question_html,
-                answer_html
-            )",
-            [],
-        )
-        .unwrap();
+        crate::db::init_database(&pool).unwrap();
 
         pool
     }
@@ -266,7 +569,7 @@ mod tests {
     #[test]
     fn test_markdown_to_html_basic_formatting() {
         let markdown = "**bold** *italic* ~~strikethrough~~";
-        let html = markdown_to_html(markdown).unwrap();
+        let html = markdown_to_html(markdown, None).unwrap();
 
         assert!(html.contains("<strong>bold</strong>"));
         assert!(html.contains("<em>italic</em>"));
@@ -276,7 +579,7 @@ mod tests {
     #[test]
     fn test_markdown_to_html_headers() {
         let markdown = "### Header 3\n\n#### Header 4";
-        let html = markdown_to_html(markdown).unwrap();
+        let html = markdown_to_html(markdown, None).unwrap();
 
         assert!(html.contains("<h3>Header 3</h3>"));
         assert!(html.contains("<h4>Header 4</h4>"));
@@ -285,7 +588,7 @@ mod tests {
     #[test]
     fn test_markdown_to_html_code_inline() {
         let markdown = "Use `println!()` macro";
-        let html = markdown_to_html(markdown).unwrap();
+        let html = markdown_to_html(markdown, None).unwrap();
 
         assert!(html.contains("<code>println!()</code>"));
     }
@@ -297,7 +600,7 @@ fn main() {
     println!("Hello");
 }
 ```"#;
-        let html = markdown_to_html(markdown).unwrap();
+        let html = markdown_to_html(markdown, None).unwrap();
 
         // Should contain pre/code tags from syntax highlighting
         assert!(html.contains("<pre><code>"));
@@ -311,7 +614,7 @@ fn main() {
         let markdown = r#"| Header 1 | Header 2 |
 |----------|----------|
 | Cell 1   | Cell 2   |"#;
-        let html = markdown_to_html(markdown).unwrap();
+        let html = markdown_to_html(markdown, None).unwrap();
 
         assert!(html.contains("<table>"));
         assert!(html.contains("<th>Header 1</th>"));
@@ -321,14 +624,14 @@ fn main() {
     #[test]
     fn test_markdown_to_html_links() {
         let markdown = "[Rust](https://rust-lang.org)";
-        let html = markdown_to_html(markdown).unwrap();
+        let html = markdown_to_html(markdown, None).unwrap();
 
         assert!(html.contains(r#"<a href="https://rust-lang.org">Rust</a>"#));
     }
 
     #[test]
     fn test_markdown_to_html_empty_input() {
-        let html = markdown_to_html("").unwrap();
+        let html = markdown_to_html("", None).unwrap();
         // Empty markdown should produce minimal HTML
         assert!(html.is_empty() || html == "\n");
     }
@@ -379,7 +682,8 @@ fn main() {
 
         fs::write(&file_path, content).unwrap();
 
-        let count = process_markdown_file(&pool, &file_path).unwrap();
+        let count =
+            process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
         assert_eq!(count, expected_count);
 
         if expected_count > 0 {
@@ -408,7 +712,8 @@ fn main() {
         let content = "Question : \nAnswer : \n\nQuestion : Valid - Question - Q\nAnswer : A";
         fs::write(&file_path, content).unwrap();
 
-        let count = process_markdown_file(&pool, &file_path).unwrap();
+        let count =
+            process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
         // First Q&A has both question and answer empty (after trim), so it's skipped
         // Second Q&A is valid, so only 1 card is created
         assert_eq!(count, 1);
@@ -426,7 +731,8 @@ Question : Cat - Sub - Q
 Answer : A"#;
         fs::write(&file_path, content).unwrap();
 
-        let count = process_markdown_file(&pool, &file_path).unwrap();
+        let count =
+            process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
         assert_eq!(count, 1);
 
         // Verify HTML doesn't contain comment
@@ -453,7 +759,8 @@ Answer : A"#;
         let content = "Question : Cat - Sub - Q\nAnswer  : A";
         fs::write(&file_path, content).unwrap();
 
-        let count = process_markdown_file(&pool, &file_path).unwrap();
+        let count =
+            process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
         assert_eq!(count, 1);
     }
 
@@ -466,7 +773,8 @@ Answer : A"#;
         let content = "Question : Machine-Learning - Deep-Learning - Q?\nAnswer : A";
         fs::write(&file_path, content).unwrap();
 
-        let count = process_markdown_file(&pool, &file_path).unwrap();
+        let count =
+            process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
         assert_eq!(count, 1);
 
         let conn = pool.get().unwrap();
@@ -545,7 +853,8 @@ Answer : A"#;
 
         fs::write(&file_path, content).unwrap();
 
-        let count = process_markdown_file(&pool, &file_path).unwrap();
+        let count =
+            process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
         assert_eq!(count, expected_count, "Failed for case: {}", description);
 
         if expected_count > 0 {
@@ -579,7 +888,8 @@ Answer  :
 
         fs::write(&file_path, content).unwrap();
 
-        let count = process_markdown_file(&pool, &file_path).unwrap();
+        let count =
+            process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
         assert_eq!(count, 1, "Should parse Hitchhiker's Guide question");
 
         let conn = pool.get().unwrap();
@@ -603,6 +913,472 @@ Answer  :
         );
     }
 
+    // ========== Tests for Markers in Literal Blocks ==========
+
+    #[rstest]
+    #[case(
+        "Question : Py - Str - Output?\nAnswer :\n```python\nprint(\"x\")\nAnswer: 42\n```\nDone",
+        "Answer: 42",
+        "Answer line in fenced code"
+    )]
+    #[case(
+        "Question : Py - Str - Output?\nAnswer :\n~~~\nQuestion: why?\n~~~",
+        "Question: why?",
+        "Question line in tilde fence"
+    )]
+    #[case(
+        "Question : Sql - Select - Output?\nAnswer : Rows\n\n    Question : id\n    Answer : 1\n",
+        "Answer : 1",
+        "Markers in indented code"
+    )]
+    #[case(
+        "Question : Html - Pre - Output?\nAnswer :\n<pre>\nQuestion: who?\n\nAnswer: me\n</pre>",
+        "Answer: me",
+        "Markers in HTML block"
+    )]
+    fn test_parse_cards_ignores_markers_in_literal_blocks(
+        #[case] content: &str,
+        #[case] expected_in_answer: &str,
+        #[case] description: &str,
+    ) {
+        let cards = parse_cards(content);
+
+        assert_eq!(cards.len(), 1, "Failed for case: {}", description);
+        let (answer, _) = cards[0].answer.as_ref().unwrap();
+        assert!(answer.contains(expected_in_answer), "Failed for case: {}", description);
+    }
+
+    #[test]
+    fn test_parse_cards_answer_marker_after_code_in_question() {
+        let content = "Question : Output of\n```python\nAnswer: 1\n```\nAnswer : 2";
+
+        let cards = parse_cards(content);
+
+        assert_eq!(cards.len(), 1);
+        assert!(cards[0].question_md.contains("Answer: 1"));
+        assert_eq!(cards[0].answer, Some(("2".to_string(), 5)));
+    }
+
+    #[test]
+    fn test_parse_cards_marker_after_code_block_still_splits() {
+        let content = "Question : A - B - Q1?\nAnswer :\n```\ncode\n```\nQuestion : A - B - Q2?\nAnswer : A2";
+
+        let cards = parse_cards(content);
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[1].line, 6);
+    }
+
+    #[test]
+    fn test_parse_cards_answer_after_blockquote() {
+        let content = "Question : A - B - Who said\n> To be or not to be\nAnswer : Shakespeare";
+
+        let cards = parse_cards(content);
+
+        assert_eq!(cards[0].answer, Some(("Shakespeare".to_string(), 3)));
+    }
+
+    // ========== Tests for Front Matter ==========
+
+    #[test]
+    fn test_parse_cards_applies_front_matter_defaults() {
+        let content = "---\ncategory: Python\nsubcategory: Matplotlib\ntags: [plot]\ndifficulty: easy\n---\nQuestion : Figure size?\nAnswer : figsize";
+
+        let cards = parse_cards(content);
+
+        assert_eq!(cards[0].line, 7);
+        assert_eq!(cards[0].category.as_deref(), Some("Python"));
+        assert_eq!(cards[0].subcategory.as_deref(), Some("Matplotlib"));
+        assert_eq!(cards[0].tags, vec!["plot"]);
+        assert_eq!(cards[0].difficulty, Some(Difficulty::Easy));
+        assert_eq!(cards[0].question_md, "Figure size?");
+    }
+
+    #[test]
+    fn test_parse_cards_inline_metadata_overrides_front_matter() {
+        let content = "---\ncategory: Python\nsubcategory: Matplotlib\ndifficulty: easy\n---\nQuestion : Python - Seaborn - Theme?\nDifficulty: hard\nAnswer : set_theme";
+
+        let cards = parse_cards(content);
+
+        assert_eq!(cards[0].subcategory.as_deref(), Some("Seaborn"));
+        assert_eq!(cards[0].difficulty, Some(Difficulty::Hard));
+        assert_eq!(cards[0].question_md, "Theme?");
+    }
+
+    #[test]
+    fn test_parse_cards_inline_tags_add_to_front_matter_tags() {
+        let content = "---\ntags: [Pandas]\n---\nQuestion : Speed up groupby?\nTags: performance, pandas\nAnswer : A";
+
+        let cards = parse_cards(content);
+
+        assert_eq!(cards[0].tags, vec!["pandas", "performance"]);
+        assert_eq!(cards[0].question_md, "Speed up groupby?");
+    }
+
+    #[test]
+    fn test_load_markdown_stores_tags() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        fs::write(temp_dir.path().join("test.md"), "Question : Q?\nID: tagged\nTags: a, b\nAnswer : A").unwrap();
+
+        load_markdown(&pool, temp_dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(queries::get_card_tags(&pool, "tagged").unwrap(), vec!["a", "b"]);
+    }
+
+    #[rstest]
+    #[case("**Bold** and *italic*", "Bold and italic")]
+    #[case("# Title\nText", "Title Text")]
+    #[case("Call `len()`", "Call len()")]
+    #[case("```rust\nlet x = 1;\n```", "let x = 1;")]
+    #[case("- one\n- two", "one two")]
+    #[case("- metrics\n  - conf matrix", "metrics conf matrix")]
+    #[case("<p align=\"center\"><img src=\"a.png\"/></p>", "")]
+    #[case("<span style=\"color:red\">Élève</span> noté", "Élève noté")]
+    #[case("![Kitten photo](kitten.png)", "Kitten photo")]
+    fn test_markdown_to_text(#[case] markdown: &str, #[case] expected: &str) {
+        assert_eq!(markdown_to_text(markdown), expected);
+    }
+
+    /// Loads `content` and returns how many cards match the FTS5 `query`.
+    fn search_count(content: &str, query: &str) -> i64 {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        fs::write(temp_dir.path().join("test.md"), content).unwrap();
+        load_markdown(&pool, temp_dir.path().to_str().unwrap()).unwrap();
+        queries::populate_fts_table(&pool).unwrap();
+
+        let conn = pool.get().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM flashcards_fts WHERE flashcards_fts MATCH ?1", [query], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case("span", 0)] // Syntax highlighting markup
+    #[case("color", 0)]
+    #[case("question", 0)] // Header added to the HTML
+    #[case("println", 1)] // Code is searchable
+    #[case("eleve", 1)] // Accents are ignored
+    #[case("élève", 1)]
+    #[case("Eleve", 1)]
+    fn test_search_indexes_plain_text(#[case] query: &str, #[case] expected: i64) {
+        let content = "Question : Rust - Macros - How does an élève print?\nAnswer :\n```rust\nprintln!(\"hi\");\n```";
+        assert_eq!(search_count(content, query), expected);
+    }
+
+    #[test]
+    fn test_parse_cards_ignores_invalid_front_matter() {
+        let cards = parse_cards("---\ncategroy: Python\n---\nQuestion : Q?\nAnswer : A");
+
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].category, None);
+    }
+
+    #[test]
+    fn test_card_key_survives_moving_category_to_front_matter() {
+        let before = load_keys("Question : Python - Matplotlib - Figure size?\nAnswer : figsize");
+        let after = load_keys("---\ncategory: Python\nsubcategory: Matplotlib\n---\nQuestion : Figure size?\nAnswer : figsize");
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_process_markdown_file_stores_difficulty() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        fs::write(&file_path, "---\ndifficulty: medium\n---\nQuestion : Cat - Sub - Q?\nAnswer : A").unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let difficulty: Option<String> = conn
+            .query_row("SELECT difficulty FROM flashcards WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(difficulty.as_deref(), Some("medium"));
+    }
+
+    #[test]
+    fn test_process_markdown_file_stores_kind() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        // Empty question: still a markdown card
+        fs::write(&file_path, "Question :\nAnswer : A").unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let kind: String = conn.query_row("SELECT kind FROM flashcards WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(kind, "markdown");
+    }
+
+    #[test]
+    fn test_parse_cards_cloze_block() {
+        let content = "Question : Cat - Sub - Q?\nAnswer : A\n\ncloze : Rust - Ownership - A value has one {{c1::owner}}.\nID: owner\n";
+        let cards = parse_cards(content);
+
+        assert_eq!(cards.len(), 2);
+        assert!(!cards[0].cloze);
+        assert!(cards[1].cloze);
+        assert_eq!(cards[1].line, 4);
+        assert_eq!(cards[1].category.as_deref(), Some("Rust"));
+        assert_eq!(cards[1].question_md, "A value has one {{c1::owner}}.");
+        assert_eq!(cards[1].explicit_id.as_deref(), Some("owner"));
+        assert_eq!(cards[1].answer, None);
+    }
+
+    #[test]
+    fn test_parse_cards_hints() {
+        let content = "Question : Maths - Calculus - Derive $x^2$\nDifficulty: hard\nHint : Power rule\n\n\
+                       Hint:\n```text\nHint : not a marker\n```\nAnswer : $2x$";
+        let cards = parse_cards(content);
+
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].question_md, "Derive $x^2$");
+        assert_eq!(cards[0].hints.len(), 2);
+        assert_eq!(cards[0].hints[0], ("Power rule".to_string(), 3));
+        assert!(cards[0].hints[1].0.contains("Hint : not a marker"));
+        assert_eq!(cards[0].hints[1].1, 6);
+        assert_eq!(cards[0].answer, Some(("$2x$".to_string(), 9)));
+    }
+
+    /// Loads `content` as `test.md` and returns (card_key, kind, question_html, answer_html) in file order.
+    fn load_cards(content: &str) -> Vec<(String, String, String, String)> {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        fs::write(&file_path, content).unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT card_key, kind, question_html, answer_html FROM flashcards ORDER BY id")
+            .unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_process_markdown_file_expands_cloze() {
+        let cards = load_cards(
+            "Cloze : Rust - Ownership - A value has a single {{c1::owner}} and is {{c2::**dropped**}} with it.\nID: owner\n\n\
+             Answer : See the *Book*, chapter 4.",
+        );
+
+        assert_eq!(cards.len(), 2);
+        let (key, kind, q_html, a_html) = &cards[0];
+        assert_eq!(key, "owner.c1");
+        assert_eq!(kind, "cloze");
+        assert!(q_html.contains(r#"single <span class="cloze">[...]</span> and is <strong>dropped</strong>"#));
+        assert!(a_html.contains(r#"single <span class="cloze">owner</span> and is <strong>dropped</strong>"#));
+        assert!(a_html.contains("<em>Book</em>"));
+
+        let (key, _, q_html, a_html) = &cards[1];
+        assert_eq!(key, "owner.c2");
+        assert!(q_html.contains(r#"single owner and is <span class="cloze">[...]</span>"#));
+        assert!(a_html.contains(r#"<span class="cloze"><strong>dropped</strong></span>"#));
+    }
+
+    #[test]
+    fn test_process_markdown_file_skips_cloze_without_deletion() {
+        let cards = load_cards("Cloze : Rust - Ownership - Nothing to recall\n\nQuestion : Cat - Sub - Q?\nAnswer : A");
+
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].1, "markdown");
+    }
+
+    #[test]
+    fn test_process_markdown_file_adds_reversed_card() {
+        let cards = load_cards(
+            "Question : Rust - Types - What does `Box<T>` do?\nID: box\nReverse: true\nAnswer : Heap-allocated owned pointer\n\n\
+             Question : Rust - Types - What does `Rc<T>` do?\nAnswer : Reference-counted pointer",
+        );
+
+        assert_eq!(cards.len(), 3);
+        let (key, kind, q_html, a_html) = &cards[1];
+        assert_eq!(key, "box.reverse");
+        assert_eq!(kind, "reversed");
+        assert!(q_html.contains("Heap-allocated owned pointer"));
+        assert!(a_html.contains("What does <code>Box&lt;T&gt;</code> do?"));
+        assert!(!q_html.contains("Reverse"));
+        assert_eq!(cards[2].1, "markdown");
+    }
+
+    #[rstest]
+    #[case("---\nreverse: true\n---\nQuestion : Cat - Sub - Q?\nAnswer : A", 2)]
+    #[case("---\nreverse: true\n---\nQuestion : Cat - Sub - Q?\nReverse: false\nAnswer : A", 1)]
+    #[case("---\nreverse: true\n---\nCloze : Cat - Sub - {{c1::a}} and {{c2::b}}", 2)]
+    fn test_reverse_flag_from_front_matter(#[case] content: &str, #[case] expected_count: usize) {
+        assert_eq!(load_cards(content).len(), expected_count);
+    }
+
+    #[test]
+    fn test_process_markdown_file_stores_sibling_keys() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        fs::write(
+            &file_path,
+            "Question : Cat - Sub - Q?\nID: q\nReverse: true\nAnswer : A\n\n\
+             Cloze : Cat - Sub - {{c1::a}} and {{c2::b}}\nID: c\n\n\
+             Question : Cat - Sub - Alone?\nID: alone\nAnswer : A",
+        )
+        .unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(queries::get_sibling_keys(&pool, "q").unwrap(), vec!["q.reverse"]);
+        assert_eq!(queries::get_sibling_keys(&pool, "c.c2").unwrap(), vec!["c.c1"]);
+        assert!(queries::get_sibling_keys(&pool, "alone").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_process_markdown_file_disambiguates_repeated_reversed_cards() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        let block = "Question : Cat - Sub - Q?\nReverse: true\nAnswer : A\n\n";
+        fs::write(&file_path, block.repeat(2)).unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let mut stmt = conn.prepare("SELECT card_key, sibling_key FROM flashcards ORDER BY id").unwrap();
+        let keys: Vec<(String, String)> =
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|r| r.unwrap()).collect();
+        let key = &keys[0].0;
+        let second = format!("{}-2", key);
+        assert_eq!(
+            keys,
+            vec![
+                (key.clone(), key.clone()),
+                (format!("{}.reverse", key), key.clone()),
+                (second.clone(), second.clone()),
+                (format!("{}.reverse", second), second.clone()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_markdown_file_stores_hints() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        fs::write(
+            &file_path,
+            "Question : Cat - Sub - Q?\nID: q\nReverse: true\nHint : **First**\nHint :\nHint : Second\nAnswer : A\n\n\
+             Cloze : Cat - Sub - {{c1::a}} and {{c2::b}}\nID: c\nHint : Letters",
+        )
+        .unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        // Empty hints are skipped, reversed cards have none
+        let hints = queries::get_card_hints(&pool, "q").unwrap();
+        assert_eq!(hints.len(), 2);
+        assert!(hints[0].contains("<strong>First</strong>"));
+        assert!(hints[1].contains("Second"));
+        assert!(queries::get_card_hints(&pool, "q.reverse").unwrap().is_empty());
+        assert_eq!(queries::get_card_hints(&pool, "c.c2").unwrap().len(), 1);
+
+        let conn = pool.get().unwrap();
+        let q_html: String =
+            conn.query_row("SELECT question_html FROM flashcards WHERE card_key = 'q'", [], |row| row.get(0)).unwrap();
+        assert!(!q_html.contains("First"));
+    }
+
+    #[test]
+    fn test_process_markdown_file_stores_expected_answers() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        fs::write(
+            &file_path,
+            "Question : Cat - Sub - Q?\nID: q\nReverse: true\nExpected: FTS5 | SQLite FTS5\nAnswer : FTS5\n\n\
+             Question : Cat - Sub - Invalid?\nID: invalid\nExpected: /(unclosed/\nAnswer : A\n\n\
+             Cloze : Cat - Sub - {{c1::a}}\nID: c\nExpected: a",
+        )
+        .unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        // Only the question as written has one, invalid ones are dropped
+        assert_eq!(queries::get_expected_answer(&pool, "q").unwrap().as_deref(), Some("FTS5 | SQLite FTS5"));
+        assert_eq!(queries::get_expected_answer(&pool, "q.reverse").unwrap(), None);
+        assert_eq!(queries::get_expected_answer(&pool, "invalid").unwrap(), None);
+        assert_eq!(queries::get_expected_answer(&pool, "c.c1").unwrap(), None);
+
+        let conn = pool.get().unwrap();
+        let q_html: String =
+            conn.query_row("SELECT question_html FROM flashcards WHERE card_key = 'q'", [], |row| row.get(0)).unwrap();
+        assert!(!q_html.contains("Expected"));
+    }
+
+    #[test]
+    fn test_process_markdown_file_stores_distractors() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let file_path = temp_dir.path().join("test.md");
+        fs::write(
+            &file_path,
+            "Question : Cat - Sub - Q?\nID: q\nReverse: true\nDistractors: **Lucene** | Xapian\nAnswer : FTS5",
+        )
+        .unwrap();
+
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
+
+        let distractors = queries::get_card_distractors(&pool, "q").unwrap();
+        assert_eq!(distractors.len(), 2);
+        assert!(distractors[0].contains("<strong>Lucene</strong>"));
+        assert!(queries::get_card_distractors(&pool, "q.reverse").unwrap().is_empty());
+
+        let conn = pool.get().unwrap();
+        let q_html: String =
+            conn.query_row("SELECT question_html FROM flashcards WHERE card_key = 'q'", [], |row| row.get(0)).unwrap();
+        assert!(!q_html.contains("Xapian"));
+    }
+
+    #[test]
+    fn test_cloze_keys_survive_extra_edit() {
+        let before = load_keys("Cloze : Cat - Sub - {{c1::a}} and {{c2::b}}\nAnswer : Typo");
+        let after = load_keys("Cloze : Cat - Sub - {{c1::a}} and {{c2::b}}\nAnswer : Fixed");
+
+        assert_eq!(before.len(), 2);
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_process_markdown_file_rewrites_relative_links() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        let md_dir = temp_dir.path().join("static").join("deck").join("md");
+        fs::create_dir_all(md_dir.join("maths").join("assets")).unwrap();
+        fs::write(md_dir.join("maths").join("assets").join("plot.png"), b"png").unwrap();
+        let file_path = md_dir.join("maths").join("cards.md");
+        let content = "Question : Maths - Plots - ![plot](assets/plot.png)?\n\
+                       Answer :\n<img src=\"assets/plot.png\" width=\"200\">\n\n\
+                       [Remote](https://example.com) and [missing](assets/missing.png)";
+        fs::write(&file_path, content).unwrap();
+
+        process_markdown_file(&pool, &file_path, md_dir.to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let (q_html, a_html): (String, String) = conn
+            .query_row("SELECT question_html, answer_html FROM flashcards WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert!(q_html.contains(r#"<img src="/static/deck/md/maths/assets/plot.png" alt="plot" />"#));
+        assert!(a_html.contains(r#"<img src="/static/deck/md/maths/assets/plot.png" width="200">"#));
+        assert!(a_html.contains(r#"<a href="https://example.com">"#));
+        // Missing targets are kept (and reported)
+        assert!(a_html.contains(r#"<a href="assets/missing.png">"#));
+    }
+
     #[test]
     fn test_question_regex_pattern() {
         let question_regex = Regex::new(r"(?mi)^\s*Question\s*:").unwrap();
@@ -662,7 +1438,7 @@ Answer  :
         let content = "Question : Cat - Sub - What is 2+2?\nAnswer : 4";
         fs::write(&file_path, content).unwrap();
 
-        process_markdown_file(&pool, &file_path).unwrap();
+        process_markdown_file(&pool, &file_path, temp_dir.path().to_str().unwrap()).unwrap();
 
         let conn = pool.get().unwrap();
         let (q_html, a_html): (String, String) = conn
@@ -855,4 +1631,79 @@ Answer  :
         // Should have loaded at least the valid file
         assert!(count >= 1);
     }
+
+    // ========== Tests for Card Keys ==========
+
+    /// Loads `content` as `test.md` in a fresh database and returns card keys in file order.
+    fn load_keys(content: &str) -> Vec<String> {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        fs::write(temp_dir.path().join("test.md"), content).unwrap();
+
+        load_markdown(&pool, temp_dir.path().to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let mut stmt = conn.prepare("SELECT card_key FROM flashcards ORDER BY id").unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load_markdown_uses_explicit_id() {
+        let keys = load_keys("Question : Cat - Sub - Q?\nID: my-card\nAnswer : A");
+        assert_eq!(keys, vec!["my-card"]);
+    }
+
+    #[test]
+    fn test_load_markdown_explicit_id_not_rendered() {
+        let pool = setup_test_db();
+        let temp_dir = setup_test_dir();
+        fs::write(
+            temp_dir.path().join("test.md"),
+            "Question : Cat - Sub - Q?\nID: my-card\nAnswer : A",
+        )
+        .unwrap();
+
+        load_markdown(&pool, temp_dir.path().to_str().unwrap()).unwrap();
+
+        let conn = pool.get().unwrap();
+        let html: String = conn
+            .query_row("SELECT question_html FROM flashcards", [], |row| row.get(0))
+            .unwrap();
+        assert!(!html.contains("my-card"));
+    }
+
+    #[test]
+    fn test_card_key_survives_answer_edit() {
+        let before = load_keys("Question : Cat - Sub - Q?\nAnswer : Typo");
+        let after = load_keys("Question : Cat - Sub - Q?\nAnswer : Fixed");
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_card_key_survives_category_rename() {
+        let before = load_keys("Question : Cat - Sub - Q?\nAnswer : A");
+        let after = load_keys("Question : Other - Sub - Q?\nAnswer : A");
+
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_card_key_changes_with_question() {
+        let before = load_keys("Question : Cat - Sub - Q?\nAnswer : A");
+        let after = load_keys("Question : Cat - Sub - Another Q?\nAnswer : A");
+
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn test_duplicate_questions_get_distinct_keys() {
+        let keys = load_keys("Question : Cat - Sub - Q?\nAnswer : A1\n\nQuestion : Cat - Sub - Q?\nAnswer : A2");
+
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[1], format!("{}-2", keys[0]));
+    }
 }
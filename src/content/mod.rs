@@ -1,8 +1,20 @@
+pub mod card_key;
+pub mod check;
+pub mod cloze;
+pub mod distractors;
+pub mod front_matter;
+pub mod image_cache;
 pub mod markdown;
 pub mod images;
+pub mod links;
+pub mod sidecar;
+pub mod sync;
+pub mod typed_answer;
+pub mod watch;
 
 pub use markdown::load_markdown;
 pub use images::load_images;
+pub use sync::SyncReport;
 
 use std::path::Path;
 
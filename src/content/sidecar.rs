@@ -0,0 +1,176 @@
+// Rust guideline compliant 2025-01
+//! Metadata of image cards (sidecar files).
+//!
+//! An image may come with a file of the same name holding its question and
+//! metadata, either markdown with front matter (`bayes_theorem.md`):
+//!
+//! ```markdown
+//! ---
+//! category: Maths
+//! subcategory: Probability
+//! tags: [bayes]
+//! ---
+//! What does **Bayes' theorem** state?
+//! ```
+//!
+//! or TOML (`bayes_theorem.toml`), the question being a `question` key:
+//!
+//! ```toml
+//! question = "What does **Bayes' theorem** state?"
+//! category = "Maths"
+//! subcategory = "Probability"
+//! tags = ["bayes"]
+//! ```
+//!
+//! The image is the answer. The markdown sidecar wins if both exist.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::front_matter::{self, Difficulty};
+
+/// Extensions of sidecar files, by precedence.
+const SIDECAR_EXTENSIONS: [&str; 2] = ["md", "toml"];
+
+/// Question and metadata of an image card.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageMetadata {
+    /// Question in markdown (empty if none).
+    pub question_md: String,
+    pub category: Option<String>,
+    pub subcategory: Option<String>,
+    /// Tags (normalized).
+    pub tags: Vec<String>,
+    pub difficulty: Option<Difficulty>,
+}
+
+/// Keys of a TOML sidecar.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TomlSidecar {
+    question: String,
+    category: Option<String>,
+    subcategory: Option<String>,
+    tags: Vec<String>,
+    difficulty: Option<Difficulty>,
+}
+
+/// Returns the sidecar file of an image, if any.
+pub fn find(image: &Path) -> Option<PathBuf> {
+    SIDECAR_EXTENSIONS
+        .iter()
+        .map(|ext| image.with_extension(ext))
+        .find(|path| path.is_file())
+}
+
+/// Reads the metadata of an image from its sidecar file.
+///
+/// # Errors
+/// Returns error if the file cannot be read, or has invalid front matter or TOML.
+pub fn read(path: &Path) -> Result<ImageMetadata> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read sidecar: {:?}", path))?;
+
+    let metadata = if path.extension().is_some_and(|ext| ext == "toml") {
+        parse_toml(&content)
+    } else {
+        parse_markdown(&content)
+    };
+    metadata.with_context(|| format!("Invalid sidecar: {:?}", path))
+}
+
+/// Parses a markdown sidecar: optional front matter, then the question.
+fn parse_markdown(content: &str) -> Result<ImageMetadata> {
+    let (yaml, body) = front_matter::split(content);
+    let front_matter = yaml.map(front_matter::parse).transpose()?.unwrap_or_default();
+
+    Ok(ImageMetadata {
+        question_md: body.trim().to_string(),
+        category: front_matter.category,
+        subcategory: front_matter.subcategory,
+        tags: front_matter::normalize_tags(front_matter.tags.iter().map(String::as_str)),
+        difficulty: front_matter.difficulty,
+    })
+}
+
+/// Parses a TOML sidecar.
+fn parse_toml(content: &str) -> Result<ImageMetadata> {
+    let sidecar: TomlSidecar = toml::from_str(content)?;
+
+    Ok(ImageMetadata {
+        question_md: sidecar.question.trim().to_string(),
+        category: sidecar.category,
+        subcategory: sidecar.subcategory,
+        tags: front_matter::normalize_tags(sidecar.tags.iter().map(String::as_str)),
+        difficulty: sidecar.difficulty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn expected() -> ImageMetadata {
+        ImageMetadata {
+            question_md: "What does **Bayes' theorem** state?".to_string(),
+            category: Some("Maths".to_string()),
+            subcategory: Some("Probability".to_string()),
+            tags: vec!["bayes".to_string()],
+            difficulty: Some(Difficulty::Easy),
+        }
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let content = "---\ncategory: Maths\nsubcategory: Probability\ntags: [Bayes]\ndifficulty: easy\n---\nWhat does **Bayes' theorem** state?\n";
+
+        assert_eq!(parse_markdown(content).unwrap(), expected());
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let content = "question = \"What does **Bayes' theorem** state?\"\ncategory = \"Maths\"\nsubcategory = \"Probability\"\ntags = [\"Bayes\"]\ndifficulty = \"easy\"\n";
+
+        assert_eq!(parse_toml(content).unwrap(), expected());
+    }
+
+    #[rstest]
+    #[case("Just a question", "Just a question", None)]
+    #[case("---\ncategory: Maths\n---\n", "", Some("Maths"))]
+    #[case("", "", None)]
+    fn test_parse_markdown_partial(#[case] content: &str, #[case] question: &str, #[case] category: Option<&str>) {
+        let metadata = parse_markdown(content).unwrap();
+
+        assert_eq!(metadata.question_md, question);
+        assert_eq!(metadata.category.as_deref(), category);
+    }
+
+    #[rstest]
+    #[case::unknown_key("categroy = \"Maths\"")]
+    #[case::invalid_toml("question = ")]
+    #[case::unknown_difficulty("difficulty = \"extreme\"")]
+    fn test_parse_toml_invalid(#[case] content: &str) {
+        assert!(parse_toml(content).is_err());
+    }
+
+    #[test]
+    fn test_parse_markdown_invalid_front_matter() {
+        assert!(parse_markdown("---\ncategroy: Maths\n---\nQ").is_err());
+    }
+
+    #[test]
+    fn test_find_prefers_markdown_sidecar() {
+        let dir = TempDir::new().unwrap();
+        let image = dir.path().join("bayes.webp");
+        assert_eq!(find(&image), None);
+
+        fs::write(dir.path().join("bayes.toml"), "").unwrap();
+        assert_eq!(find(&image), Some(dir.path().join("bayes.toml")));
+
+        fs::write(dir.path().join("bayes.md"), "").unwrap();
+        assert_eq!(find(&image), Some(dir.path().join("bayes.md")));
+    }
+}
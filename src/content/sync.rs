@@ -0,0 +1,311 @@
+// Rust guideline compliant 2025-01
+//! Incremental content loading.
+//!
+//! Content files are tracked in the `content_files` table with their modification
+//! time and content hash. On load, only added or changed files are parsed again,
+//! and the cards of removed files are deleted, so startup picks up edits without
+//! rebuilding the database. Card keys are stable, so review history survives.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::db::connection::DbPool;
+use crate::db::models::ContentFile;
+use crate::db::queries;
+
+/// Outcome of synchronizing a content directory with the database.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Files loaded for the first time.
+    pub added: usize,
+    /// Files parsed again because their content changed.
+    pub updated: usize,
+    /// Files gone from disk, whose cards were deleted.
+    pub removed: usize,
+    /// Files skipped because they did not change.
+    pub unchanged: usize,
+    /// Flashcards inserted from added and updated files.
+    pub cards_loaded: usize,
+}
+
+impl SyncReport {
+    /// Returns true if some cards were added, changed or deleted.
+    pub fn has_changes(&self) -> bool {
+        self.added + self.updated + self.removed > 0
+    }
+}
+
+/// Returns `path` relative to `base_dir`, with `/` separators.
+pub fn relative_path(path: &Path, base_dir: &str) -> String {
+    path.strip_prefix(base_dir).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
+
+/// Returns the last modification time of a file, in Unix milliseconds.
+fn file_mtime(path: &Path) -> Result<i64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH)?.as_millis() as i64)
+}
+
+/// Returns the modification times of files, summed.
+///
+/// Changes when any of the files is modified, added or removed from the list.
+fn files_mtime(paths: &[PathBuf]) -> Result<i64> {
+    paths.iter().map(|path| file_mtime(path)).sum()
+}
+
+/// Returns the SHA-256 of the content of files, one after the other (hex).
+fn files_hash(paths: &[PathBuf]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for path in paths {
+        hasher.update(fs::read(path)?);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Synchronizes the cards loaded from `files` with the database.
+///
+/// `files` are the content files currently found under `base_dir`; they are
+/// tracked as `prefix` + path relative to `base_dir`. `sidecar` returns the
+/// file holding metadata of a content file, if any: it is tracked with it.
+/// Files whose modification time or content (or their sidecar's) changed are
+/// passed to `load_file`, which inserts their cards and returns how many it
+/// inserted. A file that fails to load is logged and left out, so it is
+/// retried on next load.
+///
+/// Cards not attached to any content file (loaded by older versions) are deleted.
+/// The FTS table must be updated afterwards with `populate_fts_table()`.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn sync_files(
+    pool: &DbPool,
+    base_dir: &str,
+    prefix: &str,
+    files: Vec<PathBuf>,
+    sidecar: impl Fn(&Path) -> Option<PathBuf>,
+    mut load_file: impl FnMut(&Path) -> Result<usize>,
+) -> Result<SyncReport> {
+    let mut report = SyncReport::default();
+
+    let orphans = queries::delete_flashcards_without_source(pool)?;
+    if orphans > 0 {
+        tracing::info!("Deleted {} flashcards without source file", orphans);
+    }
+
+    let mut tracked: HashMap<String, ContentFile> = queries::get_content_files(pool, prefix)?
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect();
+
+    for path in files {
+        let source_path = format!("{}{}", prefix, relative_path(&path, base_dir));
+        let previous = tracked.remove(&source_path);
+        let sources: Vec<PathBuf> = std::iter::once(path.clone()).chain(sidecar(&path)).collect();
+
+        let mtime = match files_mtime(&sources) {
+            Ok(mtime) => mtime,
+            Err(e) => {
+                tracing::warn!("Failed to read {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        // Same modification time: assume unchanged without reading the file
+        if previous.as_ref().is_some_and(|p| p.mtime == mtime) {
+            report.unchanged += 1;
+            continue;
+        }
+
+        let hash = match files_hash(&sources) {
+            Ok(hash) => hash,
+            Err(e) => {
+                tracing::warn!("Failed to read {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        let file = ContentFile {
+            path: source_path,
+            mtime,
+            hash,
+        };
+
+        // Touched but identical: only remember the new modification time
+        if previous.as_ref().is_some_and(|p| p.hash == file.hash) {
+            queries::upsert_content_file(pool, &file)?;
+            report.unchanged += 1;
+            continue;
+        }
+
+        queries::delete_flashcards_from_source(pool, &file.path)?;
+
+        match load_file(&path) {
+            Ok(n) => {
+                queries::upsert_content_file(pool, &file)?;
+                report.cards_loaded += n;
+                if previous.is_some() {
+                    report.updated += 1;
+                } else {
+                    report.added += 1;
+                }
+                tracing::debug!("Loaded {} flashcards from {:?}", n, path);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to process {:?}: {:?}", path, e);
+                // Drop cards inserted before the failure, retry on next load
+                queries::delete_flashcards_from_source(pool, &file.path)?;
+                queries::delete_content_file(pool, &file.path)?;
+            }
+        }
+    }
+
+    // Remaining tracked files no longer exist
+    for source_path in tracked.into_keys() {
+        let deleted = queries::delete_flashcards_from_source(pool, &source_path)?;
+        queries::delete_content_file(pool, &source_path)?;
+        report.removed += 1;
+        tracing::debug!("Removed {} flashcards of deleted file {}", deleted, source_path);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> DbPool {
+        let manager = r2d2_sqlite::SqliteConnectionManager::memory();
+        let pool = r2d2::Pool::builder().max_size(1).build(manager).unwrap();
+        crate::db::init_database(&pool).unwrap();
+        pool
+    }
+
+    /// Syncs all files of `dir`, counting calls to the loader (which inserts nothing).
+    fn sync(pool: &DbPool, dir: &TempDir, loads: &Cell<usize>) -> SyncReport {
+        let base_dir = dir.path().to_str().unwrap();
+        let mut files: Vec<PathBuf> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().path()).collect();
+        files.sort();
+
+        sync_files(pool, base_dir, "md/", files, |_| None, |_| {
+            loads.set(loads.get() + 1);
+            Ok(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path(Path::new("./static/md/a/b.md"), "./static/md"), "a/b.md");
+        assert_eq!(relative_path(Path::new("other/b.md"), "./static/md"), "other/b.md");
+    }
+
+    #[test]
+    fn test_sync_loads_new_files_once() {
+        let pool = setup_test_db();
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.md"), "a").unwrap();
+        fs::write(dir.path().join("b.md"), "b").unwrap();
+        let loads = Cell::new(0);
+
+        let first = sync(&pool, &dir, &loads);
+        let second = sync(&pool, &dir, &loads);
+
+        assert_eq!(first.added, 2);
+        assert_eq!(second.unchanged, 2);
+        assert!(!second.has_changes());
+        assert_eq!(loads.get(), 2);
+    }
+
+    #[test]
+    fn test_sync_reloads_changed_file() {
+        let pool = setup_test_db();
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.md"), "a").unwrap();
+        let loads = Cell::new(0);
+        sync(&pool, &dir, &loads);
+
+        // Force a different mtime in case the rewrite happens within the same millisecond
+        let mut file = queries::get_content_files(&pool, "md/").unwrap().remove(0);
+        file.mtime -= 1000;
+        queries::upsert_content_file(&pool, &file).unwrap();
+        fs::write(dir.path().join("a.md"), "changed").unwrap();
+
+        let report = sync(&pool, &dir, &loads);
+
+        assert_eq!(report.updated, 1);
+        assert_eq!(loads.get(), 2);
+    }
+
+    #[test]
+    fn test_sync_skips_touched_but_identical_file() {
+        let pool = setup_test_db();
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.md"), "a").unwrap();
+        let loads = Cell::new(0);
+        sync(&pool, &dir, &loads);
+
+        let mut file = queries::get_content_files(&pool, "md/").unwrap().remove(0);
+        file.mtime -= 1000;
+        queries::upsert_content_file(&pool, &file).unwrap();
+
+        let report = sync(&pool, &dir, &loads);
+
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(loads.get(), 1);
+        // New modification time remembered
+        assert_ne!(queries::get_content_files(&pool, "md/").unwrap()[0].mtime, file.mtime);
+    }
+
+    #[test]
+    fn test_sync_forgets_removed_files() {
+        let pool = setup_test_db();
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.md"), "a").unwrap();
+        let loads = Cell::new(0);
+        sync(&pool, &dir, &loads);
+
+        fs::remove_file(dir.path().join("a.md")).unwrap();
+        let report = sync(&pool, &dir, &loads);
+
+        assert_eq!(report.removed, 1);
+        assert!(queries::get_content_files(&pool, "md/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sync_ignores_files_of_other_prefix() {
+        let pool = setup_test_db();
+        let image = ContentFile {
+            path: "img/a.png".to_string(),
+            mtime: 0,
+            hash: "x".to_string(),
+        };
+        queries::upsert_content_file(&pool, &image).unwrap();
+        let dir = TempDir::new().unwrap();
+
+        let report = sync(&pool, &dir, &Cell::new(0));
+
+        assert_eq!(report.removed, 0);
+        assert_eq!(queries::get_content_files(&pool, "img/").unwrap(), vec![image]);
+    }
+
+    #[test]
+    fn test_sync_retries_failed_file() {
+        let pool = setup_test_db();
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.md"), "a").unwrap();
+        let base_dir = dir.path().to_str().unwrap();
+        let files = vec![dir.path().join("a.md")];
+
+        let report = sync_files(&pool, base_dir, "md/", files, |_| None, |_| anyhow::bail!("parse error")).unwrap();
+
+        assert_eq!(report.added, 0);
+        assert!(queries::get_content_files(&pool, "md/").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+// Rust guideline compliant 2025-01
+//! Typed answers.
+//!
+//! A card may declare the short answer expected from the user on an
+//! `Expected:` line of its question part, either accepted alternatives
+//! separated by `|` or a `/regex/`:
+//!
+//! ```markdown
+//! Question : SQLite - Search - Which full-text search engine does SQLite ship?
+//! Expected: FTS5 | SQLite FTS5
+//! ```
+//!
+//! In typed-answer mode, the response typed before the reveal is compared to
+//! it, ignoring case, spacing and surrounding punctuation: the practice page
+//! shows a character-level diff against the closest alternative and suggests a grade.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use crate::scheduler::Grade;
+
+/// `Expected: <answer>` line inside a question (case-insensitive, on its own line).
+static EXPECTED_LINE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?mi)^[ \t]*Expected[ \t]*:[ \t]*(.*?)[ \t]*(?:\r?\n|$)").unwrap());
+
+/// Similarity (see [`similarity`]) from which a wrong response is a slip worth `Hard`.
+const HARD_SIMILARITY: f64 = 0.75;
+
+/// Longest normalized text diffed character by character (the diff is quadratic).
+const MAX_DIFF_CHARS: usize = 300;
+
+/// Characters ignored at both ends of an answer.
+const TRIMMED_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', '"', '\''];
+
+/// Answer expected from the user.
+#[derive(Debug, Clone)]
+pub enum ExpectedAnswer {
+    /// Accepted answers, compared after normalization (see [`normalize`]).
+    Alternatives(Vec<String>),
+    /// Pattern the trimmed response must match.
+    Pattern(Regex),
+}
+
+impl ExpectedAnswer {
+    /// Parses an `Expected:` value: a `/regex/`, else alternatives separated by `|`.
+    ///
+    /// Returns None if the value has no alternative.
+    ///
+    /// # Errors
+    /// Returns error if the regex is invalid.
+    pub fn parse(spec: &str) -> Result<Option<Self>, regex::Error> {
+        let spec = spec.trim();
+        if let Some(pattern) = spec.strip_prefix('/').and_then(|rest| rest.strip_suffix('/'))
+            && !pattern.is_empty()
+        {
+            return Regex::new(pattern).map(|regex| Some(ExpectedAnswer::Pattern(regex)));
+        }
+
+        let alternatives: Vec<String> =
+            spec.split('|').map(str::trim).filter(|alternative| !alternative.is_empty()).map(String::from).collect();
+        Ok((!alternatives.is_empty()).then_some(ExpectedAnswer::Alternatives(alternatives)))
+    }
+}
+
+/// Extracts an inline `Expected:` line from the question part of a card.
+///
+/// Returns its value (None if absent) and the question part with the line removed.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::typed_answer::extract_expected;
+///
+/// let (expected, question) = extract_expected("Which FTS engine does SQLite ship?\nExpected: FTS5");
+/// assert_eq!(expected.as_deref(), Some("FTS5"));
+/// assert_eq!(question, "Which FTS engine does SQLite ship?\n");
+/// ```
+pub fn extract_expected(question_part: &str) -> (Option<String>, String) {
+    match EXPECTED_LINE_REGEX.captures(question_part) {
+        Some(caps) => {
+            let value = caps.get(1).map_or("", |m| m.as_str()).to_string();
+            (Some(value), EXPECTED_LINE_REGEX.replace(question_part, "").into_owned())
+        }
+        None => (None, question_part.to_string()),
+    }
+}
+
+/// Normalizes an answer for comparison: lowercase, single spaces, without
+/// backticks and without punctuation or quotes at both ends.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::content::typed_answer::normalize;
+///
+/// assert_eq!(normalize("  The `Box<T>`  type. "), "the box<t> type");
+/// ```
+pub fn normalize(answer: &str) -> String {
+    let answer = answer.replace('`', "").to_lowercase();
+    let answer = answer.split_whitespace().collect::<Vec<_>>().join(" ");
+    answer.trim_matches(TRIMMED_PUNCTUATION).trim().to_string()
+}
+
+/// Part of a character-level diff, from the expected answer to the response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffSegment {
+    /// Text in both.
+    Same(String),
+    /// Expected text missing from the response.
+    Missing(String),
+    /// Response text that was not expected.
+    Extra(String),
+}
+
+/// Result of the comparison of a response with the expected answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnswerCheck {
+    /// Whether the response is accepted.
+    pub correct: bool,
+    /// Closest accepted answer (normalized), None for patterns.
+    pub closest: Option<String>,
+    /// Diff from `closest` to the normalized response (empty for patterns).
+    pub diff: Vec<DiffSegment>,
+    /// Grade suggested to the user: `Good` if correct, `Hard` for a slip, else `Again`.
+    pub suggested_grade: Grade,
+}
+
+/// Compares a typed response with the expected answer.
+pub fn check(expected: &ExpectedAnswer, response: &str) -> AnswerCheck {
+    let normalized = normalize(response);
+
+    match expected {
+        ExpectedAnswer::Pattern(regex) => {
+            let correct = !normalized.is_empty() && regex.is_match(response.trim());
+            AnswerCheck {
+                correct,
+                closest: None,
+                diff: Vec::new(),
+                suggested_grade: if correct { Grade::Good } else { Grade::Again },
+            }
+        }
+        ExpectedAnswer::Alternatives(alternatives) => {
+            let (closest, score) = alternatives
+                .iter()
+                .map(|alternative| normalize(alternative))
+                .map(|alternative| {
+                    let score = similarity(&alternative, &normalized);
+                    (alternative, score)
+                })
+                .fold((String::new(), -1.0), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+
+            let correct = closest == normalized;
+            let suggested_grade = if correct {
+                Grade::Good
+            } else if !normalized.is_empty() && score >= HARD_SIMILARITY {
+                Grade::Hard
+            } else {
+                Grade::Again
+            };
+            AnswerCheck { correct, diff: diff_chars(&closest, &normalized), closest: Some(closest), suggested_grade }
+        }
+    }
+}
+
+/// Similarity of two texts, from 0 (nothing in common) to 1 (equal):
+/// twice the length of their longest common subsequence over their total length.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let total = a.chars().count() + b.chars().count();
+    if total == 0 {
+        return 1.0;
+    }
+    let common: usize = diff_chars(a, b)
+        .iter()
+        .map(|segment| match segment {
+            DiffSegment::Same(text) => text.chars().count(),
+            _ => 0,
+        })
+        .sum();
+    2.0 * common as f64 / total as f64
+}
+
+/// Character-level diff from `expected` to `actual` (longest common subsequence).
+///
+/// Texts longer than [`MAX_DIFF_CHARS`] are not diffed: the whole of `expected`
+/// is missing and the whole of `actual` extra, unless they are equal.
+pub fn diff_chars(expected: &str, actual: &str) -> Vec<DiffSegment> {
+    let a: Vec<char> = expected.chars().collect();
+    let b: Vec<char> = actual.chars().collect();
+
+    let mut segments = Vec::new();
+    if a.len() > MAX_DIFF_CHARS || b.len() > MAX_DIFF_CHARS {
+        if expected == actual {
+            push_segment(&mut segments, DiffSegment::Same, expected.chars());
+        } else {
+            push_segment(&mut segments, DiffSegment::Missing, expected.chars());
+            push_segment(&mut segments, DiffSegment::Extra, actual.chars());
+        }
+        return segments;
+    }
+
+    // lengths[i][j]: longest common subsequence of a[i..] and b[j..]
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] =
+                if a[i] == b[j] { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            push_segment(&mut segments, DiffSegment::Same, [a[i]]);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            push_segment(&mut segments, DiffSegment::Extra, [b[j]]);
+            j += 1;
+        } else {
+            push_segment(&mut segments, DiffSegment::Missing, [a[i]]);
+            i += 1;
+        }
+    }
+    segments
+}
+
+/// Appends characters to the last segment if it has the same kind, else as a new segment.
+fn push_segment(segments: &mut Vec<DiffSegment>, kind: fn(String) -> DiffSegment, chars: impl IntoIterator<Item = char>) {
+    let text: String = chars.into_iter().collect();
+    if text.is_empty() {
+        return;
+    }
+    match (segments.last_mut(), kind(String::new())) {
+        (Some(DiffSegment::Same(last)), DiffSegment::Same(_))
+        | (Some(DiffSegment::Missing(last)), DiffSegment::Missing(_))
+        | (Some(DiffSegment::Extra(last)), DiffSegment::Extra(_)) => last.push_str(&text),
+        _ => segments.push(kind(text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn alternatives(spec: &str) -> ExpectedAnswer {
+        ExpectedAnswer::parse(spec).unwrap().unwrap()
+    }
+
+    #[rstest]
+    #[case("FTS5", &["FTS5"])]
+    #[case(" FTS5 | SQLite FTS5 |", &["FTS5", "SQLite FTS5"])]
+    #[case("/", &["/"])]
+    fn test_parse_alternatives(#[case] spec: &str, #[case] expected: &[&str]) {
+        match alternatives(spec) {
+            ExpectedAnswer::Alternatives(list) => assert_eq!(list, expected),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        assert!(matches!(ExpectedAnswer::parse("/^fts ?5$/").unwrap(), Some(ExpectedAnswer::Pattern(_))));
+        assert!(ExpectedAnswer::parse("/(unclosed/").is_err());
+        assert!(ExpectedAnswer::parse(" | ").unwrap().is_none());
+    }
+
+    #[rstest]
+    #[case("Q?\nExpected: FTS5 | fts 5\n", Some("FTS5 | fts 5"), "Q?\n")]
+    #[case("Q?\nexpected : /^a+$/", Some("/^a+$/"), "Q?\n")]
+    #[case("What is Expected: here?", None, "What is Expected: here?")]
+    fn test_extract_expected(#[case] question_part: &str, #[case] expected: Option<&str>, #[case] expected_rest: &str) {
+        assert_eq!(extract_expected(question_part), (expected.map(String::from), expected_rest.to_string()));
+    }
+
+    #[rstest]
+    #[case("FTS5", "fts5")]
+    #[case("  sqlite   FTS5. ", "sqlite fts5")]
+    #[case("\"`HashMap`\"!", "hashmap")]
+    #[case("...", "")]
+    fn test_normalize(#[case] answer: &str, #[case] expected: &str) {
+        assert_eq!(normalize(answer), expected);
+    }
+
+    #[rstest]
+    #[case("fts5", Grade::Good, true)]
+    #[case(" SQLite  FTS5.", Grade::Good, true)]
+    #[case("fts4", Grade::Hard, false)]
+    #[case("lucene", Grade::Again, false)]
+    #[case("", Grade::Again, false)]
+    fn test_check_alternatives(#[case] response: &str, #[case] grade: Grade, #[case] correct: bool) {
+        let result = check(&alternatives("FTS5 | SQLite FTS5"), response);
+
+        assert_eq!(result.suggested_grade, grade);
+        assert_eq!(result.correct, correct);
+    }
+
+    #[test]
+    fn test_check_diffs_against_closest_alternative() {
+        let result = check(&alternatives("FTS5 | SQLite FTS5"), "sqlite fts4");
+
+        assert_eq!(result.closest.as_deref(), Some("sqlite fts5"));
+        assert_eq!(
+            result.diff,
+            vec![
+                DiffSegment::Same("sqlite fts".to_string()),
+                DiffSegment::Extra("4".to_string()),
+                DiffSegment::Missing("5".to_string()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("FTS 5", true)]
+    #[case("fts5", false)]
+    fn test_check_pattern(#[case] response: &str, #[case] correct: bool) {
+        let result = check(&ExpectedAnswer::parse("/^FTS ?5$/").unwrap().unwrap(), response);
+
+        assert_eq!(result.correct, correct);
+        assert!(result.diff.is_empty());
+    }
+
+    #[rstest]
+    #[case("kitten", "sitting", vec![
+        DiffSegment::Extra("s".to_string()),
+        DiffSegment::Missing("k".to_string()),
+        DiffSegment::Same("itt".to_string()),
+        DiffSegment::Extra("i".to_string()),
+        DiffSegment::Missing("e".to_string()),
+        DiffSegment::Same("n".to_string()),
+        DiffSegment::Extra("g".to_string()),
+    ])]
+    #[case("abc", "abc", vec![DiffSegment::Same("abc".to_string())])]
+    #[case("", "ab", vec![DiffSegment::Extra("ab".to_string())])]
+    #[case("ab", "", vec![DiffSegment::Missing("ab".to_string())])]
+    fn test_diff_chars(#[case] expected: &str, #[case] actual: &str, #[case] diff: Vec<DiffSegment>) {
+        assert_eq!(diff_chars(expected, actual), diff);
+    }
+
+    #[test]
+    fn test_diff_chars_long_texts_are_not_diffed() {
+        let long = "a".repeat(MAX_DIFF_CHARS + 1);
+
+        assert_eq!(diff_chars(&long, "a"), vec![DiffSegment::Missing(long.clone()), DiffSegment::Extra("a".to_string())]);
+    }
+
+    #[rstest]
+    #[case("abc", "abc", 1.0)]
+    #[case("abcd", "abce", 0.75)]
+    #[case("abc", "xyz", 0.0)]
+    #[case("", "", 1.0)]
+    fn test_similarity(#[case] a: &str, #[case] b: &str, #[case] expected: f64) {
+        assert!((similarity(a, b) - expected).abs() < 1e-9);
+    }
+}
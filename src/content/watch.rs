@@ -0,0 +1,141 @@
+// Rust guideline compliant 2025-01
+//! Content watching for authoring (`--watch`).
+//!
+//! Watches the deck's markdown and image directories while the server runs and
+//! reloads changed files into the database and FTS index. Optionally notifies
+//! open browser tabs (live reload) when cards changed.
+
+use anyhow::Result;
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use super::{load_images, load_markdown, validate_content_directory, ContentDirStatus};
+use crate::db::connection::DbPool;
+use crate::db::queries;
+
+/// Delay grouping a burst of file events (e.g. an editor save) into one reload.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Reloads changed content files into the database and FTS index.
+///
+/// Unavailable directories are skipped. Returns true if some cards changed.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn reload_content(pool: &DbPool, md_path: &str, img_path: &str) -> Result<bool> {
+    let mut changed = false;
+
+    if validate_content_directory(md_path) == ContentDirStatus::Valid {
+        changed |= load_markdown(pool, md_path)?.has_changes();
+    }
+    if validate_content_directory(img_path) == ContentDirStatus::Valid {
+        changed |= load_images(pool, img_path)?.has_changes();
+    }
+
+    queries::populate_fts_table(pool)?;
+
+    Ok(changed)
+}
+
+/// Starts watching content directories, reloading changed files as they are saved.
+///
+/// After each reload that changed cards, a message is sent on `reload_tx` (if
+/// any) for live reload. Directories missing at startup are not watched.
+/// Watching stops when the returned watcher is dropped.
+///
+/// # Errors
+/// Returns error if the file system watcher cannot be created.
+pub fn watch_content(
+    pool: DbPool,
+    md_path: String,
+    img_path: String,
+    reload_tx: Option<broadcast::Sender<()>>,
+) -> Result<Debouncer<RecommendedWatcher, RecommendedCache>> {
+    let dirs = [md_path.clone(), img_path.clone()];
+
+    let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| match result {
+        Ok(events) => {
+            // Reading files during a reload reports access events: ignore them, or reloads never stop
+            let changes = events.iter().filter(|e| !matches!(e.kind, EventKind::Access(_))).count();
+            if changes == 0 {
+                return;
+            }
+
+            tracing::debug!("{} content file event(s), reloading", changes);
+            match reload_content(&pool, &md_path, &img_path) {
+                Ok(true) => {
+                    tracing::info!("Content reloaded");
+                    if let Some(tx) = &reload_tx {
+                        // No receiver just means no browser tab is open
+                        let _ = tx.send(());
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Failed to reload content: {:?}", e),
+            }
+        }
+        Err(errors) => tracing::warn!("Content watch errors: {:?}", errors),
+    })?;
+
+    for dir in &dirs {
+        if validate_content_directory(dir) == ContentDirStatus::Valid {
+            debouncer.watch(Path::new(dir), RecursiveMode::Recursive)?;
+            tracing::info!("Watching {} for changes", dir);
+        } else {
+            tracing::warn!("Not watching unavailable content directory: {}", dir);
+        }
+    }
+
+    Ok(debouncer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup_test_db() -> DbPool {
+        let manager = r2d2_sqlite::SqliteConnectionManager::memory();
+        let pool = r2d2::Pool::builder().max_size(1).build(manager).unwrap();
+        crate::db::init_database(&pool).unwrap();
+        pool
+    }
+
+    #[test]
+    fn test_reload_content_reports_changes() {
+        let pool = setup_test_db();
+        let md_dir = TempDir::new().unwrap();
+        let md_path = md_dir.path().to_str().unwrap();
+        fs::write(md_dir.path().join("a.md"), "Question : A - B - Q?\nAnswer : A").unwrap();
+
+        assert!(reload_content(&pool, md_path, "./missing/img").unwrap());
+        assert!(!reload_content(&pool, md_path, "./missing/img").unwrap());
+
+        fs::write(md_dir.path().join("b.md"), "Question : A - B - Q2?\nAnswer : A").unwrap();
+        assert!(reload_content(&pool, md_path, "./missing/img").unwrap());
+        assert_eq!(queries::get_total_count(&pool).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_watch_content_reloads_saved_file() {
+        let pool = setup_test_db();
+        let md_dir = TempDir::new().unwrap();
+        let md_path = md_dir.path().to_str().unwrap().to_string();
+        let (tx, mut rx) = broadcast::channel(4);
+
+        let _watcher = watch_content(pool.clone(), md_path, "./missing/img".to_string(), Some(tx)).unwrap();
+        fs::write(md_dir.path().join("a.md"), "Question : A - B - Q?\nAnswer : A").unwrap();
+
+        // Wait for the debounced reload
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        while rx.try_recv().is_err() {
+            assert!(std::time::Instant::now() < deadline, "no reload event received");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(queries::get_total_count(&pool).unwrap(), 1);
+    }
+}
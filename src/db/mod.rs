@@ -2,6 +2,7 @@ pub mod connection;
 pub mod models;
 pub mod queries;
 pub mod schema;
+pub mod search;
 
 // Re-export commonly used items
 pub use connection::create_pool; //, DbPool};
@@ -1,18 +1,156 @@
 // Rust guideline compliant 2024-01
 use serde::{Deserialize, Serialize};
 
+use crate::scheduler::Grade;
+
+/// Kind of a flashcard, stored in the `kind` column of `flashcards`.
+///
+/// Set by the content loader that created the card.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardKind {
+    /// Question and answer from a markdown file.
+    #[default]
+    Markdown,
+    /// Image file shown as the answer, without question.
+    Image,
+    /// One cloze index of a markdown `Cloze :` block.
+    Cloze,
+    /// Markdown card with question and answer swapped (`Reverse: true`).
+    Reversed,
+}
+
+impl CardKind {
+    /// Name stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CardKind::Markdown => "markdown",
+            CardKind::Image => "image",
+            CardKind::Cloze => "cloze",
+            CardKind::Reversed => "reversed",
+        }
+    }
+
+    /// Parses a stored kind name. Returns None for unknown kinds.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "markdown" => Some(CardKind::Markdown),
+            "image" => Some(CardKind::Image),
+            "cloze" => Some(CardKind::Cloze),
+            "reversed" => Some(CardKind::Reversed),
+            _ => None,
+        }
+    }
+}
+
 /// Flashcard data model.
 ///
 /// Represents a single flashcard with question, answer, and optional categorization.
+/// `id` is reassigned on every content load; `card_key` is the stable identity
+/// used by per-card state.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Flashcard {
     pub id: i64,
+    pub card_key: String,
     pub category: Option<String>,
     pub subcategory: Option<String>,
+    /// Difficulty rated by the author (`easy`, `medium` or `hard`), if any.
+    pub difficulty: Option<String>,
+    /// How the card was created, and so how it is practiced.
+    pub kind: CardKind,
     pub question_html: String,
     pub answer_html: String,
+    /// Whether the question has text beyond its header (image cards may have none).
+    pub has_question: bool,
+    /// Thumbnail `<img>` tag of image cards.
+    pub thumbnail_html: Option<String>,
+}
+
+/// Marks the start of a matched term in [`SearchHit`] excerpts.
+pub const MATCH_START: char = '\u{2}';
+/// Marks the end of a matched term in [`SearchHit`] excerpts.
+pub const MATCH_END: char = '\u{3}';
+
+/// Flashcard matching a search, with excerpts of its text.
+///
+/// Excerpts are plain text in which matched terms are enclosed in
+/// [`MATCH_START`] and [`MATCH_END`], to be highlighted once escaped.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub card: Flashcard,
+    /// Whole question text.
+    pub question_excerpt: String,
+    /// Part of the answer text around the best matches.
+    pub answer_excerpt: String,
+}
+
+/// Flashcard to insert, as produced by content loaders.
+#[derive(Debug, Clone, Copy)]
+pub struct NewFlashcard<'a> {
+    /// Stable identity (explicit `ID:` or content hash), unique in the deck.
+    pub card_key: &'a str,
+    /// Content file the card comes from (see [`ContentFile::path`]).
+    pub source_path: &'a str,
+    pub category: Option<&'a str>,
+    pub subcategory: Option<&'a str>,
+    pub difficulty: Option<&'a str>,
+    pub kind: CardKind,
+    /// Key shared by the cards of one source block (reversed pair, cloze indexes),
+    /// None for a card alone in its block.
+    pub sibling_key: Option<&'a str>,
+    /// `Expected:` answer of typed-answer mode, as declared (see [`crate::content::typed_answer`]).
+    pub expected_answer: Option<&'a str>,
+    /// Tags (normalized), stored in the `card_tags` table.
+    pub tags: &'a [String],
+    /// Hints (HTML) in reveal order, stored in the `card_hints` table.
+    pub hints_html: &'a [String],
+    /// Wrong options of the card's quiz question (HTML), stored in the `card_distractors` table.
+    pub distractors_html: &'a [String],
+    pub question_html: &'a str,
+    pub answer_html: &'a str,
+    /// Plain text of the question, indexed for search (HTML is only displayed).
+    pub question_text: &'a str,
+    /// Plain text of the answer, indexed for search.
+    pub answer_text: &'a str,
+    /// Thumbnail `<img>` tag of image cards.
+    pub thumbnail_html: Option<&'a str>,
+}
+
+/// Content file loaded into the database, as tracked in the `content_files` table.
+///
+/// Lets startup re-parse only the files that changed since the last load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentFile {
+    /// Path relative to the deck directory (e.g. `md/rust/ownership.md`, `img/chart.webp`).
+    pub path: String,
+    /// Last modification time when loaded, in Unix milliseconds
+    /// (summed with the one of its sidecar file, if any).
+    pub mtime: i64,
+    /// SHA-256 of the file content when loaded, followed by its sidecar's (hex).
+    pub hash: String,
+}
+
+/// A subcategory together with its parent category.
+///
+/// Subcategory names are reused across categories ("Basics" of "Rust" and of
+/// "Python"), so a subcategory is only identified by both names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subcategory {
+    /// Parent category name.
+    pub category: String,
+    /// Subcategory name.
+    pub name: String,
 }
 
+impl Subcategory {
+    /// Creates the subcategory `name` of `category`.
+    pub fn new(category: &str, name: &str) -> Self {
+        Self {
+            category: category.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
 
 /// Filter criteria for flashcard queries.
 ///
@@ -24,18 +162,123 @@ pub struct Flashcard {
 /// let criteria = FilterCriteria {
 ///     keywords: vec!["rust".to_string(), "async".to_string()],
 ///     categories: Some(vec!["Programming".to_string()]),
-///     subcategories: None,  // All subcategories
+///     subcategories: Some(vec![Subcategory::new("Programming", "Rust")]),
+///     tags: vec!["async".to_string(), "tokio".to_string()],
+///     tag_match: TagMatch::Any,
 ///     include_images: true,
 /// };
 /// ```
 #[derive(Debug, Clone)]
 pub struct FilterCriteria {
-    /// Keywords for FTS5 full-text search (AND logic).
+    /// Search query words, joined with spaces and parsed by [`crate::db::search`].
     pub keywords: Vec<String>,
-    /// Selected categories (None = all categories).
+    /// Selected categories (None = all categories, empty = image cards only, whatever their category).
     pub categories: Option<Vec<String>>,
     /// Selected subcategories (None = all subcategories).
-    pub subcategories: Option<Vec<String>>,
+    pub subcategories: Option<Vec<Subcategory>>,
+    /// Selected tags (empty = no tag filter).
+    pub tags: Vec<String>,
+    /// Whether cards need all selected tags or any of them.
+    pub tag_match: TagMatch,
     /// Whether to include image-only flashcards.
     pub include_images: bool,
-}
\ No newline at end of file
+}
+
+/// How selected tags combine when filtering flashcards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Cards having at least one of the selected tags (OR).
+    #[default]
+    Any,
+    /// Cards having every selected tag (AND).
+    All,
+}
+
+/// Order of flashcards within a category/subcategory group of the deck listing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardOrder {
+    /// Order of the cards in the content files.
+    #[default]
+    Source,
+    /// Alphabetical order of the questions.
+    Question,
+    /// Easy cards first, cards without difficulty last.
+    Difficulty,
+}
+
+/// Spaced repetition state of a single flashcard.
+///
+/// One row per card in the `reviews` table. Cards without a row have never
+/// been graded and are considered new (due immediately).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewState {
+    /// Stable key of the reviewed flashcard.
+    pub card_key: String,
+    /// SM-2 ease factor (2.5 for a new card, never below 1.3).
+    pub ease: f64,
+    /// Current interval between reviews, in days (0 while relearning).
+    pub interval_days: i64,
+    /// Consecutive successful reviews since the last lapse.
+    pub repetitions: i64,
+    /// Number of times the card was forgotten after being learned.
+    pub lapses: i64,
+    /// Unix timestamp (seconds) at which the card is due again.
+    pub due_at: i64,
+    /// Unix timestamp (seconds) of the last review.
+    pub last_reviewed_at: i64,
+    /// FSRS memory stability in days (0 until first scheduled with FSRS).
+    pub stability: f64,
+    /// FSRS difficulty between 1 and 10 (0 until first scheduled with FSRS).
+    pub difficulty: f64,
+}
+
+/// Single graded review, as recorded in the `review_log` table.
+///
+/// Append-only history of every grade given, kept independently of the
+/// scheduling state so statistics survive session and scheduler changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewLogEntry {
+    /// Stable key of the reviewed flashcard.
+    pub card_key: String,
+    /// Unix timestamp (seconds) of the review.
+    pub reviewed_at: i64,
+    /// Grade given to the card.
+    pub grade: Grade,
+    /// Milliseconds between card display and answer reveal (None if not measured).
+    pub time_to_reveal_ms: Option<i64>,
+    /// Milliseconds between card display and grading (None if not measured).
+    pub time_on_card_ms: Option<i64>,
+    /// Number of hints shown before grading (None if not measured).
+    pub hints_used: Option<i64>,
+}
+
+/// Number of items for a single calendar day (UTC).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyCount {
+    /// Day formatted as YYYY-MM-DD.
+    pub day: String,
+    /// Number of reviews done (or cards due) that day.
+    pub count: i64,
+}
+
+/// Review outcomes of the cards of one category/subcategory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionStats {
+    /// Category name (None for cards without category, markdown or image).
+    pub category: Option<String>,
+    /// Subcategory name (None if absent).
+    pub subcategory: Option<String>,
+    /// Number of reviews logged.
+    pub reviews: i64,
+    /// Number of reviews graded other than `Again`.
+    pub recalled: i64,
+}
+
+impl RetentionStats {
+    /// Returns the share of successful reviews in percent, or None without reviews.
+    pub fn retention_percent(&self) -> Option<f64> {
+        (self.reviews > 0).then(|| 100.0 * self.recalled as f64 / self.reviews as f64)
+    }
+}
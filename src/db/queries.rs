@@ -1,28 +1,175 @@
 use anyhow::{Context, Result};
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, OptionalExtension, ToSql};
 
 use super::connection::DbPool;
-use super::models::Flashcard;
+use super::models::{
+    CardKind, CardOrder, ContentFile, DailyCount, FilterCriteria, Flashcard, MATCH_END, MATCH_START, NewFlashcard, RetentionStats,
+    ReviewLogEntry, ReviewState, SearchHit, TagMatch,
+};
+use super::search;
 
 /// Insert a flashcard into the database
 ///
 /// Note: FTS table sync happens via `populate_fts_table()` after all inserts complete.
-pub fn insert_flashcard(
-    pool: &DbPool,
-    category: Option<&str>,
-    subcategory: Option<&str>,
-    question_html: &str,
-    answer_html: &str,
-) -> Result<i64> {
+/// `source_path` ties the card to its content file for incremental reloads.
+/// Tags are stored in `card_tags`, creating missing ones in `tags`, hints in
+/// `card_hints` and distractors in `card_distractors`.
+/// Fails if a card with the same `card_key` already exists.
+pub fn insert_flashcard(pool: &DbPool, card: &NewFlashcard) -> Result<i64> {
     let conn = pool.get().context("Failed to get DB connection")?;
 
     conn.execute(
-        "INSERT INTO flashcards (category, subcategory, question_html, answer_html) VALUES (?1, ?2, ?3, ?4)",
-        params![category, subcategory, question_html, answer_html],
+        "INSERT INTO flashcards (card_key, source_path, category, subcategory, difficulty, kind, sibling_key,
+                                 expected_answer, question_html, answer_html, question_text, answer_text, thumbnail_html)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            card.card_key,
+            card.source_path,
+            card.category,
+            card.subcategory,
+            card.difficulty,
+            card.kind.as_str(),
+            card.sibling_key,
+            card.expected_answer,
+            card.question_html,
+            card.answer_html,
+            card.question_text,
+            card.answer_text,
+            card.thumbnail_html
+        ],
     )
-    .context("Failed to insert flashcard")?;
+    .with_context(|| format!("Failed to insert flashcard {}", card.card_key))?;
+    let id = conn.last_insert_rowid();
+
+    for tag in card.tags {
+        conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])
+            .with_context(|| format!("Failed to insert tag {}", tag))?;
+        conn.execute(
+            "INSERT OR IGNORE INTO card_tags (card_key, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+            params![card.card_key, tag],
+        )
+        .with_context(|| format!("Failed to tag flashcard {}", card.card_key))?;
+    }
+
+    for (position, hint_html) in card.hints_html.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO card_hints (card_key, position, hint_html) VALUES (?1, ?2, ?3)",
+            params![card.card_key, position as i64, hint_html],
+        )
+        .with_context(|| format!("Failed to insert hint of flashcard {}", card.card_key))?;
+    }
+
+    for (position, distractor_html) in card.distractors_html.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO card_distractors (card_key, position, distractor_html) VALUES (?1, ?2, ?3)",
+            params![card.card_key, position as i64, distractor_html],
+        )
+        .with_context(|| format!("Failed to insert distractor of flashcard {}", card.card_key))?;
+    }
+
+    Ok(id)
+}
+
+/// Returns true if a flashcard with the given key is loaded.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn card_key_exists(pool: &DbPool, card_key: &str) -> Result<bool> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let exists = conn
+        .prepare("SELECT 1 FROM flashcards WHERE card_key = ?1")?
+        .exists([card_key])
+        .context("Failed to query card key")?;
+
+    Ok(exists)
+}
+
+/// Returns true if some flashcards were loaded before stable card keys existed.
+///
+/// Such databases must be reloaded from content to get card keys.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn has_cards_without_key(pool: &DbPool) -> Result<bool> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let exists = conn
+        .prepare("SELECT 1 FROM flashcards WHERE card_key IS NULL")?
+        .exists([])
+        .context("Failed to query cards without key")?;
+
+    Ok(exists)
+}
+
+/// Returns true if some flashcards were loaded before their plain text was stored.
+///
+/// Such databases must be reloaded from content for their cards to be searchable.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn has_cards_without_text(pool: &DbPool) -> Result<bool> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let exists = conn
+        .prepare("SELECT 1 FROM flashcards WHERE question_text IS NULL")?
+        .exists([])
+        .context("Failed to query cards without text")?;
+
+    Ok(exists)
+}
+
+/// Returns true if some flashcards were loaded before their kind was stored.
+///
+/// Such databases must be reloaded from content to tell image cards apart.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn has_cards_without_kind(pool: &DbPool) -> Result<bool> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let exists = conn
+        .prepare("SELECT 1 FROM flashcards WHERE kind IS NULL")?
+        .exists([])
+        .context("Failed to query cards without kind")?;
+
+    Ok(exists)
+}
+
+/// Returns true if some image cards were loaded before their file name was indexed.
+///
+/// Such databases must be reloaded from content for image cards to get their
+/// searchable text and categories (unchanged images are not loaded again).
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn has_image_cards_without_text(pool: &DbPool) -> Result<bool> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let exists = conn
+        .prepare("SELECT 1 FROM flashcards WHERE kind = 'image' AND trim(answer_text) = ''")?
+        .exists([])
+        .context("Failed to query image cards without text")?;
+
+    Ok(exists)
+}
+
+/// Returns true if some image cards were loaded before their web variants were made.
+///
+/// Such databases must be reloaded from content for image cards to get their
+/// dimensions, `srcset` and thumbnail.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn has_image_cards_without_thumbnail(pool: &DbPool) -> Result<bool> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let exists = conn
+        .prepare("SELECT 1 FROM flashcards WHERE kind = 'image' AND thumbnail_html IS NULL")?
+        .exists([])
+        .context("Failed to query image cards without thumbnail")?;
 
-    Ok(conn.last_insert_rowid())
+    Ok(exists)
 }
 
 /// Clear all flashcards from both tables
@@ -35,17 +182,172 @@ pub fn clear_flashcards(pool: &DbPool) -> Result<()> {
     conn.execute("DELETE FROM flashcards_fts", [])
         .context("Failed to clear flashcards_fts table")?;
 
+    conn.execute("DELETE FROM card_tags", [])
+        .context("Failed to clear card_tags table")?;
+
+    conn.execute("DELETE FROM card_hints", [])
+        .context("Failed to clear card_hints table")?;
+
+    conn.execute("DELETE FROM card_distractors", [])
+        .context("Failed to clear card_distractors table")?;
+
+    conn.execute("DELETE FROM tags", [])
+        .context("Failed to clear tags table")?;
+
+    // Forget loaded files so the next load parses everything again
+    conn.execute("DELETE FROM content_files", [])
+        .context("Failed to clear content_files table")?;
+
     tracing::info!("Cleared all flashcards");
     Ok(())
 }
 
-/// Populate FTS table from main flashcards table
+/// Deletes the flashcards loaded from a content file, and their FTS rows, tags, hints and distractors.
+///
+/// Returns the number of flashcards deleted.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn delete_flashcards_from_source(pool: &DbPool, source_path: &str) -> Result<usize> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    conn.execute(
+        "DELETE FROM flashcards_fts WHERE id IN (SELECT id FROM flashcards WHERE source_path = ?1)",
+        [source_path],
+    )
+    .context("Failed to delete FTS rows")?;
+
+    conn.execute(
+        "DELETE FROM card_tags WHERE card_key IN (SELECT card_key FROM flashcards WHERE source_path = ?1)",
+        [source_path],
+    )
+    .context("Failed to delete card tags")?;
+
+    conn.execute(
+        "DELETE FROM card_hints WHERE card_key IN (SELECT card_key FROM flashcards WHERE source_path = ?1)",
+        [source_path],
+    )
+    .context("Failed to delete card hints")?;
+
+    conn.execute(
+        "DELETE FROM card_distractors WHERE card_key IN (SELECT card_key FROM flashcards WHERE source_path = ?1)",
+        [source_path],
+    )
+    .context("Failed to delete card distractors")?;
+
+    let deleted = conn
+        .execute("DELETE FROM flashcards WHERE source_path = ?1", [source_path])
+        .with_context(|| format!("Failed to delete flashcards from {}", source_path))?;
+
+    Ok(deleted)
+}
+
+/// Deletes flashcards not attached to any content file, and their FTS rows, tags, hints and distractors.
+///
+/// Such cards cannot be kept in sync with content and are dropped on load.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn delete_flashcards_without_source(pool: &DbPool) -> Result<usize> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    conn.execute(
+        "DELETE FROM flashcards_fts WHERE id IN (SELECT id FROM flashcards WHERE source_path IS NULL)",
+        [],
+    )
+    .context("Failed to delete FTS rows")?;
+
+    conn.execute(
+        "DELETE FROM card_tags WHERE card_key IN (SELECT card_key FROM flashcards WHERE source_path IS NULL)",
+        [],
+    )
+    .context("Failed to delete card tags")?;
+
+    conn.execute(
+        "DELETE FROM card_hints WHERE card_key IN (SELECT card_key FROM flashcards WHERE source_path IS NULL)",
+        [],
+    )
+    .context("Failed to delete card hints")?;
+
+    conn.execute(
+        "DELETE FROM card_distractors WHERE card_key IN (SELECT card_key FROM flashcards WHERE source_path IS NULL)",
+        [],
+    )
+    .context("Failed to delete card distractors")?;
+
+    let deleted = conn
+        .execute("DELETE FROM flashcards WHERE source_path IS NULL", [])
+        .context("Failed to delete flashcards without source")?;
+
+    Ok(deleted)
+}
+
+/// Retrieves tracked content files whose path starts with `prefix` (e.g. `md/`).
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_content_files(pool: &DbPool, prefix: &str) -> Result<Vec<ContentFile>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare("SELECT path, mtime, hash FROM content_files WHERE substr(path, 1, length(?1)) = ?1 ORDER BY path")
+        .context("Failed to prepare content files query")?;
+
+    let files = stmt
+        .query_map([prefix], |row| {
+            Ok(ContentFile {
+                path: row.get(0)?,
+                mtime: row.get(1)?,
+                hash: row.get(2)?,
+            })
+        })
+        .context("Failed to query content files")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect content files")?;
+
+    Ok(files)
+}
+
+/// Inserts or replaces a tracked content file.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn upsert_content_file(pool: &DbPool, file: &ContentFile) -> Result<()> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO content_files (path, mtime, hash) VALUES (?1, ?2, ?3)",
+        params![file.path, file.mtime, file.hash],
+    )
+    .with_context(|| format!("Failed to save content file {}", file.path))?;
+
+    Ok(())
+}
+
+/// Stops tracking a content file.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn delete_content_file(pool: &DbPool, path: &str) -> Result<()> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    conn.execute("DELETE FROM content_files WHERE path = ?1", [path])
+        .with_context(|| format!("Failed to delete content file {}", path))?;
+
+    Ok(())
+}
+
+/// Indexes in the FTS table the plain text of the flashcards not indexed yet.
+///
+/// Safe to call after every load: flashcard ids are never reused, so
+/// already indexed cards are skipped.
 pub fn populate_fts_table(pool: &DbPool) -> Result<()> {
     let conn = pool.get().context("Failed to get DB connection")?;
 
     conn.execute(
-        "INSERT INTO flashcards_fts (id, category, subcategory, question_html, answer_html)
-         SELECT id, category, subcategory, question_html, answer_html FROM flashcards",
+        "INSERT INTO flashcards_fts (id, category, subcategory, question, answer)
+         SELECT id, category, subcategory, question_text, answer_text FROM flashcards
+         WHERE id NOT IN (SELECT id FROM flashcards_fts)",
         [],
     )
     .context("Failed to populate FTS table")?;
@@ -99,6 +401,177 @@ pub fn get_distinct_categories(pool: &DbPool) -> Result<Vec<String>> {
     Ok(categories)
 }
 
+/// Retrieves tags of loaded flashcards with their number of cards.
+///
+/// Returns (tag, card count) tuples sorted by tag.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_tag_counts(pool: &DbPool) -> Result<Vec<(String, i64)>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tags.name, COUNT(*) FROM card_tags
+             JOIN tags ON tags.id = card_tags.tag_id
+             GROUP BY tags.name
+             ORDER BY tags.name",
+        )
+        .context("Failed to prepare tag query")?;
+
+    let tags = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to query tags")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect tags")?;
+
+    Ok(tags)
+}
+
+/// Retrieves the keys of the other cards of a flashcard's source block (see [`NewFlashcard::sibling_key`]).
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_sibling_keys(pool: &DbPool, card_key: &str) -> Result<Vec<String>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT siblings.card_key FROM flashcards
+             JOIN flashcards AS siblings ON siblings.sibling_key = flashcards.sibling_key
+             WHERE flashcards.card_key = ?1 AND siblings.card_key != ?1
+             ORDER BY siblings.id",
+        )
+        .context("Failed to prepare sibling query")?;
+
+    let keys = stmt
+        .query_map([card_key], |row| row.get(0))
+        .context("Failed to query sibling cards")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect sibling cards")?;
+
+    Ok(keys)
+}
+
+/// Retrieves the `Expected:` answer of a flashcard, as declared (see [`NewFlashcard::expected_answer`]).
+///
+/// Returns None if the card has none or does not exist.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_expected_answer(pool: &DbPool, card_key: &str) -> Result<Option<String>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let expected = conn
+        .query_row("SELECT expected_answer FROM flashcards WHERE card_key = ?1", [card_key], |row| row.get(0))
+        .optional()
+        .context("Failed to query expected answer")?;
+
+    Ok(expected.flatten())
+}
+
+/// Retrieves the distractors declared by a flashcard (HTML), in declaration order.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_card_distractors(pool: &DbPool, card_key: &str) -> Result<Vec<String>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare("SELECT distractor_html FROM card_distractors WHERE card_key = ?1 ORDER BY position")
+        .context("Failed to prepare card distractor query")?;
+
+    let distractors = stmt
+        .query_map([card_key], |row| row.get(0))
+        .context("Failed to query card distractors")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect card distractors")?;
+
+    Ok(distractors)
+}
+
+/// Draws up to `limit` answers (HTML) of other cards of a flashcard's category
+/// and subcategory, as wrong options of its quiz question, in random order.
+///
+/// Only cards of the same kind are drawn (a reversed card gets questions, a
+/// cloze other clozes), never its siblings nor cards with the same answer text.
+/// Uncategorized cards get none.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_random_distractors(pool: &DbPool, card_key: &str, limit: usize) -> Result<Vec<String>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT other.answer_html FROM flashcards AS card
+             JOIN flashcards AS other
+               ON other.category = card.category
+              AND other.subcategory IS card.subcategory
+              AND other.kind IS card.kind
+              AND other.card_key != card.card_key
+              AND (card.sibling_key IS NULL OR other.sibling_key IS NOT card.sibling_key)
+              AND other.answer_text != card.answer_text
+             WHERE card.card_key = ?1
+             GROUP BY other.answer_text
+             ORDER BY RANDOM() LIMIT ?2",
+        )
+        .context("Failed to prepare distractor query")?;
+
+    let distractors = stmt
+        .query_map(params![card_key, limit as i64], |row| row.get(0))
+        .context("Failed to query distractors")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect distractors")?;
+
+    Ok(distractors)
+}
+
+/// Retrieves the hints of a flashcard (HTML), in reveal order.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_card_hints(pool: &DbPool, card_key: &str) -> Result<Vec<String>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare("SELECT hint_html FROM card_hints WHERE card_key = ?1 ORDER BY position")
+        .context("Failed to prepare card hint query")?;
+
+    let hints = stmt
+        .query_map([card_key], |row| row.get(0))
+        .context("Failed to query card hints")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect card hints")?;
+
+    Ok(hints)
+}
+
+/// Retrieves the tags of a flashcard, sorted.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_card_tags(pool: &DbPool, card_key: &str) -> Result<Vec<String>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tags.name FROM card_tags
+             JOIN tags ON tags.id = card_tags.tag_id
+             WHERE card_tags.card_key = ?1
+             ORDER BY tags.name",
+        )
+        .context("Failed to prepare card tag query")?;
+
+    let tags = stmt
+        .query_map([card_key], |row| row.get(0))
+        .context("Failed to query card tags")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect card tags")?;
+
+    Ok(tags)
+}
+
 /// Retrieves distinct subcategories with their parent categories.
 ///
 /// Returns tuples of (subcategory_name, parent_category_name) to enable
@@ -143,22 +616,21 @@ pub fn get_distinct_subcategories(
     Ok(subcategories)
 }
 
-/// Counts flashcards matching filter criteria.
+/// Appends SQL clauses (and their parameters) implementing filter criteria.
+///
+/// Each clause starts with `AND` so it can follow a `WHERE 1=1` prefix.
+/// Column names are unqualified and refer to the `flashcards` table.
 ///
 /// # Errors
-/// Returns error if database query fails.
-pub fn count_filtered_flashcards(
-    pool: &DbPool,
-    filters: &super::models::FilterCriteria,
-) -> Result<i64> {
-    let conn = pool.get().context("Failed to get DB connection")?;
-
-    let mut query_parts = vec!["SELECT COUNT(*) FROM flashcards WHERE 1=1".to_string()];
-    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
+/// Returns error if the keywords are not a valid search query.
+fn push_filter_clauses(
+    filters: &FilterCriteria,
+    query_parts: &mut Vec<String>,
+    params: &mut Vec<Box<dyn ToSql>>,
+) -> Result<()> {
     // Keywords filter (FTS5 subquery)
-    if !filters.keywords.is_empty() {
-        let match_query = filters.keywords.join(" AND ");
+    let match_query = search::compile(&filters.keywords.join(" ")).context("Invalid search query")?;
+    if let Some(match_query) = match_query {
         query_parts.push("AND id IN (SELECT id FROM flashcards_fts WHERE flashcards_fts MATCH ?)".to_string());
         params.push(Box::new(match_query));
     }
@@ -166,8 +638,8 @@ pub fn count_filtered_flashcards(
     // Category filter
     if let Some(ref cats) = filters.categories {
         if cats.is_empty() {
-            // Empty vec means "no categories" - only match image-only cards
-            query_parts.push("AND category IS NULL".to_string());
+            // Empty vec means "no categories" - only match image cards, which may have a category
+            query_parts.push("AND kind = 'image'".to_string());
         } else {
             let placeholders = cats.iter().map(|_| "?").collect::<Vec<_>>().join(",");
             query_parts.push(format!("AND category IN ({})", placeholders));
@@ -177,99 +649,366 @@ pub fn count_filtered_flashcards(
         }
     }
 
-    // Subcategory filter
-    if let Some(ref subcats) = filters.subcategories {
-        if !subcats.is_empty() {
-            let placeholders = subcats.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-            query_parts.push(format!("AND subcategory IN ({})", placeholders));
-            for subcat in subcats {
-                params.push(Box::new(subcat.clone()));
-            }
+    // Subcategory filter, matching (category, subcategory) pairs: names are reused across categories
+    if let Some(ref subcats) = filters.subcategories
+        && !subcats.is_empty()
+    {
+        let placeholders = subcats.iter().map(|_| "(?, ?)").collect::<Vec<_>>().join(",");
+        query_parts.push(format!("AND (category, subcategory) IN (VALUES {})", placeholders));
+        for subcat in subcats {
+            params.push(Box::new(subcat.category.clone()));
+            params.push(Box::new(subcat.name.clone()));
+        }
+    }
+
+    // Tag filter: cards having any selected tag, or all of them
+    if !filters.tags.is_empty() {
+        // Repeated tags would make the "all" count unreachable
+        let mut tags: Vec<&String> = filters.tags.iter().collect();
+        tags.sort();
+        tags.dedup();
+        let placeholders = tags.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let having = match filters.tag_match {
+            TagMatch::Any => String::new(),
+            TagMatch::All => format!(" GROUP BY card_tags.card_key HAVING COUNT(*) = {}", tags.len()),
+        };
+        query_parts.push(format!(
+            "AND flashcards.card_key IN (SELECT card_tags.card_key FROM card_tags JOIN tags ON tags.id = card_tags.tag_id WHERE tags.name IN ({}){})",
+            placeholders, having
+        ));
+        for tag in tags {
+            params.push(Box::new(tag.clone()));
         }
     }
 
     // Image filter
     if !filters.include_images {
-        query_parts.push("AND question_html != '<h3>Question:</h3>'".to_string());
+        query_parts.push("AND kind != 'image'".to_string());
     }
 
-    let query = query_parts.join(" ");
-
-    let count: i64 = conn
-        .query_row(
-            &query,
-            rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
-            |row| row.get(0),
-        )
-        .context("Failed to count filtered flashcards")?;
-
-    Ok(count)
+    Ok(())
+}
+
+/// Appends a `card_key NOT IN (...)` clause excluding the given card keys.
+fn push_exclude_clause(exclude: &[String], query_parts: &mut Vec<String>, params: &mut Vec<Box<dyn ToSql>>) {
+    if !exclude.is_empty() {
+        let placeholders = exclude.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        query_parts.push(format!("AND flashcards.card_key NOT IN ({})", placeholders));
+        for key in exclude {
+            params.push(Box::new(key.clone()));
+        }
+    }
+}
+
+/// Columns read by [`flashcard_from_row`].
+const FLASHCARD_COLUMNS: &str = "flashcards.id, flashcards.card_key, flashcards.category, flashcards.subcategory, \
+                                 flashcards.difficulty, flashcards.kind, flashcards.question_html, flashcards.answer_html, \
+                                 flashcards.question_text <> '', flashcards.thumbnail_html";
+
+/// Maps a [`FLASHCARD_COLUMNS`] row to a flashcard.
+fn flashcard_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Flashcard> {
+    Ok(Flashcard {
+        id: row.get(0)?,
+        card_key: row.get(1)?,
+        category: row.get(2)?,
+        subcategory: row.get(3)?,
+        difficulty: row.get(4)?,
+        kind: row.get::<_, Option<String>>(5)?.as_deref().and_then(CardKind::parse).unwrap_or_default(),
+        question_html: row.get(6)?,
+        answer_html: row.get(7)?,
+        has_question: row.get(8)?,
+        thumbnail_html: row.get(9)?,
+    })
+}
+
+/// Maps a `day, count` row to a daily count.
+fn daily_count_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DailyCount> {
+    Ok(DailyCount {
+        day: row.get(0)?,
+        count: row.get(1)?,
+    })
+}
+
+/// Counts flashcards matching filter criteria.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn count_filtered_flashcards(pool: &DbPool, filters: &FilterCriteria) -> Result<i64> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut query_parts = vec!["SELECT COUNT(*) FROM flashcards WHERE 1=1".to_string()];
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    push_filter_clauses(filters, &mut query_parts, &mut params)?;
+
+    let query = query_parts.join(" ");
+
+    let count: i64 = conn
+        .query_row(
+            &query,
+            rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+            |row| row.get(0),
+        )
+        .context("Failed to count filtered flashcards")?;
+
+    Ok(count)
+}
+
+/// Retrieves a flashcard by its stable key. Returns None if no card has this key.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_flashcard_by_key(pool: &DbPool, card_key: &str) -> Result<Option<Flashcard>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let card = conn
+        .query_row(
+            &format!("SELECT {} FROM flashcards WHERE card_key = ?1", FLASHCARD_COLUMNS),
+            [card_key],
+            flashcard_from_row,
+        )
+        .optional()
+        .context("Failed to query flashcard by key")?;
+
+    Ok(card)
 }
 
 /// Retrieves random flashcard matching filter criteria, excluding specified IDs.
 ///
-/// Combines all filter criteria (keywords, categories, subcategories, images) with
-/// AND logic. Returns None if no matching cards found.
+/// Combines all filter criteria (keywords, categories, subcategories, tags, images)
+/// with AND logic. Returns None if no matching cards found.
 ///
 /// # Errors
 /// Returns error if database query fails.
 pub fn get_filtered_random_flashcard(
     pool: &DbPool,
-    exclude: &[i64],
-    filters: &super::models::FilterCriteria,
+    exclude: &[String],
+    filters: &FilterCriteria,
 ) -> Result<Option<Flashcard>> {
     let conn = pool.get().context("Failed to get DB connection")?;
 
-    let mut query_parts = vec!["SELECT id, category, subcategory, question_html, answer_html FROM flashcards WHERE 1=1".to_string()];
-    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    let mut query_parts = vec![format!("SELECT {} FROM flashcards WHERE 1=1", FLASHCARD_COLUMNS)];
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    push_filter_clauses(filters, &mut query_parts, &mut params)?;
 
-    // Keywords filter (FTS5 subquery)
-    if !filters.keywords.is_empty() {
-        let match_query = filters.keywords.join(" AND ");
-        query_parts.push("AND id IN (SELECT id FROM flashcards_fts WHERE flashcards_fts MATCH ?)".to_string());
-        params.push(Box::new(match_query));
-    }
+    // Exclude seen cards
+    push_exclude_clause(exclude, &mut query_parts, &mut params);
 
-    // Category filter
-    if let Some(ref cats) = filters.categories {
-        if cats.is_empty() {
-            // Empty vec means "no categories" - only match image-only cards
-            query_parts.push("AND category IS NULL".to_string());
-        } else {
-            let placeholders = cats.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-            query_parts.push(format!("AND category IN ({})", placeholders));
-            for cat in cats {
-                params.push(Box::new(cat.clone()));
-            }
-        }
-    }
+    query_parts.push("ORDER BY RANDOM() LIMIT 1".to_string());
 
-    // Subcategory filter
-    if let Some(ref subcats) = filters.subcategories {
-        if !subcats.is_empty() {
-            let placeholders = subcats.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-            query_parts.push(format!("AND subcategory IN ({})", placeholders));
-            for subcat in subcats {
-                params.push(Box::new(subcat.clone()));
-            }
-        }
-    }
+    let query = query_parts.join(" ");
 
-    // Image filter
-    if !filters.include_images {
-        query_parts.push("AND question_html != '<h3>Question:</h3>'".to_string());
-    }
+    let card = conn
+        .query_row(
+            &query,
+            rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+            flashcard_from_row,
+        )
+        .optional()
+        .context("Failed to query filtered flashcard")?;
 
-    // Exclude seen cards
-    if !exclude.is_empty() {
-        let placeholders = exclude.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-        query_parts.push(format!("AND id NOT IN ({})", placeholders));
-        for id in exclude {
-            params.push(Box::new(*id));
+    Ok(card)
+}
+
+/// Number of cards of a category and subcategory: `(category, subcategory, count)`.
+pub type GroupCount = (Option<String>, Option<String>, i64);
+
+/// Counts flashcards matching filter criteria per category and subcategory, in listing order.
+///
+/// Groups are those of [`get_filtered_flashcards`]: categorized cards without subcategory after the others, cards without
+/// category last. Empty groups are not listed.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn count_filtered_groups(pool: &DbPool, filters: &FilterCriteria) -> Result<Vec<GroupCount>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut query_parts = vec!["SELECT category, subcategory, COUNT(*) FROM flashcards WHERE 1=1".to_string()];
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    push_filter_clauses(filters, &mut query_parts, &mut params)?;
+    query_parts.push(
+        "GROUP BY category, subcategory ORDER BY category IS NULL, category, subcategory IS NULL, subcategory".to_string(),
+    );
+
+    let query = query_parts.join(" ");
+
+    let mut stmt = conn.prepare(&query).context("Failed to prepare group count query")?;
+    let groups = stmt
+        .query_map(rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .context("Failed to count flashcard groups")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect flashcard groups")?;
+
+    Ok(groups)
+}
+
+/// Retrieves a page of flashcards matching filter criteria, grouped for listing.
+///
+/// Cards are sorted by category then subcategory (categorized cards without
+/// subcategory after the others, image-only cards last), then by `order`
+/// within each group.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_filtered_flashcards(
+    pool: &DbPool,
+    filters: &FilterCriteria,
+    order: CardOrder,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Flashcard>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut query_parts = vec![format!("SELECT {} FROM flashcards WHERE 1=1", FLASHCARD_COLUMNS)];
+    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+    push_filter_clauses(filters, &mut query_parts, &mut params)?;
+
+    let order_by = match order {
+        CardOrder::Source => "source_path, id",
+        CardOrder::Question => "question_text COLLATE NOCASE, id",
+        CardOrder::Difficulty => {
+            "CASE difficulty WHEN 'easy' THEN 0 WHEN 'medium' THEN 1 WHEN 'hard' THEN 2 ELSE 3 END, source_path, id"
         }
-    }
+    };
+    query_parts.push(format!(
+        "ORDER BY category IS NULL, category, subcategory IS NULL, subcategory, {} LIMIT ? OFFSET ?",
+        order_by
+    ));
+    params.push(Box::new(limit));
+    params.push(Box::new(offset));
 
-    query_parts.push("ORDER BY RANDOM() LIMIT 1".to_string());
+    let query = query_parts.join(" ");
+
+    let mut stmt = conn.prepare(&query).context("Failed to prepare flashcard listing query")?;
+    let cards = stmt
+        .query_map(
+            rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+            flashcard_from_row,
+        )
+        .context("Failed to query filtered flashcards")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect filtered flashcards")?;
+
+    Ok(cards)
+}
+
+/// Counts flashcards matching an FTS5 `MATCH` expression (see [`search::compile`]).
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn count_search_results(pool: &DbPool, match_query: &str) -> Result<i64> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM flashcards_fts WHERE flashcards_fts MATCH ?1",
+            [match_query],
+            |row| row.get(0),
+        )
+        .context("Failed to count search results")?;
+
+    Ok(count)
+}
+
+/// Retrieves a page of flashcards matching an FTS5 `MATCH` expression, best matches first.
+///
+/// Cards are ranked by `bm25()`; excerpts come from `highlight()` (question)
+/// and `snippet()` (answer).
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn search_flashcards(pool: &DbPool, match_query: &str, limit: i64, offset: i64) -> Result<Vec<SearchHit>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    // Column 3 of flashcards_fts is the question, 4 the answer
+    let query = format!(
+        "SELECT {columns},
+                highlight(flashcards_fts, 3, ?2, ?3),
+                snippet(flashcards_fts, 4, ?2, ?3, '...', 24)
+         FROM flashcards_fts JOIN flashcards ON flashcards.id = flashcards_fts.id
+         WHERE flashcards_fts MATCH ?1
+         ORDER BY bm25(flashcards_fts)
+         LIMIT ?4 OFFSET ?5",
+        columns = FLASHCARD_COLUMNS
+    );
+
+    let mut stmt = conn.prepare(&query).context("Failed to prepare search query")?;
+
+    let hits = stmt
+        .query_map(
+            params![match_query, MATCH_START.to_string(), MATCH_END.to_string(), limit, offset],
+            |row| {
+                Ok(SearchHit {
+                    card: flashcard_from_row(row)?,
+                    question_excerpt: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+                    answer_excerpt: row.get::<_, Option<String>>(11)?.unwrap_or_default(),
+                })
+            },
+        )
+        .context("Failed to search flashcards")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect search results")?;
+
+    Ok(hits)
+}
+
+/// Counts flashcards matching filter criteria that are due at `now`.
+///
+/// A card is due when it was never graded or its `due_at` is in the past.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn count_due_flashcards(pool: &DbPool, filters: &FilterCriteria, now: i64) -> Result<i64> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut query_parts = vec![
+        "SELECT COUNT(*) FROM flashcards LEFT JOIN reviews ON reviews.card_key = flashcards.card_key
+         WHERE (reviews.card_key IS NULL OR reviews.due_at <= ?)"
+            .to_string(),
+    ];
+    let mut params: Vec<Box<dyn ToSql>> = vec![Box::new(now)];
+    push_filter_clauses(filters, &mut query_parts, &mut params)?;
+
+    let query = query_parts.join(" ");
+
+    let count: i64 = conn
+        .query_row(
+            &query,
+            rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+            |row| row.get(0),
+        )
+        .context("Failed to count due flashcards")?;
+
+    Ok(count)
+}
+
+/// Retrieves the next due flashcard matching filter criteria, excluding specified IDs.
+///
+/// Previously graded cards come first, most overdue first, so failed cards
+/// reappear before new material. New (never graded) cards follow in random order.
+/// Returns None if no card is due.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_next_due_flashcard(
+    pool: &DbPool,
+    exclude: &[String],
+    filters: &FilterCriteria,
+    now: i64,
+) -> Result<Option<Flashcard>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut query_parts = vec![format!(
+        "SELECT {} FROM flashcards LEFT JOIN reviews ON reviews.card_key = flashcards.card_key
+         WHERE (reviews.card_key IS NULL OR reviews.due_at <= ?)",
+        FLASHCARD_COLUMNS
+    )];
+    let mut params: Vec<Box<dyn ToSql>> = vec![Box::new(now)];
+    push_filter_clauses(filters, &mut query_parts, &mut params)?;
+    push_exclude_clause(exclude, &mut query_parts, &mut params);
+
+    query_parts.push("ORDER BY reviews.card_key IS NULL, reviews.due_at, RANDOM() LIMIT 1".to_string());
 
     let query = query_parts.join(" ");
 
@@ -277,60 +1016,268 @@ pub fn get_filtered_random_flashcard(
         .query_row(
             &query,
             rusqlite::params_from_iter(params.iter().map(|p| p.as_ref())),
+            flashcard_from_row,
+        )
+        .optional()
+        .context("Failed to query due flashcard")?;
+
+    Ok(card)
+}
+
+/// Retrieves the spaced repetition state of a card.
+///
+/// Returns None if the card was never graded.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_review_state(pool: &DbPool, card_key: &str) -> Result<Option<ReviewState>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let state = conn
+        .query_row(
+            "SELECT card_key, ease, interval_days, repetitions, lapses, due_at, last_reviewed_at, stability, difficulty
+             FROM reviews WHERE card_key = ?1",
+            [card_key],
             |row| {
-                Ok(Flashcard {
-                    id: row.get(0)?,
-                    category: row.get(1)?,
-                    subcategory: row.get(2)?,
-                    question_html: row.get(3)?,
-                    answer_html: row.get(4)?,
+                Ok(ReviewState {
+                    card_key: row.get(0)?,
+                    ease: row.get(1)?,
+                    interval_days: row.get(2)?,
+                    repetitions: row.get(3)?,
+                    lapses: row.get(4)?,
+                    due_at: row.get(5)?,
+                    last_reviewed_at: row.get(6)?,
+                    stability: row.get(7)?,
+                    difficulty: row.get(8)?,
                 })
             },
         )
         .optional()
-        .context("Failed to query filtered flashcard")?;
+        .context("Failed to query review state")?;
 
-    Ok(card)
+    Ok(state)
+}
+
+/// Inserts or replaces the spaced repetition state of a card.
+///
+/// # Errors
+/// Returns error if database write fails.
+pub fn upsert_review_state(pool: &DbPool, state: &ReviewState) -> Result<()> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO reviews
+            (card_key, ease, interval_days, repetitions, lapses, due_at, last_reviewed_at, stability, difficulty)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            state.card_key,
+            state.ease,
+            state.interval_days,
+            state.repetitions,
+            state.lapses,
+            state.due_at,
+            state.last_reviewed_at,
+            state.stability,
+            state.difficulty
+        ],
+    )
+    .context("Failed to save review state")?;
+
+    Ok(())
+}
+
+/// Appends a graded review to the review history.
+///
+/// # Errors
+/// Returns error if database write fails.
+pub fn insert_review_log(pool: &DbPool, entry: &ReviewLogEntry) -> Result<()> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    conn.execute(
+        "INSERT INTO review_log (card_key, reviewed_at, grade, time_to_reveal_ms, time_on_card_ms, hints_used)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            entry.card_key,
+            entry.reviewed_at,
+            entry.grade.as_str(),
+            entry.time_to_reveal_ms,
+            entry.time_on_card_ms,
+            entry.hints_used
+        ],
+    )
+    .context("Failed to save review log entry")?;
+
+    Ok(())
+}
+
+/// Counts reviews per day over the `days` days ending at `now` (UTC).
+///
+/// Returns one entry per day, oldest first, including days without reviews.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_reviews_per_day(pool: &DbPool, now: i64, days: i64) -> Result<Vec<DailyCount>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare(
+            "WITH RECURSIVE days(day, n) AS (
+                SELECT date(?1, 'unixepoch', '-' || (?2 - 1) || ' days'), 1
+                UNION ALL SELECT date(day, '+1 day'), n + 1 FROM days WHERE n < ?2
+            )
+            SELECT days.day, COUNT(review_log.id)
+            FROM days LEFT JOIN review_log ON date(review_log.reviewed_at, 'unixepoch') = days.day
+            GROUP BY days.day ORDER BY days.day",
+        )
+        .context("Failed to prepare reviews per day query")?;
+
+    let counts = stmt
+        .query_map(params![now, days], daily_count_from_row)
+        .context("Failed to query reviews per day")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect reviews per day")?;
+
+    Ok(counts)
+}
+
+/// Counts graded cards coming due per day over the `days` days starting at `now` (UTC).
+///
+/// Overdue cards are counted on the first day. Never graded cards, and
+/// reviews of cards no longer in the deck, are not counted. Returns one entry per day, including days without due cards.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_due_forecast(pool: &DbPool, now: i64, days: i64) -> Result<Vec<DailyCount>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare(
+            "WITH RECURSIVE days(day, n) AS (
+                SELECT date(?1, 'unixepoch'), 1
+                UNION ALL SELECT date(day, '+1 day'), n + 1 FROM days WHERE n < ?2
+            )
+            SELECT days.day, COUNT(due.card_key)
+            FROM days LEFT JOIN (
+                SELECT reviews.card_key, reviews.due_at
+                FROM reviews JOIN flashcards ON flashcards.card_key = reviews.card_key
+            ) AS due ON date(max(due.due_at, ?1), 'unixepoch') = days.day
+            GROUP BY days.day ORDER BY days.day",
+        )
+        .context("Failed to prepare due forecast query")?;
+
+    let counts = stmt
+        .query_map(params![now, days], daily_count_from_row)
+        .context("Failed to query due forecast")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect due forecast")?;
+
+    Ok(counts)
+}
+
+/// Aggregates review outcomes per category and subcategory.
+///
+/// Only groups with at least one logged review are returned.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub fn get_retention_stats(pool: &DbPool) -> Result<Vec<RetentionStats>> {
+    let conn = pool.get().context("Failed to get DB connection")?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT flashcards.category, flashcards.subcategory, COUNT(*),
+                    SUM(CASE WHEN review_log.grade = 'again' THEN 0 ELSE 1 END)
+             FROM review_log JOIN flashcards ON flashcards.card_key = review_log.card_key
+             GROUP BY flashcards.category, flashcards.subcategory
+             ORDER BY flashcards.category, flashcards.subcategory",
+        )
+        .context("Failed to prepare retention query")?;
+
+    let stats = stmt
+        .query_map([], |row| {
+            Ok(RetentionStats {
+                category: row.get(0)?,
+                subcategory: row.get(1)?,
+                reviews: row.get(2)?,
+                recalled: row.get(3)?,
+            })
+        })
+        .context("Failed to query retention")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect retention")?;
+
+    Ok(stats)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::models::FilterCriteria;
     use rstest::rstest;
+    use crate::db::models::Subcategory;
+    use crate::db::schema::FtsTokenizer;
+    use crate::scheduler::Grade;
+    use regex::Regex;
+    use std::sync::LazyLock;
 
     /// Creates in-memory database with schema for testing.
     fn setup_test_db() -> DbPool {
         let manager = r2d2_sqlite::SqliteConnectionManager::memory();
         let pool = r2d2::Pool::builder().max_size(1).build(manager).unwrap();
 
-        // Initialize schema
-        let conn = pool.get().unwrap();
-        conn.execute(
-            "CREATE TABLE flashcards (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                category TEXT,
-                subcategory TEXT,
-                question_html TEXT NOT NULL,
-                answer_html TEXT NOT NULL
-            )",
-            [],
-        )
-        .unwrap();
+        crate::db::init_database(&pool).unwrap();
 
-        conn.execute(
-            "CREATE VIRTUAL TABLE flashcards_fts USING fts5(
-                id UNINDEXED,
+        pool
+    }
+
+    /// HTML tag, removed from the HTML of test cards to get their plain text.
+    static HTML_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+    /// Inserts a markdown flashcard keyed `card-<n>`, where n is its expected ID in a fresh database.
+    fn insert_card(
+        pool: &DbPool,
+        category: Option<&str>,
+        subcategory: Option<&str>,
+        question_html: &str,
+        answer_html: &str,
+    ) -> Result<i64> {
+        insert_card_of_kind(pool, CardKind::Markdown, category, subcategory, question_html, answer_html)
+    }
+
+    /// Inserts an image flashcard keyed `card-<n>` (see [`insert_card`]).
+    fn insert_image_card(pool: &DbPool, answer_html: &str) -> Result<i64> {
+        insert_card_of_kind(pool, CardKind::Image, None, None, "<h3>Question:</h3>\n", answer_html)
+    }
+
+    fn insert_card_of_kind(
+        pool: &DbPool,
+        kind: CardKind,
+        category: Option<&str>,
+        subcategory: Option<&str>,
+        question_html: &str,
+        answer_html: &str,
+    ) -> Result<i64> {
+        let card_key = format!("card-{}", get_total_count(pool)? + 1);
+        insert_flashcard(
+            pool,
+            &NewFlashcard {
+                card_key: &card_key,
+                source_path: "md/test.md",
                 category,
                 subcategory,
+                difficulty: None,
+                kind,
+                sibling_key: None,
+                expected_answer: None,
+                tags: &[],
+                hints_html: &[],
+                distractors_html: &[],
                 question_html,
-                answer_html
-            )",
-            [],
+                answer_html,
+                question_text: &HTML_TAG_REGEX.replace_all(question_html, " "),
+                answer_text: &HTML_TAG_REGEX.replace_all(answer_html, " "),
+                thumbnail_html: (kind == CardKind::Image).then_some("<img class='img-thumbnail'>"),
+            },
         )
-        .unwrap();
-
-        pool
     }
 
     /// Inserts sample flashcards for filter testing.
@@ -341,7 +1288,7 @@ mod tests {
         let pool = setup_test_db();
 
         // Math - Algebra (2 cards)
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Math"),
             Some("Algebra"),
@@ -350,7 +1297,7 @@ mod tests {
         )
         .unwrap();
 
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Math"),
             Some("Algebra"),
@@ -360,7 +1307,7 @@ mod tests {
         .unwrap();
 
         // Math - Geometry (1 card)
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Math"),
             Some("Geometry"),
@@ -370,7 +1317,7 @@ mod tests {
         .unwrap();
 
         // Science - Physics (2 cards, one with "gravity" keyword)
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Science"),
             Some("Physics"),
@@ -379,7 +1326,7 @@ mod tests {
         )
         .unwrap();
 
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Science"),
             Some("Physics"),
@@ -389,7 +1336,7 @@ mod tests {
         .unwrap();
 
         // Science - Chemistry (1 card with "formula" keyword)
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Science"),
             Some("Chemistry"),
@@ -399,7 +1346,7 @@ mod tests {
         .unwrap();
 
         // Programming - Rust (1 card)
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Programming"),
             Some("Rust"),
@@ -409,7 +1356,7 @@ mod tests {
         .unwrap();
 
         // Programming - Python (1 card)
-        insert_flashcard(
+        insert_card(
             &pool,
             Some("Programming"),
             Some("Python"),
@@ -419,23 +1366,8 @@ mod tests {
         .unwrap();
 
         // Image-only cards (2 cards, no category/subcategory)
-        insert_flashcard(
-            &pool,
-            None,
-            None,
-            "<h3>Question:</h3>",
-            "<h3>Answer:</h3><img src='/static/deck/img/diagram.png'>",
-        )
-        .unwrap();
-
-        insert_flashcard(
-            &pool,
-            None,
-            None,
-            "<h3>Question:</h3>",
-            "<h3>Answer:</h3><img src='/static/deck/img/chart.webp'>",
-        )
-        .unwrap();
+        insert_image_card(&pool, "<h3>Answer:</h3><img src='/static/deck/img/diagram.png'>").unwrap();
+        insert_image_card(&pool, "<h3>Answer:</h3><img src='/static/deck/img/chart.webp'>").unwrap();
 
         // Populate FTS table for keyword searches
         populate_fts_table(&pool).unwrap();
@@ -449,7 +1381,7 @@ mod tests {
     fn test_insert_flashcard_returns_incrementing_id() {
         let pool = setup_test_db();
 
-        let id1 = insert_flashcard(
+        let id1 = insert_card(
             &pool,
             Some("Category1"),
             Some("Subcat1"),
@@ -458,7 +1390,7 @@ mod tests {
         )
         .unwrap();
 
-        let id2 = insert_flashcard(
+        let id2 = insert_card(
             &pool,
             Some("Category2"),
             Some("Subcat2"),
@@ -475,7 +1407,7 @@ mod tests {
     fn test_insert_flashcard_with_null_category() {
         let pool = setup_test_db();
 
-        let id = insert_flashcard(&pool, None, None, "<p>Question</p>", "<p>Answer</p>").unwrap();
+        let id = insert_card(&pool, None, None, "<p>Question</p>", "<p>Answer</p>").unwrap();
 
         assert_eq!(id, 1);
 
@@ -496,7 +1428,7 @@ mod tests {
     fn test_clear_flashcards_removes_from_both_tables() {
         let pool = setup_test_db();
 
-        insert_flashcard(&pool, Some("Cat"), Some("Sub"), "<p>Q</p>", "<p>A</p>").unwrap();
+        insert_card(&pool, Some("Cat"), Some("Sub"), "<p>Q</p>", "<p>A</p>").unwrap();
         populate_fts_table(&pool).unwrap();
 
         // Verify data exists
@@ -518,8 +1450,8 @@ mod tests {
     fn test_populate_fts_table_syncs_with_main_table() {
         let pool = setup_test_db();
 
-        insert_flashcard(&pool, Some("Cat1"), Some("Sub1"), "<p>Q1</p>", "<p>A1</p>").unwrap();
-        insert_flashcard(&pool, Some("Cat2"), Some("Sub2"), "<p>Q2</p>", "<p>A2</p>").unwrap();
+        insert_card(&pool, Some("Cat1"), Some("Sub1"), "<p>Q1</p>", "<p>A1</p>").unwrap();
+        insert_card(&pool, Some("Cat2"), Some("Sub2"), "<p>Q2</p>", "<p>A2</p>").unwrap();
 
         populate_fts_table(&pool).unwrap();
 
@@ -531,6 +1463,108 @@ mod tests {
         assert_eq!(fts_count, 2);
     }
 
+    fn fts_count(pool: &DbPool) -> i64 {
+        let conn = pool.get().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM flashcards_fts", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_populate_fts_table_only_adds_missing_rows() {
+        let pool = setup_test_db();
+
+        insert_card(&pool, Some("Cat1"), Some("Sub1"), "<p>Q1</p>", "<p>A1</p>").unwrap();
+        populate_fts_table(&pool).unwrap();
+        insert_card(&pool, Some("Cat2"), Some("Sub2"), "<p>Q2</p>", "<p>A2</p>").unwrap();
+        populate_fts_table(&pool).unwrap();
+
+        assert_eq!(fts_count(&pool), 2);
+    }
+
+    #[test]
+    fn test_delete_flashcards_from_source_removes_fts_rows() {
+        let pool = setup_test_db();
+        let card = |card_key, source_path| NewFlashcard {
+            card_key,
+            source_path,
+            category: Some("Cat"),
+            subcategory: None,
+            difficulty: None,
+            kind: CardKind::Markdown,
+            sibling_key: None,
+            expected_answer: None,
+            tags: &[],
+            hints_html: &[],
+            distractors_html: &[],
+            question_html: "<p>Q</p>",
+            answer_html: "<p>A</p>",
+            question_text: "Q",
+            answer_text: "A",
+            thumbnail_html: None,
+        };
+
+        insert_flashcard(&pool, &card("a", "md/a.md")).unwrap();
+        insert_flashcard(&pool, &card("b", "md/b.md")).unwrap();
+        populate_fts_table(&pool).unwrap();
+
+        let deleted = delete_flashcards_from_source(&pool, "md/a.md").unwrap();
+
+        assert_eq!(deleted, 1);
+        assert_eq!(get_total_count(&pool).unwrap(), 1);
+        assert_eq!(fts_count(&pool), 1);
+        assert!(card_key_exists(&pool, "b").unwrap());
+    }
+
+    #[test]
+    fn test_delete_flashcards_without_source() {
+        let pool = setup_test_db();
+        insert_card(&pool, Some("Cat"), None, "<p>Q</p>", "<p>A</p>").unwrap();
+        pool.get()
+            .unwrap()
+            .execute(
+                "INSERT INTO flashcards (card_key, question_html, answer_html) VALUES ('legacy', 'Q', 'A')",
+                [],
+            )
+            .unwrap();
+
+        assert_eq!(delete_flashcards_without_source(&pool).unwrap(), 1);
+        assert_eq!(get_total_count(&pool).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_content_files_roundtrip() {
+        let pool = setup_test_db();
+        let file = |path: &str, mtime| ContentFile {
+            path: path.to_string(),
+            mtime,
+            hash: "abc".to_string(),
+        };
+
+        upsert_content_file(&pool, &file("md/a.md", 1)).unwrap();
+        upsert_content_file(&pool, &file("md/a.md", 2)).unwrap();
+        upsert_content_file(&pool, &file("img/a.png", 1)).unwrap();
+
+        assert_eq!(get_content_files(&pool, "md/").unwrap(), vec![file("md/a.md", 2)]);
+
+        delete_content_file(&pool, "md/a.md").unwrap();
+        assert!(get_content_files(&pool, "md/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clear_flashcards_forgets_content_files() {
+        let pool = setup_test_db();
+        let file = ContentFile {
+            path: "md/a.md".to_string(),
+            mtime: 1,
+            hash: "abc".to_string(),
+        };
+        upsert_content_file(&pool, &file).unwrap();
+
+        clear_flashcards(&pool).unwrap();
+
+        assert!(get_content_files(&pool, "md/").unwrap().is_empty());
+    }
+
     #[test]
     fn test_get_total_count_empty_database() {
         let pool = setup_test_db();
@@ -555,6 +1589,68 @@ mod tests {
         assert!(!is_database_empty(&pool).unwrap());
     }
 
+    #[test]
+    fn test_get_flashcard_by_key() {
+        let pool = setup_test_data();
+
+        let card = get_flashcard_by_key(&pool, "card-4").unwrap().unwrap();
+        assert_eq!(card.question_html, "<h3>Question:</h3><p>What is gravity?</p>");
+        assert_eq!(card.category.as_deref(), Some("Science"));
+
+        assert!(get_flashcard_by_key(&pool, "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_insert_flashcard_rejects_duplicate_key() {
+        let pool = setup_test_db();
+        let card = NewFlashcard {
+            card_key: "same-key",
+            source_path: "md/test.md",
+            category: None,
+            subcategory: None,
+            difficulty: None,
+            kind: CardKind::Markdown,
+            sibling_key: None,
+            expected_answer: None,
+            tags: &[],
+            hints_html: &[],
+            distractors_html: &[],
+            question_html: "<p>Q</p>",
+            answer_html: "<p>A</p>",
+            question_text: "Q",
+            answer_text: "A",
+            thumbnail_html: None,
+        };
+
+        insert_flashcard(&pool, &card).unwrap();
+
+        assert!(insert_flashcard(&pool, &card).is_err());
+    }
+
+    #[test]
+    fn test_card_key_exists() {
+        let pool = setup_test_data();
+
+        assert!(card_key_exists(&pool, "card-1").unwrap());
+        assert!(!card_key_exists(&pool, "unknown").unwrap());
+    }
+
+    #[test]
+    fn test_has_cards_without_key() {
+        let pool = setup_test_data();
+        assert!(!has_cards_without_key(&pool).unwrap());
+
+        pool.get()
+            .unwrap()
+            .execute(
+                "INSERT INTO flashcards (category, subcategory, question_html, answer_html) VALUES (NULL, NULL, 'Q', 'A')",
+                [],
+            )
+            .unwrap();
+
+        assert!(has_cards_without_key(&pool).unwrap());
+    }
+
     #[test]
     fn test_get_distinct_categories_sorted() {
         let pool = setup_test_data();
@@ -595,45 +1691,208 @@ mod tests {
     fn test_get_distinct_subcategories_filtered_by_category() {
         let pool = setup_test_data();
 
-        let math_filter = vec!["Math".to_string()];
-        let subcats = get_distinct_subcategories(&pool, Some(&math_filter)).unwrap();
+        let math_filter = vec!["Math".to_string()];
+        let subcats = get_distinct_subcategories(&pool, Some(&math_filter)).unwrap();
+
+        assert_eq!(subcats.len(), 2);
+        assert!(subcats.iter().any(|(s, c)| s == "Algebra" && c == "Math"));
+        assert!(subcats.iter().any(|(s, c)| s == "Geometry" && c == "Math"));
+    }
+
+    // ========== Parametrized Tests for count_filtered_flashcards ==========
+
+    #[rstest]
+    #[case(vec![], None, None, true, 10)] // No filters - all cards
+    #[case(vec![], None, None, false, 8)] // Exclude images (10 - 2 images)
+    #[case(vec![], Some(vec!["Math".to_string()]), None, true, 3)] // Category: Math
+    #[case(vec![], Some(vec!["Science".to_string()]), None, true, 3)] // Category: Science
+    #[case(vec![], Some(vec![]), None, true, 2)] // Empty categories = images only
+    #[case(vec![], Some(vec!["Math".to_string()]), Some(vec![Subcategory::new("Math", "Algebra")]), true, 2)] // Math - Algebra
+    #[case(vec![], Some(vec!["Science".to_string()]), Some(vec![Subcategory::new("Science", "Physics")]), true, 2)] // Science - Physics
+    #[case(vec![], Some(vec!["Math".to_string(), "Science".to_string()]), None, true, 6)] // Multiple categories
+    #[case(vec!["gravity".to_string()], None, None, true, 1)] // Keyword: gravity
+    #[case(vec!["formula".to_string()], Some(vec!["Science".to_string()]), None, true, 1)] // Keyword + Category
+    fn test_count_filtered_flashcards(
+        #[case] keywords: Vec<String>,
+        #[case] categories: Option<Vec<String>>,
+        #[case] subcategories: Option<Vec<Subcategory>>,
+        #[case] include_images: bool,
+        #[case] expected_count: i64,
+    ) {
+        let pool = setup_test_data();
+
+        let filters = FilterCriteria {
+            keywords,
+            categories,
+            subcategories,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            include_images,
+        };
+
+        let count = count_filtered_flashcards(&pool, &filters).unwrap();
+        assert_eq!(count, expected_count);
+    }
+
+    #[rstest]
+    #[case(r#""speed of light""#, 1)] // Phrase
+    #[case(r#""light speed""#, 0)] // Phrase words out of order
+    #[case("gravity OR ownership", 2)] // Alternatives
+    #[case("what -pi", 4)] // Exclusion
+    #[case("grav*", 1)] // Prefix
+    #[case("q:formula", 1)] // Question only
+    #[case("a:formula", 0)] // Answer only
+    #[case("cat:physics", 2)] // Category/subcategory only
+    #[case("C++ std::vec", 0)] // FTS5 syntax characters are searched as text
+    fn test_count_filtered_flashcards_search_syntax(#[case] query: &str, #[case] expected_count: i64) {
+        let pool = setup_test_data();
+
+        let filters = FilterCriteria {
+            keywords: query.split_whitespace().map(String::from).collect(),
+            categories: None,
+            subcategories: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            include_images: true,
+        };
+
+        assert_eq!(count_filtered_flashcards(&pool, &filters).unwrap(), expected_count);
+    }
+
+    #[rstest]
+    #[case(FtsTokenizer::Unicode61, "speeds", 0)]
+    #[case(FtsTokenizer::Porter, "speeds", 1)] // Same stem as "Speed"
+    #[case(FtsTokenizer::Porter, "comprehensions", 1)]
+    #[case(FtsTokenizer::Trigram, "ravit", 1)] // Substring of "gravity"
+    #[case(FtsTokenizer::Trigram, "grav* -light", 1)]
+    #[case(FtsTokenizer::Trigram, "cat:hemis", 1)]
+    fn test_count_filtered_flashcards_with_tokenizer(
+        #[case] tokenizer: FtsTokenizer,
+        #[case] query: &str,
+        #[case] expected_count: i64,
+    ) {
+        let pool = setup_test_data();
+        crate::db::schema::set_fts_tokenizer(&pool, tokenizer).unwrap();
+        populate_fts_table(&pool).unwrap();
+
+        let filters = FilterCriteria {
+            keywords: query.split_whitespace().map(String::from).collect(),
+            categories: None,
+            subcategories: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            include_images: true,
+        };
+
+        assert_eq!(count_filtered_flashcards(&pool, &filters).unwrap(), expected_count);
+    }
+
+    #[test]
+    fn test_search_flashcards_ranks_and_highlights() {
+        let pool = setup_test_data();
+        insert_card(
+            &pool,
+            Some("Science"),
+            Some("Physics"),
+            "<p>Gravity on the moon?</p>",
+            "<p>Gravity there is a sixth of gravity on Earth</p>",
+        )
+        .unwrap();
+        populate_fts_table(&pool).unwrap();
+
+        let match_query = search::compile("gravity").unwrap().unwrap();
+        assert_eq!(count_search_results(&pool, &match_query).unwrap(), 2);
+
+        let hits = search_flashcards(&pool, &match_query, 10, 0).unwrap();
+        assert_eq!(hits.len(), 2);
+        // More occurrences rank first
+        assert_eq!(hits[0].card.card_key, "card-11");
+        assert_eq!(hits[0].question_excerpt.trim(), "\u{2}Gravity\u{3} on the moon?");
+        assert!(hits[0].answer_excerpt.contains("sixth of \u{2}gravity\u{3}"));
+        assert!(hits[1].question_excerpt.contains("\u{2}gravity\u{3}"));
+    }
+
+    #[test]
+    fn test_search_flashcards_pages() {
+        let pool = setup_test_data();
+        let match_query = search::compile("what").unwrap().unwrap();
+
+        let first = search_flashcards(&pool, &match_query, 3, 0).unwrap();
+        let second = search_flashcards(&pool, &match_query, 3, 3).unwrap();
+
+        assert_eq!(count_search_results(&pool, &match_query).unwrap(), 5);
+        assert_eq!(first.len(), 3);
+        assert_eq!(second.len(), 2);
+        assert!(first.iter().all(|hit| second.iter().all(|other| other.card.card_key != hit.card.card_key)));
+    }
+
+    #[test]
+    fn test_count_filtered_flashcards_invalid_search() {
+        let pool = setup_test_data();
+
+        let filters = FilterCriteria {
+            keywords: vec!["\"unclosed".to_string()],
+            categories: None,
+            subcategories: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            include_images: true,
+        };
 
-        assert_eq!(subcats.len(), 2);
-        assert!(subcats.iter().any(|(s, c)| s == "Algebra" && c == "Math"));
-        assert!(subcats.iter().any(|(s, c)| s == "Geometry" && c == "Math"));
+        let err = count_filtered_flashcards(&pool, &filters).unwrap_err();
+        assert!(format!("{:#}", err).contains("missing closing quote"));
     }
 
-    // ========== Parametrized Tests for count_filtered_flashcards ==========
+    // ========== Tests for Subcategories Reused Across Categories ==========
+
+    /// Returns a database with subcategory "Basics" in both "Rust" (2 cards) and "Python" (1 card),
+    /// and "Rust - Async" (1 card).
+    fn setup_colliding_subcategories() -> DbPool {
+        let pool = setup_test_db();
+        insert_card(&pool, Some("Rust"), Some("Basics"), "<p>Rust basics 1</p>", "<p>A</p>").unwrap();
+        insert_card(&pool, Some("Rust"), Some("Basics"), "<p>Rust basics 2</p>", "<p>A</p>").unwrap();
+        insert_card(&pool, Some("Python"), Some("Basics"), "<p>Python basics</p>", "<p>A</p>").unwrap();
+        insert_card(&pool, Some("Rust"), Some("Async"), "<p>Rust async</p>", "<p>A</p>").unwrap();
+        pool
+    }
 
     #[rstest]
-    #[case(vec![], None, None, true, 10)] // No filters - all cards
-    #[case(vec![], None, None, false, 8)] // Exclude images (10 - 2 images)
-    #[case(vec![], Some(vec!["Math".to_string()]), None, true, 3)] // Category: Math
-    #[case(vec![], Some(vec!["Science".to_string()]), None, true, 3)] // Category: Science
-    #[case(vec![], Some(vec![]), None, true, 2)] // Empty categories = images only
-    #[case(vec![], Some(vec!["Math".to_string()]), Some(vec!["Algebra".to_string()]), true, 2)] // Math - Algebra
-    #[case(vec![], Some(vec!["Science".to_string()]), Some(vec!["Physics".to_string()]), true, 2)] // Science - Physics
-    #[case(vec![], Some(vec!["Math".to_string(), "Science".to_string()]), None, true, 6)] // Multiple categories
-    #[case(vec!["gravity".to_string()], None, None, true, 1)] // Keyword: gravity
-    #[case(vec!["formula".to_string()], Some(vec!["Science".to_string()]), None, true, 1)] // Keyword + Category
-    fn test_count_filtered_flashcards(
-        #[case] keywords: Vec<String>,
-        #[case] categories: Option<Vec<String>>,
-        #[case] subcategories: Option<Vec<String>>,
-        #[case] include_images: bool,
-        #[case] expected_count: i64,
+    #[case(None, vec![("Rust", "Basics")], 2)]
+    #[case(None, vec![("Python", "Basics")], 1)]
+    #[case(None, vec![("Rust", "Basics"), ("Python", "Basics")], 3)]
+    #[case(None, vec![("Python", "Basics"), ("Rust", "Async")], 2)]
+    #[case(Some(vec!["Rust", "Python"]), vec![("Rust", "Async")], 1)] // Python selected without its subcategories
+    #[case(None, vec![("Python", "Async")], 0)]
+    fn test_count_filtered_flashcards_colliding_subcategories(
+        #[case] categories: Option<Vec<&str>>,
+        #[case] subcategories: Vec<(&str, &str)>,
+        #[case] expected: i64,
     ) {
-        let pool = setup_test_data();
+        let pool = setup_colliding_subcategories();
 
         let filters = FilterCriteria {
-            keywords,
-            categories,
-            subcategories,
-            include_images,
+            categories: categories.map(|cats| cats.into_iter().map(String::from).collect()),
+            subcategories: Some(subcategories.into_iter().map(|(cat, sub)| Subcategory::new(cat, sub)).collect()),
+            ..no_filters()
         };
 
-        let count = count_filtered_flashcards(&pool, &filters).unwrap();
-        assert_eq!(count, expected_count);
+        assert_eq!(count_filtered_flashcards(&pool, &filters).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_get_filtered_random_flashcard_colliding_subcategories() {
+        let pool = setup_colliding_subcategories();
+
+        let filters = FilterCriteria {
+            subcategories: Some(vec![Subcategory::new("Python", "Basics")]),
+            ..no_filters()
+        };
+
+        // Only one matching card, whatever the random pick
+        for _ in 0..10 {
+            let card = get_filtered_random_flashcard(&pool, &[], &filters).unwrap().unwrap();
+            assert_eq!(card.category.as_deref(), Some("Python"));
+        }
     }
 
     // ========== Parametrized Tests for get_filtered_random_flashcard ==========
@@ -648,7 +1907,7 @@ mod tests {
     fn test_get_filtered_random_flashcard(
         #[case] keywords: Vec<String>,
         #[case] categories: Option<Vec<String>>,
-        #[case] subcategories: Option<Vec<String>>,
+        #[case] subcategories: Option<Vec<Subcategory>>,
         #[case] include_images: bool,
         #[case] should_return_card: bool,
     ) {
@@ -658,6 +1917,8 @@ mod tests {
             keywords,
             categories,
             subcategories,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
             include_images,
         };
 
@@ -677,7 +1938,9 @@ mod tests {
         let filters = FilterCriteria {
             keywords: vec![],
             categories: Some(vec!["Math".to_string()]),
-            subcategories: Some(vec!["Algebra".to_string()]),
+            subcategories: Some(vec![Subcategory::new("Math", "Algebra")]),
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
             include_images: true,
         };
 
@@ -688,14 +1951,14 @@ mod tests {
             .unwrap();
 
         // Exclude first card, should get the other one
-        let card2 = get_filtered_random_flashcard(&pool, &[card1.id], &filters)
+        let card2 = get_filtered_random_flashcard(&pool, std::slice::from_ref(&card1.card_key), &filters)
             .unwrap()
             .unwrap();
 
         assert_ne!(card1.id, card2.id);
 
         // Exclude both, should return None
-        let card3 = get_filtered_random_flashcard(&pool, &[card1.id, card2.id], &filters).unwrap();
+        let card3 = get_filtered_random_flashcard(&pool, &[card1.card_key, card2.card_key], &filters).unwrap();
 
         assert!(card3.is_none());
     }
@@ -708,6 +1971,8 @@ mod tests {
             keywords: vec![],
             categories: Some(vec!["Math".to_string()]),
             subcategories: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
             include_images: true,
         };
 
@@ -725,7 +1990,9 @@ mod tests {
         let filters = FilterCriteria {
             keywords: vec![],
             categories: Some(vec!["Science".to_string()]),
-            subcategories: Some(vec!["Chemistry".to_string()]),
+            subcategories: Some(vec![Subcategory::new("Science", "Chemistry")]),
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
             include_images: true,
         };
 
@@ -737,6 +2004,36 @@ mod tests {
         assert_eq!(card.subcategory, Some("Chemistry".to_string()));
     }
 
+    #[test]
+    fn test_count_filtered_groups_in_listing_order() {
+        let pool = setup_test_data();
+        let filters = FilterCriteria {
+            keywords: vec![],
+            categories: None,
+            subcategories: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            include_images: true,
+        };
+
+        let groups = count_filtered_groups(&pool, &filters).unwrap();
+        let group = |category: Option<&str>, subcategory: Option<&str>, count: i64| {
+            (category.map(String::from), subcategory.map(String::from), count)
+        };
+        assert_eq!(
+            groups,
+            vec![
+                group(Some("Math"), Some("Algebra"), 2),
+                group(Some("Math"), Some("Geometry"), 1),
+                group(Some("Programming"), Some("Python"), 1),
+                group(Some("Programming"), Some("Rust"), 1),
+                group(Some("Science"), Some("Chemistry"), 1),
+                group(Some("Science"), Some("Physics"), 2),
+                group(None, None, 2),
+            ]
+        );
+    }
+
     #[test]
     fn test_get_filtered_random_flashcard_image_only_filter() {
         let pool = setup_test_data();
@@ -745,6 +2042,8 @@ mod tests {
             keywords: vec![],
             categories: Some(vec![]), // Empty = images only
             subcategories: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
             include_images: true,
         };
 
@@ -754,7 +2053,580 @@ mod tests {
 
         // Should be image-only card (no category)
         assert!(card.category.is_none());
-        assert_eq!(card.question_html, "<h3>Question:</h3>");
+        assert_eq!(card.kind, CardKind::Image);
+    }
+
+    #[test]
+    fn test_markdown_card_with_empty_question_is_not_an_image() {
+        let pool = setup_test_db();
+        insert_card(&pool, None, None, "<h3>Question:</h3>", "<h3>Answer:</h3><p>A</p>").unwrap();
+        insert_image_card(&pool, "<h3>Answer:</h3><img src='/static/deck/img/chart.webp'>").unwrap();
+
+        let filters = FilterCriteria {
+            include_images: false,
+            ..no_filters()
+        };
+
+        assert_eq!(count_filtered_flashcards(&pool, &filters).unwrap(), 1);
+        let card = get_filtered_random_flashcard(&pool, &[], &filters).unwrap().unwrap();
+        assert_eq!(card.kind, CardKind::Markdown);
+    }
+
+    #[test]
+    fn test_has_cards_without_kind() {
+        let pool = setup_test_data();
+        assert!(!has_cards_without_kind(&pool).unwrap());
+
+        // Cards loaded before the kind column existed
+        let conn = pool.get().unwrap();
+        conn.execute("UPDATE flashcards SET kind = NULL WHERE id = 1", []).unwrap();
+        drop(conn);
+
+        assert!(has_cards_without_kind(&pool).unwrap());
+    }
+
+    #[test]
+    fn test_has_image_cards_without_text() {
+        let pool = setup_test_data();
+        assert!(!has_image_cards_without_text(&pool).unwrap());
+
+        // Image cards loaded before their file name was indexed
+        insert_image_card(&pool, "<img src='/static/deck/img/bayes.webp'>").unwrap();
+        assert!(has_image_cards_without_text(&pool).unwrap());
+
+        let conn = pool.get().unwrap();
+        conn.execute("UPDATE flashcards SET answer_text = 'bayes' WHERE kind = 'image'", []).unwrap();
+        drop(conn);
+        assert!(!has_image_cards_without_text(&pool).unwrap());
+    }
+
+    #[test]
+    fn test_has_image_cards_without_thumbnail() {
+        let pool = setup_test_data();
+        insert_image_card(&pool, "<img src='/static/deck/img/bayes.webp'>").unwrap();
+        assert!(!has_image_cards_without_thumbnail(&pool).unwrap());
+
+        // Image cards loaded before their web variants were made
+        let conn = pool.get().unwrap();
+        conn.execute("UPDATE flashcards SET thumbnail_html = NULL WHERE kind = 'image'", []).unwrap();
+        drop(conn);
+        assert!(has_image_cards_without_thumbnail(&pool).unwrap());
+    }
+
+    // ========== Tests for get_filtered_flashcards ==========
+
+    fn listed_keys(pool: &DbPool, filters: &FilterCriteria, order: CardOrder, limit: i64, offset: i64) -> Vec<String> {
+        get_filtered_flashcards(pool, filters, order, limit, offset)
+            .unwrap()
+            .into_iter()
+            .map(|card| card.card_key)
+            .collect()
+    }
+
+    #[rstest]
+    #[case(CardOrder::Source, &[1, 2, 3, 8, 7, 6, 4, 5, 9, 10])]
+    #[case(CardOrder::Question, &[1, 2, 3, 8, 7, 6, 5, 4, 9, 10])]
+    fn test_get_filtered_flashcards_grouped_by_category(#[case] order: CardOrder, #[case] expected: &[i64]) {
+        let pool = setup_test_data();
+
+        let expected: Vec<String> = expected.iter().map(|id| format!("card-{}", id)).collect();
+        assert_eq!(listed_keys(&pool, &no_filters(), order, 100, 0), expected);
+    }
+
+    #[test]
+    fn test_get_filtered_flashcards_pages() {
+        let pool = setup_test_data();
+
+        assert_eq!(listed_keys(&pool, &no_filters(), CardOrder::Source, 3, 2), vec!["card-3", "card-8", "card-7"]);
+        assert!(listed_keys(&pool, &no_filters(), CardOrder::Source, 3, 10).is_empty());
+    }
+
+    #[test]
+    fn test_get_filtered_flashcards_applies_filters() {
+        let pool = setup_test_data();
+
+        let filters = FilterCriteria {
+            categories: Some(vec!["Science".to_string()]),
+            ..no_filters()
+        };
+
+        assert_eq!(listed_keys(&pool, &filters, CardOrder::Source, 100, 0), vec!["card-6", "card-4", "card-5"]);
+    }
+
+    #[test]
+    fn test_get_filtered_flashcards_by_difficulty() {
+        let pool = setup_test_db();
+        for (card_key, difficulty) in [("a", Some("hard")), ("b", None), ("c", Some("easy")), ("d", Some("medium"))] {
+            insert_flashcard(
+                &pool,
+                &NewFlashcard {
+                    card_key,
+                    source_path: "md/test.md",
+                    category: Some("Python"),
+                    subcategory: Some("Data"),
+                    difficulty,
+                    kind: CardKind::Markdown,
+                    sibling_key: None,
+                    expected_answer: None,
+                    tags: &[],
+                    hints_html: &[],
+                    distractors_html: &[],
+                    question_html: "<p>Q</p>",
+                    answer_html: "<p>A</p>",
+                    question_text: "Q",
+                    answer_text: "A",
+                    thumbnail_html: None,
+                },
+            )
+            .unwrap();
+        }
+
+        assert_eq!(listed_keys(&pool, &no_filters(), CardOrder::Difficulty, 100, 0), vec!["c", "d", "a", "b"]);
+    }
+
+    // ========== Tests for Tags ==========
+
+    /// Returns a database with cards `pandas-perf` (pandas, performance),
+    /// `pandas-io` (pandas, io) and `numpy-perf` (numpy, performance).
+    fn setup_tagged_data() -> DbPool {
+        let pool = setup_test_db();
+        let tagged = [
+            ("pandas-perf", vec!["pandas", "performance"]),
+            ("pandas-io", vec!["pandas", "io"]),
+            ("numpy-perf", vec!["numpy", "performance"]),
+        ];
+
+        for (card_key, tags) in tagged {
+            let tags: Vec<String> = tags.into_iter().map(String::from).collect();
+            insert_flashcard(
+                &pool,
+                &NewFlashcard {
+                    card_key,
+                    source_path: &format!("md/{}.md", card_key),
+                    category: Some("Python"),
+                    subcategory: Some("Data"),
+                    difficulty: None,
+                    kind: CardKind::Markdown,
+                    sibling_key: None,
+                    expected_answer: None,
+                    tags: &tags,
+                    hints_html: &[],
+                    distractors_html: &[],
+                    question_html: "<p>Q</p>",
+                    answer_html: "<p>A</p>",
+                    question_text: "Q",
+                    answer_text: "A",
+                    thumbnail_html: None,
+                },
+            )
+            .unwrap();
+        }
+
+        pool
+    }
+
+    fn tag_filters(tags: &[&str], tag_match: TagMatch) -> FilterCriteria {
+        FilterCriteria {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            tag_match,
+            ..no_filters()
+        }
+    }
+
+    #[test]
+    fn test_get_tag_counts() {
+        let pool = setup_tagged_data();
+
+        let counts = get_tag_counts(&pool).unwrap();
+
+        assert_eq!(
+            counts,
+            vec![
+                ("io".to_string(), 1),
+                ("numpy".to_string(), 1),
+                ("pandas".to_string(), 2),
+                ("performance".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_card_tags() {
+        let pool = setup_tagged_data();
+
+        assert_eq!(get_card_tags(&pool, "pandas-io").unwrap(), vec!["io", "pandas"]);
+        assert!(get_card_tags(&pool, "unknown").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_sibling_keys() {
+        let pool = setup_test_db();
+        let cards = [
+            ("box", Some("box"), CardKind::Markdown),
+            ("box.reverse", Some("box"), CardKind::Reversed),
+            ("owner.c1", Some("owner"), CardKind::Cloze),
+            ("owner.c2", Some("owner"), CardKind::Cloze),
+            ("owner.c3", Some("owner"), CardKind::Cloze),
+            ("alone", None, CardKind::Markdown),
+        ];
+        for (card_key, sibling_key, kind) in cards {
+            insert_flashcard(
+                &pool,
+                &NewFlashcard {
+                    card_key,
+                    source_path: "md/test.md",
+                    category: None,
+                    subcategory: None,
+                    difficulty: None,
+                    kind,
+                    sibling_key,
+                    expected_answer: None,
+                    tags: &[],
+                    hints_html: &[],
+                    distractors_html: &[],
+                    question_html: "<p>Q</p>",
+                    answer_html: "<p>A</p>",
+                    question_text: "Q",
+                    answer_text: "A",
+                    thumbnail_html: None,
+                },
+            )
+            .unwrap();
+        }
+
+        assert_eq!(get_sibling_keys(&pool, "box").unwrap(), vec!["box.reverse"]);
+        assert_eq!(get_sibling_keys(&pool, "box.reverse").unwrap(), vec!["box"]);
+        assert_eq!(get_sibling_keys(&pool, "owner.c2").unwrap(), vec!["owner.c1", "owner.c3"]);
+        assert!(get_sibling_keys(&pool, "alone").unwrap().is_empty());
+        assert!(get_sibling_keys(&pool, "unknown").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_random_distractors() {
+        let pool = setup_test_db();
+        // (key, subcategory, sibling key, kind, answer)
+        let cards = [
+            ("vec", Some("Collections"), Some("vec"), CardKind::Markdown, "Vec"),
+            ("vec.reverse", Some("Collections"), Some("vec"), CardKind::Reversed, "Growable array?"),
+            ("map", Some("Collections"), None, CardKind::Markdown, "HashMap"),
+            ("set", Some("Collections"), None, CardKind::Markdown, "HashSet"),
+            ("set-again", Some("Collections"), None, CardKind::Markdown, "HashSet"),
+            ("same", Some("Collections"), None, CardKind::Markdown, "Vec"),
+            ("box", Some("Pointers"), None, CardKind::Markdown, "Box"),
+        ];
+        for (card_key, subcategory, sibling_key, kind, answer) in cards {
+            let answer_html = format!("<p>{}</p>", answer);
+            insert_flashcard(
+                &pool,
+                &NewFlashcard {
+                    card_key,
+                    source_path: "md/test.md",
+                    category: Some("Rust"),
+                    subcategory,
+                    difficulty: None,
+                    kind,
+                    sibling_key,
+                    expected_answer: None,
+                    tags: &[],
+                    hints_html: &[],
+                    distractors_html: &[],
+                    question_html: "<p>Q</p>",
+                    answer_html: &answer_html,
+                    question_text: "Q",
+                    answer_text: answer,
+                    thumbnail_html: None,
+                },
+            )
+            .unwrap();
+        }
+
+        // Same subcategory and kind, without siblings, repeated or identical answers
+        let mut distractors = get_random_distractors(&pool, "vec", 5).unwrap();
+        distractors.sort();
+        assert_eq!(distractors, vec!["<p>HashMap</p>", "<p>HashSet</p>"]);
+        assert_eq!(get_random_distractors(&pool, "vec", 1).unwrap().len(), 1);
+        assert!(get_random_distractors(&pool, "vec.reverse", 5).unwrap().is_empty());
+        assert!(get_random_distractors(&pool, "box", 5).unwrap().is_empty());
+        assert!(get_random_distractors(&pool, "unknown", 5).unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[case(&["pandas"], TagMatch::Any, 2)]
+    #[case(&["pandas", "numpy"], TagMatch::Any, 3)]
+    #[case(&["pandas", "performance"], TagMatch::Any, 3)]
+    #[case(&["pandas", "performance"], TagMatch::All, 1)]
+    #[case(&["pandas", "numpy"], TagMatch::All, 0)]
+    #[case(&["pandas", "performance", "pandas"], TagMatch::All, 1)]
+    #[case(&["pandas", "pandas"], TagMatch::Any, 2)]
+    #[case(&["unknown"], TagMatch::Any, 0)]
+    fn test_count_filtered_flashcards_by_tags(
+        #[case] tags: &[&str],
+        #[case] tag_match: TagMatch,
+        #[case] expected: i64,
+    ) {
+        let pool = setup_tagged_data();
+
+        assert_eq!(count_filtered_flashcards(&pool, &tag_filters(tags, tag_match)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_get_filtered_random_flashcard_matches_all_tags() {
+        let pool = setup_tagged_data();
+
+        let card = get_filtered_random_flashcard(&pool, &[], &tag_filters(&["performance", "numpy"], TagMatch::All))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(card.card_key, "numpy-perf");
+    }
+
+    #[test]
+    fn test_deleting_flashcards_removes_their_tags() {
+        let pool = setup_tagged_data();
+
+        delete_flashcards_from_source(&pool, "md/pandas-io.md").unwrap();
+
+        assert!(get_card_tags(&pool, "pandas-io").unwrap().is_empty());
+        assert!(!get_tag_counts(&pool).unwrap().iter().any(|(tag, _)| tag == "io"));
+    }
+
+    #[test]
+    fn test_card_hints_in_order_and_deleted_with_card() {
+        let pool = setup_test_db();
+        let hints = vec!["<p>First</p>".to_string(), "<p>Second</p>".to_string()];
+        insert_flashcard(
+            &pool,
+            &NewFlashcard {
+                card_key: "derivation",
+                source_path: "md/maths.md",
+                category: None,
+                subcategory: None,
+                difficulty: None,
+                kind: CardKind::Markdown,
+                sibling_key: None,
+                expected_answer: None,
+                tags: &[],
+                hints_html: &hints,
+                distractors_html: &[],
+                question_html: "<p>Q</p>",
+                answer_html: "<p>A</p>",
+                question_text: "Q",
+                answer_text: "A",
+                thumbnail_html: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(get_card_hints(&pool, "derivation").unwrap(), hints);
+        assert!(get_card_hints(&pool, "unknown").unwrap().is_empty());
+
+        delete_flashcards_from_source(&pool, "md/maths.md").unwrap();
+        assert!(get_card_hints(&pool, "derivation").unwrap().is_empty());
+    }
+
+    // ========== Tests for Spaced Repetition Queries ==========
+
+    /// Builds a review state of card `card_id` (key `card-<id>`) due at `due_at`.
+    fn review(card_id: i64, due_at: i64) -> ReviewState {
+        ReviewState {
+            card_key: format!("card-{}", card_id),
+            ease: 2.5,
+            interval_days: 1,
+            repetitions: 1,
+            lapses: 0,
+            due_at,
+            last_reviewed_at: due_at - 86_400,
+            stability: 0.0,
+            difficulty: 0.0,
+        }
+    }
+
+    /// Filter criteria matching every card.
+    fn no_filters() -> FilterCriteria {
+        FilterCriteria {
+            keywords: vec![],
+            categories: None,
+            subcategories: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            include_images: true,
+        }
+    }
+
+    #[test]
+    fn test_review_state_roundtrip() {
+        let pool = setup_test_data();
+
+        assert!(get_review_state(&pool, "card-1").unwrap().is_none());
+
+        let state = review(1, 1_000);
+        upsert_review_state(&pool, &state).unwrap();
+        assert_eq!(get_review_state(&pool, "card-1").unwrap(), Some(state));
+
+        // Upsert replaces existing row
+        let updated = review(1, 2_000);
+        upsert_review_state(&pool, &updated).unwrap();
+        assert_eq!(get_review_state(&pool, "card-1").unwrap(), Some(updated));
+    }
+
+    #[test]
+    fn test_count_due_flashcards_excludes_future_reviews() {
+        let pool = setup_test_data();
+        let now = 10_000;
+
+        upsert_review_state(&pool, &review(1, now - 1)).unwrap(); // Due
+        upsert_review_state(&pool, &review(2, now + 1)).unwrap(); // Not due yet
+
+        // 10 cards - 1 scheduled in the future
+        assert_eq!(count_due_flashcards(&pool, &no_filters(), now).unwrap(), 9);
+    }
+
+    #[test]
+    fn test_get_next_due_flashcard_prefers_most_overdue() {
+        let pool = setup_test_data();
+        let now = 10_000;
+
+        upsert_review_state(&pool, &review(3, now - 100)).unwrap();
+        upsert_review_state(&pool, &review(5, now - 500)).unwrap();
+
+        let card = get_next_due_flashcard(&pool, &[], &no_filters(), now).unwrap().unwrap();
+        assert_eq!(card.id, 5);
+
+        let card = get_next_due_flashcard(&pool, &["card-5".to_string()], &no_filters(), now).unwrap().unwrap();
+        assert_eq!(card.id, 3);
+    }
+
+    #[test]
+    fn test_get_next_due_flashcard_respects_filters() {
+        let pool = setup_test_data();
+        let now = 10_000;
+
+        // Overdue Math card must not leak into a Science session
+        upsert_review_state(&pool, &review(1, now - 500)).unwrap();
+
+        let filters = FilterCriteria {
+            categories: Some(vec!["Science".to_string()]),
+            ..no_filters()
+        };
+
+        let card = get_next_due_flashcard(&pool, &[], &filters, now).unwrap().unwrap();
+        assert_eq!(card.category, Some("Science".to_string()));
+    }
+
+    #[test]
+    fn test_get_next_due_flashcard_none_when_nothing_due() {
+        let pool = setup_test_data();
+        let now = 10_000;
+
+        let filters = FilterCriteria {
+            categories: Some(vec!["Math".to_string()]),
+            subcategories: Some(vec![Subcategory::new("Math", "Geometry")]),
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            ..no_filters()
+        };
+
+        // Only Geometry card (ID 3) scheduled in the future
+        upsert_review_state(&pool, &review(3, now + 86_400)).unwrap();
+
+        assert!(get_next_due_flashcard(&pool, &[], &filters, now).unwrap().is_none());
+        assert_eq!(count_due_flashcards(&pool, &filters, now).unwrap(), 0);
+    }
+
+    // ========== Tests for Review Log and Statistics ==========
+
+    /// 2023-11-14T22:13:20Z
+    const STATS_NOW: i64 = 1_700_000_000;
+    const DAY: i64 = 86_400;
+
+    /// Logs a review of card `card_id` at `reviewed_at`.
+    fn log(pool: &DbPool, card_id: i64, reviewed_at: i64, grade: Grade) {
+        let entry = ReviewLogEntry {
+            card_key: format!("card-{}", card_id),
+            reviewed_at,
+            grade,
+            time_to_reveal_ms: Some(1_500),
+            time_on_card_ms: Some(3_000),
+            hints_used: Some(1),
+        };
+        insert_review_log(pool, &entry).unwrap();
+    }
+
+    #[test]
+    fn test_insert_review_log_stores_entry() {
+        let pool = setup_test_data();
+
+        log(&pool, 1, STATS_NOW, Grade::Hard);
+
+        let conn = pool.get().unwrap();
+        let (grade, reveal, on_card, hints): (String, Option<i64>, Option<i64>, Option<i64>) = conn
+            .query_row(
+                "SELECT grade, time_to_reveal_ms, time_on_card_ms, hints_used FROM review_log WHERE card_key = 'card-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(grade, "hard");
+        assert_eq!(reveal, Some(1_500));
+        assert_eq!(on_card, Some(3_000));
+        assert_eq!(hints, Some(1));
+    }
+
+    #[test]
+    fn test_get_reviews_per_day_fills_empty_days() {
+        let pool = setup_test_data();
+        log(&pool, 1, STATS_NOW, Grade::Good);
+        log(&pool, 2, STATS_NOW, Grade::Again);
+        log(&pool, 1, STATS_NOW - 2 * DAY, Grade::Good);
+        log(&pool, 1, STATS_NOW - 10 * DAY, Grade::Good); // Outside window
+
+        let counts = get_reviews_per_day(&pool, STATS_NOW, 7).unwrap();
+
+        assert_eq!(counts.len(), 7);
+        assert_eq!(counts[0].day, "2023-11-08");
+        assert_eq!(counts[6].day, "2023-11-14");
+        let per_day: Vec<i64> = counts.iter().map(|c| c.count).collect();
+        assert_eq!(per_day, vec![0, 0, 0, 0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn test_get_due_forecast_counts_overdue_today() {
+        let pool = setup_test_data();
+        upsert_review_state(&pool, &review(1, STATS_NOW - 5 * DAY)).unwrap(); // Overdue
+        upsert_review_state(&pool, &review(2, STATS_NOW + 60)).unwrap(); // Later today
+        upsert_review_state(&pool, &review(3, STATS_NOW + 3 * DAY)).unwrap();
+        upsert_review_state(&pool, &review(4, STATS_NOW + 90 * DAY)).unwrap(); // Outside window
+
+        let forecast = get_due_forecast(&pool, STATS_NOW, 30).unwrap();
+
+        assert_eq!(forecast.len(), 30);
+        assert_eq!(forecast[0].day, "2023-11-14");
+        assert_eq!(forecast[0].count, 2);
+        assert_eq!(forecast[3].count, 1);
+        assert_eq!(forecast.iter().map(|c| c.count).sum::<i64>(), 3);
+    }
+
+    #[test]
+    fn test_get_retention_stats_groups_by_subcategory() {
+        let pool = setup_test_data();
+        log(&pool, 1, STATS_NOW, Grade::Good); // Math - Algebra
+        log(&pool, 2, STATS_NOW, Grade::Again); // Math - Algebra
+        log(&pool, 3, STATS_NOW, Grade::Easy); // Math - Geometry
+
+        let stats = get_retention_stats(&pool).unwrap();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].subcategory.as_deref(), Some("Algebra"));
+        assert_eq!((stats[0].reviews, stats[0].recalled), (2, 1));
+        assert_eq!(stats[0].retention_percent(), Some(50.0));
+        assert_eq!(stats[1].subcategory.as_deref(), Some("Geometry"));
+        assert_eq!(stats[1].retention_percent(), Some(100.0));
+    }
+
+    #[test]
+    fn test_get_retention_stats_empty_without_reviews() {
+        let pool = setup_test_data();
+        assert!(get_retention_stats(&pool).unwrap().is_empty());
     }
 
     // ========== Property-Based Tests ==========
@@ -772,9 +2644,9 @@ mod tests {
             prop::collection::vec("[a-z]{3,10}", 0..5)
         }
 
-        /// Generates random exclude ID lists.
-        fn arb_exclude_ids() -> impl Strategy<Value = Vec<i64>> {
-            prop::collection::vec(1i64..100, 0..10)
+        /// Generates random exclude key lists (`card-<id>`, as inserted by setup).
+        fn arb_exclude_keys() -> impl Strategy<Value = Vec<String>> {
+            prop::collection::vec((1i64..100).prop_map(|id| format!("card-{}", id)), 0..10)
         }
 
         /// Tests that filtered count never exceeds total count.
@@ -790,6 +2662,8 @@ mod tests {
                     keywords,
                     categories: None,
                     subcategories: None,
+                    tags: Vec::new(),
+                    tag_match: TagMatch::Any,
                     include_images,
                 };
 
@@ -804,22 +2678,24 @@ mod tests {
         /// Tests that random flashcard respects exclude list.
         ///
         /// Invariant: If get_filtered_random_flashcard returns a card,
-        /// its ID must NOT be in the exclude_ids list.
+        /// its key must NOT be in the exclude_keys list.
         #[test]
         fn prop_random_card_respects_exclude_list() {
-            proptest!(|(exclude_ids in arb_exclude_ids())| {
+            proptest!(|(exclude_keys in arb_exclude_keys())| {
                 let pool = setup_test_data();
 
                 let filters = FilterCriteria {
                     keywords: vec![],
                     categories: None,
                     subcategories: None,
+                    tags: Vec::new(),
+                    tag_match: TagMatch::Any,
                     include_images: true,
                 };
 
-                if let Some(card) = get_filtered_random_flashcard(&pool, &exclude_ids, &filters).unwrap() {
-                    // Returned card ID must not be in exclude list
-                    prop_assert!(!exclude_ids.contains(&card.id));
+                if let Some(card) = get_filtered_random_flashcard(&pool, &exclude_keys, &filters).unwrap() {
+                    // Returned card key must not be in exclude list
+                    prop_assert!(!exclude_keys.contains(&card.card_key));
                 }
             });
         }
@@ -838,6 +2714,8 @@ mod tests {
                     keywords: vec![],
                     categories: Some(vec!["Math".to_string()]),
                     subcategories: None,
+                    tags: Vec::new(),
+                    tag_match: TagMatch::Any,
                     include_images,
                 };
 
@@ -866,6 +2744,8 @@ mod tests {
                     keywords: vec![],
                     categories: None,
                     subcategories: None,
+                    tags: Vec::new(),
+                    tag_match: TagMatch::Any,
                     include_images: true,
                 };
 
@@ -873,6 +2753,8 @@ mod tests {
                     keywords,
                     categories: Some(vec!["Math".to_string()]),
                     subcategories: None,
+                    tags: Vec::new(),
+                    tag_match: TagMatch::Any,
                     include_images: true,
                 };
 
@@ -886,7 +2768,7 @@ mod tests {
 
         /// Tests that exclude list reduces available cards.
         ///
-        /// Invariant: Excluding N existing IDs should reduce available cards
+        /// Invariant: Excluding N existing cards should reduce available cards
         /// (unless all cards are excluded).
         #[test]
         fn prop_exclude_list_reduces_available() {
@@ -897,17 +2779,19 @@ mod tests {
                     keywords: vec![],
                     categories: None,
                     subcategories: None,
+                    tags: Vec::new(),
+                    tag_match: TagMatch::Any,
                     include_images: true,
                 };
 
                 // Get total count
                 let total = count_filtered_flashcards(&pool, &filters).unwrap();
 
-                // Exclude first n_exclude IDs
-                let exclude_ids: Vec<i64> = (1..=n_exclude as i64).collect();
+                // Exclude first n_exclude cards
+                let exclude_keys: Vec<String> = (1..=n_exclude).map(|id| format!("card-{}", id)).collect();
 
                 // Try to get a card with exclusions
-                let card = get_filtered_random_flashcard(&pool, &exclude_ids, &filters).unwrap();
+                let card = get_filtered_random_flashcard(&pool, &exclude_keys, &filters).unwrap();
 
                 // If we got a card, there must be more cards than excluded
                 if card.is_some() {
@@ -1,5 +1,89 @@
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
 use super::connection::DbPool;
 
+/// HTML tag.
+static HTML_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// HTML character reference (named ones escaped by markdown rendering, or numeric).
+static HTML_ENTITY_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(?:(lt|gt|amp|quot|apos|nbsp)|#(\d+)|#[xX]([0-9a-fA-F]+));").unwrap());
+
+/// `src` attribute of an HTML `<img>` tag.
+static IMG_SRC_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["']"#).unwrap());
+
+/// Image of an image card loaded by versions without card keys (path below `img/`).
+static LEGACY_IMAGE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"src='/static/[^/']+/img/([^']+)'").unwrap());
+
+/// Version of the way content files are turned into cards.
+///
+/// Bumped when loading changes the cards of unchanged files (e.g. how their
+/// links are rendered): databases loaded by an older version reload their
+/// content, as sync only loads changed files again.
+///
+/// | Version | Change                                         |
+/// |---------|------------------------------------------------|
+/// | 1       | Links relative to markdown files are rewritten |
+/// | 2       | `Cloze :` blocks                               |
+/// | 3       | Reversed cards, sibling keys                   |
+/// | 4       | `Hint :` sections                              |
+/// | 5       | `Expected:` answers                            |
+/// | 6       | `Distractors:` lines                           |
+/// | 7       | Percent-encoded image URLs                     |
+/// | 8       | Sibling keys of repeated blocks                |
+pub const CONTENT_VERSION: i64 = 8;
+
+/// Tokenizers available for the full-text search index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum FtsTokenizer {
+    /// Whole words, case and accent insensitive.
+    #[default]
+    Unicode61,
+    /// Whole words reduced to their English stem ("sorting" matches "sorted").
+    Porter,
+    /// Any substring of 3 characters or more, accent insensitive.
+    Trigram,
+}
+
+impl FtsTokenizer {
+    /// FTS5 `tokenize` option implementing this tokenizer.
+    pub fn option(self) -> &'static str {
+        match self {
+            FtsTokenizer::Unicode61 => "unicode61 remove_diacritics 2",
+            FtsTokenizer::Porter => "porter unicode61 remove_diacritics 2",
+            FtsTokenizer::Trigram => "trigram remove_diacritics 1",
+        }
+    }
+}
+
+impl FromStr for FtsTokenizer {
+    type Err = String;
+
+    /// Parses a tokenizer name (case-insensitive): `unicode61`, `porter` or `trigram`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as clap::ValueEnum>::from_str(s.trim(), true)
+            .map_err(|_| format!("Unknown FTS tokenizer '{}' (expected unicode61, porter or trigram)", s))
+    }
+}
+
+impl fmt::Display for FtsTokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FtsTokenizer::Unicode61 => "unicode61",
+            FtsTokenizer::Porter => "porter",
+            FtsTokenizer::Trigram => "trigram",
+        };
+        f.write_str(name)
+    }
+}
+
 pub fn init_database(pool: &DbPool) -> anyhow::Result<()> {
     let conn = pool.get()?;
 
@@ -7,25 +91,802 @@ pub fn init_database(pool: &DbPool) -> anyhow::Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS flashcards (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_key TEXT,
+            source_path TEXT,
             category TEXT,
             subcategory TEXT,
+            difficulty TEXT,
+            kind TEXT,
+            sibling_key TEXT,
+            expected_answer TEXT,
             question_html TEXT NOT NULL,
-            answer_html TEXT NOT NULL
+            answer_html TEXT NOT NULL,
+            question_text TEXT,
+            answer_text TEXT,
+            thumbnail_html TEXT
         )",
         [],
     )?;
 
+    // Upgrade flashcards tables created before stable card keys (content is reloaded at startup)
+    add_column_if_missing(&conn, "flashcards", "card_key", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "source_path", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "difficulty", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "question_text", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "answer_text", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "kind", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "thumbnail_html", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "sibling_key", "TEXT")?;
+    add_column_if_missing(&conn, "flashcards", "expected_answer", "TEXT")?;
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_flashcards_card_key ON flashcards(card_key)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_flashcards_source_path ON flashcards(source_path)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_flashcards_sibling_key ON flashcards(sibling_key)", [])?;
+
     tracing::info!("Created flashcards table");
 
-    // Create FTS5 virtual table for full-text search
+    // Create FTS5 virtual table for full-text search, on the plain text of cards
+    // (tables indexing rendered HTML are dropped, populate_fts_table() refills them)
+    drop_table_without_column(&conn, "flashcards_fts", "question")?;
+    create_fts_table(&conn, FtsTokenizer::default(), true)?;
+
+    tracing::info!("Created flashcards_fts virtual table");
+
+    // Create content file tracking table (one row per loaded markdown/image file)
     conn.execute(
-        "CREATE VIRTUAL TABLE IF NOT EXISTS flashcards_fts
-         USING fts5(id UNINDEXED, category, subcategory, question_html, answer_html)",
+        "CREATE TABLE IF NOT EXISTS content_files (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            hash TEXT NOT NULL
+        )",
         [],
     )?;
 
-    tracing::info!("Created flashcards_fts virtual table");
+    tracing::info!("Created content_files table");
+
+    // Create tag tables (tags of loaded cards, replaced when their content file is reloaded)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS card_tags (
+            card_key TEXT NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (card_key, tag_id)
+        )",
+        [],
+    )?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_card_tags_tag_id ON card_tags(tag_id)", [])?;
+
+    tracing::info!("Created tags and card_tags tables");
+
+    // Create hint table (hints of loaded cards, in reveal order, replaced with their cards)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS card_hints (
+            card_key TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            hint_html TEXT NOT NULL,
+            PRIMARY KEY (card_key, position)
+        )",
+        [],
+    )?;
+
+    tracing::info!("Created card_hints table");
+
+    // Create distractor table (wrong options of quiz questions declared by cards, replaced with their cards)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS card_distractors (
+            card_key TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            distractor_html TEXT NOT NULL,
+            PRIMARY KEY (card_key, position)
+        )",
+        [],
+    )?;
+
+    tracing::info!("Created card_distractors table");
+
+    // Per-card state used to reference cards by their autoincrement id, which changes on rebuild:
+    // it is set aside with the cards it refers to, until migrate_legacy_reviews() maps them to keys
+    let reviews_set_aside = set_aside_table_without_column(&conn, "reviews", "card_key")?;
+    let review_log_set_aside = set_aside_table_without_column(&conn, "review_log", "card_key")?;
+    if reviews_set_aside || review_log_set_aside {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS legacy_flashcards AS
+             SELECT id, category, subcategory, question_html, answer_html FROM flashcards",
+            [],
+        )?;
+    }
+
+    // Create spaced repetition state table (one row per graded card)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reviews (
+            card_key TEXT PRIMARY KEY,
+            ease REAL NOT NULL,
+            interval_days INTEGER NOT NULL,
+            repetitions INTEGER NOT NULL,
+            lapses INTEGER NOT NULL,
+            due_at INTEGER NOT NULL,
+            last_reviewed_at INTEGER NOT NULL,
+            stability REAL NOT NULL DEFAULT 0,
+            difficulty REAL NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_reviews_due_at ON reviews(due_at)", [])?;
+
+    tracing::info!("Created reviews table");
+
+    // Create review history table (one row per grade given)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_key TEXT NOT NULL,
+            reviewed_at INTEGER NOT NULL,
+            grade TEXT NOT NULL,
+            time_to_reveal_ms INTEGER,
+            time_on_card_ms INTEGER,
+            hints_used INTEGER
+        )",
+        [],
+    )?;
+
+    // Upgrade review_log tables created before hints
+    add_column_if_missing(&conn, "review_log", "hints_used", "INTEGER")?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_review_log_reviewed_at ON review_log(reviewed_at)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_review_log_card_key ON review_log(card_key)", [])?;
+
+    tracing::info!("Created review_log table");
+
+    Ok(())
+}
+
+/// Creates the FTS5 table indexing the plain text of cards with `tokenizer`.
+fn create_fts_table(conn: &Connection, tokenizer: FtsTokenizer, if_not_exists: bool) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "CREATE VIRTUAL TABLE {}flashcards_fts
+             USING fts5(id UNINDEXED, category, subcategory, question, answer, tokenize = '{}')",
+            if if_not_exists { "IF NOT EXISTS " } else { "" },
+            tokenizer.option()
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Makes the FTS table use `tokenizer`, recreating it (empty) if it uses another one.
+///
+/// Returns true if the table was recreated: it must be refilled with `populate_fts_table()`.
+///
+/// # Errors
+/// Returns error if the table cannot be inspected or recreated.
+pub fn set_fts_tokenizer(pool: &DbPool, tokenizer: FtsTokenizer) -> anyhow::Result<bool> {
+    let conn = pool.get()?;
+
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'flashcards_fts'",
+        [],
+        |row| row.get(0),
+    )?;
+    if sql.contains(&format!("tokenize = '{}'", tokenizer.option())) {
+        return Ok(false);
+    }
+
+    conn.execute("DROP TABLE flashcards_fts", [])?;
+    create_fts_table(&conn, tokenizer, false)?;
+    tracing::info!("Recreated flashcards_fts virtual table with {} tokenizer", tokenizer);
+
+    Ok(true)
+}
+
+/// Returns the [`CONTENT_VERSION`] content was last loaded with (0 if never).
+///
+/// # Errors
+/// Returns error if the database cannot be queried.
+pub fn content_version(pool: &DbPool) -> anyhow::Result<i64> {
+    let conn = pool.get()?;
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Records the [`CONTENT_VERSION`] content was loaded with.
+///
+/// # Errors
+/// Returns error if the database cannot be updated.
+pub fn set_content_version(pool: &DbPool, version: i64) -> anyhow::Result<()> {
+    let conn = pool.get()?;
+    conn.pragma_update(None, "user_version", version)?;
+    Ok(())
+}
+
+/// Returns true if `table` exists.
+fn table_exists(conn: &Connection, table: &str) -> rusqlite::Result<bool> {
+    conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")?
+        .exists([table])
+}
+
+/// Returns true if `table` has a column named `column`.
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    conn.prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?
+        .exists([column])
+}
+
+/// Adds a column to an existing table unless it is already there.
+///
+/// Lets databases created by older versions pick up new columns without a rebuild.
+///
+/// # Errors
+/// Returns error if the table cannot be inspected or altered.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> anyhow::Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        tracing::info!("Added column {}.{}", table, column);
+    }
 
     Ok(())
 }
 
+/// Drops an existing table that lacks `column`, so it gets recreated with the current schema.
+///
+/// Used for tables whose rows cannot be converted (e.g. indexing rendered HTML).
+///
+/// # Errors
+/// Returns error if the table cannot be inspected or dropped.
+fn drop_table_without_column(conn: &Connection, table: &str, column: &str) -> anyhow::Result<()> {
+    if table_exists(conn, table)? && !has_column(conn, table, column)? {
+        conn.execute(&format!("DROP TABLE {}", table), [])?;
+        tracing::warn!("Dropped outdated table {} (no {} column)", table, column);
+    }
+
+    Ok(())
+}
+
+/// Renames an existing table that lacks `column` to `legacy_<table>`, so it gets
+/// recreated with the current schema while its rows are converted.
+///
+/// Its indexes are dropped, their names being reused by the new table.
+/// Returns true if the table was renamed.
+///
+/// # Errors
+/// Returns error if the table cannot be inspected or renamed.
+fn set_aside_table_without_column(conn: &Connection, table: &str, column: &str) -> anyhow::Result<bool> {
+    if !table_exists(conn, table)? || has_column(conn, table, column)? {
+        return Ok(false);
+    }
+
+    let indexes: Vec<String> = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL")?
+        .query_map([table], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for index in indexes {
+        conn.execute(&format!("DROP INDEX {}", index), [])?;
+    }
+
+    conn.execute(&format!("DROP TABLE IF EXISTS legacy_{}", table), [])?;
+    conn.execute(&format!("ALTER TABLE {} RENAME TO legacy_{}", table, table), [])?;
+    tracing::warn!("Set aside outdated table {} (no {} column) as legacy_{}", table, column, table);
+
+    Ok(true)
+}
+
+/// Moves the review state and history set aside by [`init_database`] (keyed by
+/// obsolete card ids) to the current tables, then drops the legacy tables.
+///
+/// Old cards are matched to loaded cards by content, as rendering changed since:
+/// markdown cards by category, subcategory and question text (and answer text,
+/// for repeated questions), image cards by image file (see [`legacy_card_keys`]).
+/// Reviews of cards without a match are discarded, each card logged in a warning.
+/// Nothing is done until cards are loaded.
+/// Returns the number of rows migrated.
+///
+/// # Errors
+/// Returns error if the tables cannot be queried or updated.
+pub fn migrate_legacy_reviews(pool: &DbPool) -> anyhow::Result<usize> {
+    let mut conn = pool.get()?;
+
+    if !table_exists(&conn, "legacy_flashcards")? {
+        return Ok(0);
+    }
+    let card_count: i64 = conn.query_row("SELECT COUNT(*) FROM flashcards WHERE card_key IS NOT NULL", [], |row| row.get(0))?;
+    if card_count == 0 {
+        tracing::warn!("No cards loaded, keeping review history of older version for a later run");
+        return Ok(0);
+    }
+
+    let tx = conn.transaction()?;
+
+    let keys = legacy_card_keys(&tx)?;
+    tx.execute("CREATE TEMP TABLE legacy_keys (card_id INTEGER PRIMARY KEY, card_key TEXT NOT NULL)", [])?;
+    {
+        let mut insert = tx.prepare("INSERT INTO legacy_keys (card_id, card_key) VALUES (?1, ?2)")?;
+        for (card_id, card_key) in &keys {
+            insert.execute(rusqlite::params![card_id, card_key])?;
+        }
+    }
+
+    // Reviewed old cards left without key lose their history: tell which ones
+    let mut reviewed = Vec::new();
+    for table in ["legacy_reviews", "legacy_review_log"] {
+        if table_exists(&tx, table)? {
+            reviewed.push(format!("SELECT card_id FROM {}", table));
+        }
+    }
+    if !reviewed.is_empty() {
+        let reviewed = reviewed.join(" UNION ");
+        let mut stmt = tx.prepare(&format!(
+            "SELECT id, question_html FROM legacy_flashcards
+             WHERE id IN ({}) AND id NOT IN (SELECT card_id FROM legacy_keys) ORDER BY id",
+            reviewed
+        ))?;
+        let unmatched = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        for card in unmatched {
+            let (id, question_html) = card?;
+            tracing::warn!("No loaded card matches card {} of older version ({:?}), discarding its reviews", id, html_text(&question_html));
+        }
+    }
+
+    let mut migrated = 0;
+    let mut discarded = 0;
+    if table_exists(&tx, "legacy_reviews")? {
+        // Reviews tables created before FSRS support
+        add_column_if_missing(&tx, "legacy_reviews", "stability", "REAL NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&tx, "legacy_reviews", "difficulty", "REAL NOT NULL DEFAULT 0")?;
+
+        let total: usize = tx.query_row("SELECT COUNT(*) FROM legacy_reviews", [], |row| row.get(0))?;
+        let count = tx.execute(
+            "INSERT OR IGNORE INTO reviews
+                 (card_key, ease, interval_days, repetitions, lapses, due_at, last_reviewed_at, stability, difficulty)
+             SELECT legacy_keys.card_key, ease, interval_days, repetitions, lapses, due_at, last_reviewed_at,
+                    stability, difficulty
+             FROM legacy_reviews JOIN legacy_keys ON legacy_keys.card_id = legacy_reviews.card_id",
+            [],
+        )?;
+        migrated += count;
+        discarded += total - count;
+        tx.execute("DROP TABLE legacy_reviews", [])?;
+    }
+
+    if table_exists(&tx, "legacy_review_log")? {
+        let total: usize = tx.query_row("SELECT COUNT(*) FROM legacy_review_log", [], |row| row.get(0))?;
+        let count = tx.execute(
+            "INSERT INTO review_log (card_key, reviewed_at, grade, time_to_reveal_ms, time_on_card_ms)
+             SELECT legacy_keys.card_key, reviewed_at, grade, time_to_reveal_ms, time_on_card_ms
+             FROM legacy_review_log JOIN legacy_keys ON legacy_keys.card_id = legacy_review_log.card_id
+             ORDER BY legacy_review_log.id",
+            [],
+        )?;
+        migrated += count;
+        discarded += total - count;
+        tx.execute("DROP TABLE legacy_review_log", [])?;
+    }
+
+    tx.execute("DROP TABLE legacy_keys", [])?;
+    tx.execute("DROP TABLE legacy_flashcards", [])?;
+    tx.commit()?;
+
+    tracing::info!("Migrated {} review rows of older version to card keys", migrated);
+    if discarded > 0 {
+        tracing::warn!("Discarded {} review rows of older version whose card was not found", discarded);
+    }
+
+    Ok(migrated)
+}
+
+/// Returns the plain text of rendered HTML: tags dropped, character references decoded.
+fn html_text(html: &str) -> String {
+    let text = HTML_TAG_REGEX.replace_all(html, " ");
+    let text = HTML_ENTITY_REGEX.replace_all(&text, |caps: &regex::Captures| {
+        let decoded = match (caps.get(1), caps.get(2), caps.get(3)) {
+            (Some(name), _, _) => match name.as_str() {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => Some(' '),
+            },
+            (_, Some(decimal), _) => decimal.as_str().parse().ok().and_then(char::from_u32),
+            (_, _, Some(hex)) => u32::from_str_radix(hex.as_str(), 16).ok().and_then(char::from_u32),
+            _ => None,
+        };
+        decoded.map_or_else(|| caps[0].to_string(), String::from)
+    });
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns `text` without whitespace, so texts compare whatever their markup split them into.
+fn compact(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Category, subcategory and compact question text of a card, compared between versions.
+type QuestionId = (Option<String>, Option<String>, String);
+
+/// Compact answer text of a card and the file names of its images, compared between versions.
+type AnswerId = (String, Vec<String>);
+
+/// Returns the answer of a card as compared between versions: its compact text and
+/// the file names of its images (their URLs changed, not their names).
+fn answer_id(text: &str, html: &str) -> AnswerId {
+    let images = IMG_SRC_REGEX
+        .captures_iter(html)
+        .map(|caps| {
+            let name = caps[1].rsplit('/').next().unwrap_or_default();
+            percent_decode_str(name).decode_utf8_lossy().into_owned()
+        })
+        .collect();
+    (compact(text), images)
+}
+
+/// Maps the cards set aside in `legacy_flashcards` to the keys of loaded cards.
+///
+/// Rendered HTML changed since (highlighting, links...), so cards are compared on
+/// their plain text, the way keys are hashed: a markdown card matches the only
+/// loaded card of its category and subcategory with the same question text, or
+/// else with the same question and answer (text and images). An image card matches the card
+/// of its image file. Loaded cards matched by several old cards are left out.
+///
+/// # Errors
+/// Returns error if the tables cannot be queried.
+fn legacy_card_keys(conn: &Connection) -> anyhow::Result<Vec<(i64, String)>> {
+    // Loaded cards by question: (key, answer); image cards by source path
+    let mut by_question: HashMap<QuestionId, Vec<(String, AnswerId)>> = HashMap::new();
+    let mut by_source: HashMap<String, String> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT card_key, kind, source_path, category, subcategory, question_text, answer_text, answer_html
+         FROM flashcards WHERE card_key IS NOT NULL",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let card_key: String = row.get(0)?;
+        if row.get::<_, Option<String>>(1)?.as_deref() == Some("image")
+            && let Some(source_path) = row.get::<_, Option<String>>(2)?
+        {
+            by_source.insert(source_path, card_key.clone());
+        }
+        let question = compact(&row.get::<_, Option<String>>(5)?.unwrap_or_default());
+        let answer = answer_id(&row.get::<_, Option<String>>(6)?.unwrap_or_default(), &row.get::<_, String>(7)?);
+        by_question.entry((row.get(3)?, row.get(4)?, question)).or_default().push((card_key, answer));
+    }
+
+    let mut keys: Vec<(i64, String)> = Vec::new();
+    let mut stmt = conn.prepare("SELECT id, category, subcategory, question_html, answer_html FROM legacy_flashcards")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        let question_html: String = row.get(3)?;
+        let answer_html: String = row.get(4)?;
+
+        // Rendered with a "Question:" / "Answer:" header
+        let question = compact(html_text(&question_html).trim_start_matches("Question:"));
+        let answer = answer_id(html_text(&answer_html).trim_start_matches("Answer:"), &answer_html);
+
+        let key = match LEGACY_IMAGE_REGEX.captures(&answer_html) {
+            Some(caps) if question.is_empty() => by_source.get(&format!("img/{}", &caps[1])).cloned(),
+            _ => by_question.get(&(row.get(1)?, row.get(2)?, question)).and_then(|candidates| match candidates.as_slice() {
+                [(key, _)] => Some(key.clone()),
+                _ => match candidates.iter().filter(|(_, a)| *a == answer).collect::<Vec<_>>().as_slice() {
+                    [(key, _)] => Some(key.clone()),
+                    _ => None,
+                },
+            }),
+        };
+        if let Some(key) = key {
+            keys.push((id, key));
+        }
+    }
+
+    // A loaded card cannot tell which of several old cards it comes from
+    let mut claims: HashMap<&str, usize> = HashMap::new();
+    for (_, key) in &keys {
+        *claims.entry(key.as_str()).or_default() += 1;
+    }
+    let shared: Vec<String> = claims.into_iter().filter(|(_, n)| *n > 1).map(|(key, _)| key.to_string()).collect();
+    keys.retain(|(_, key)| !shared.contains(key));
+
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Creates in-memory database pool for testing.
+    fn setup_test_pool() -> DbPool {
+        let manager = r2d2_sqlite::SqliteConnectionManager::memory();
+        r2d2::Pool::builder().max_size(1).build(manager).unwrap()
+    }
+
+    fn column_names(pool: &DbPool, table: &str) -> Vec<String> {
+        let conn = pool.get().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT name FROM pragma_table_info('{}')", table)).unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn test_content_version() {
+        let pool = setup_test_pool();
+        init_database(&pool).unwrap();
+        assert_eq!(content_version(&pool).unwrap(), 0);
+
+        set_content_version(&pool, CONTENT_VERSION).unwrap();
+
+        assert_eq!(content_version(&pool).unwrap(), CONTENT_VERSION);
+    }
+
+    #[test]
+    fn test_init_database_is_idempotent() {
+        let pool = setup_test_pool();
+
+        init_database(&pool).unwrap();
+        init_database(&pool).unwrap();
+
+        let columns = column_names(&pool, "reviews");
+        assert!(columns.contains(&"stability".to_string()));
+        assert!(columns.contains(&"difficulty".to_string()));
+    }
+
+    #[test]
+    fn test_init_database_adds_card_key_to_old_flashcards_table() {
+        let pool = setup_test_pool();
+        pool.get()
+            .unwrap()
+            .execute(
+                "CREATE TABLE flashcards (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    category TEXT,
+                    subcategory TEXT,
+                    question_html TEXT NOT NULL,
+                    answer_html TEXT NOT NULL
+                )",
+                [],
+            )
+            .unwrap();
+
+        init_database(&pool).unwrap();
+
+        let columns = column_names(&pool, "flashcards");
+        assert!(columns.contains(&"card_key".to_string()));
+        assert!(columns.contains(&"source_path".to_string()));
+    }
+
+    fn fts_sql(pool: &DbPool) -> String {
+        let conn = pool.get().unwrap();
+        conn.query_row("SELECT sql FROM sqlite_master WHERE name = 'flashcards_fts'", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_init_database_replaces_fts_table_indexing_html() {
+        let pool = setup_test_pool();
+        pool.get()
+            .unwrap()
+            .execute(
+                "CREATE VIRTUAL TABLE flashcards_fts
+                 USING fts5(id UNINDEXED, category, subcategory, question_html, answer_html)",
+                [],
+            )
+            .unwrap();
+
+        init_database(&pool).unwrap();
+
+        let columns = column_names(&pool, "flashcards_fts");
+        assert!(columns.contains(&"question".to_string()));
+        assert!(!columns.contains(&"question_html".to_string()));
+        assert!(fts_sql(&pool).contains("remove_diacritics 2"));
+    }
+
+    #[rstest]
+    #[case(FtsTokenizer::Unicode61, false)]
+    #[case(FtsTokenizer::Porter, true)]
+    #[case(FtsTokenizer::Trigram, true)]
+    fn test_set_fts_tokenizer(#[case] tokenizer: FtsTokenizer, #[case] recreated: bool) {
+        let pool = setup_test_pool();
+        init_database(&pool).unwrap();
+
+        assert_eq!(set_fts_tokenizer(&pool, tokenizer).unwrap(), recreated);
+        assert!(fts_sql(&pool).contains(tokenizer.option()));
+        // Already using it
+        assert!(!set_fts_tokenizer(&pool, tokenizer).unwrap());
+    }
+
+    #[rstest]
+    #[case("unicode61", FtsTokenizer::Unicode61)]
+    #[case("Porter", FtsTokenizer::Porter)]
+    #[case(" trigram ", FtsTokenizer::Trigram)]
+    fn test_fts_tokenizer_from_str(#[case] name: &str, #[case] expected: FtsTokenizer) {
+        assert_eq!(name.parse::<FtsTokenizer>().unwrap(), expected);
+        assert_eq!(expected.to_string(), name.trim().to_lowercase());
+    }
+
+    #[test]
+    fn test_fts_tokenizer_from_str_unknown() {
+        assert!("ascii".parse::<FtsTokenizer>().is_err());
+    }
+
+    #[test]
+    fn test_init_database_replaces_reviews_keyed_by_id() {
+        let pool = setup_test_pool();
+        pool.get()
+            .unwrap()
+            .execute(
+                "CREATE TABLE reviews (
+                    card_id INTEGER PRIMARY KEY,
+                    ease REAL NOT NULL,
+                    interval_days INTEGER NOT NULL,
+                    repetitions INTEGER NOT NULL,
+                    lapses INTEGER NOT NULL,
+                    due_at INTEGER NOT NULL,
+                    last_reviewed_at INTEGER NOT NULL
+                )",
+                [],
+            )
+            .unwrap();
+        pool.get()
+            .unwrap()
+            .execute("CREATE INDEX idx_reviews_due_at ON reviews(due_at)", [])
+            .unwrap();
+
+        init_database(&pool).unwrap();
+
+        let columns = column_names(&pool, "reviews");
+        assert!(columns.contains(&"card_key".to_string()));
+        assert!(!columns.contains(&"card_id".to_string()));
+        assert!(column_names(&pool, "legacy_reviews").contains(&"card_id".to_string()));
+        let index_table: String = pool
+            .get()
+            .unwrap()
+            .query_row("SELECT tbl_name FROM sqlite_master WHERE name = 'idx_reviews_due_at'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(index_table, "reviews");
+    }
+
+    #[test]
+    fn test_migrate_legacy_reviews() {
+        let pool = setup_test_pool();
+        let conn = pool.get().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE flashcards (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 category TEXT,
+                 subcategory TEXT,
+                 question_html TEXT NOT NULL,
+                 answer_html TEXT NOT NULL
+             );
+             CREATE TABLE reviews (
+                 card_id INTEGER PRIMARY KEY,
+                 ease REAL NOT NULL,
+                 interval_days INTEGER NOT NULL,
+                 repetitions INTEGER NOT NULL,
+                 lapses INTEGER NOT NULL,
+                 due_at INTEGER NOT NULL,
+                 last_reviewed_at INTEGER NOT NULL
+             );
+             CREATE TABLE review_log (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 card_id INTEGER NOT NULL,
+                 reviewed_at INTEGER NOT NULL,
+                 grade TEXT NOT NULL,
+                 time_to_reveal_ms INTEGER,
+                 time_on_card_ms INTEGER
+             );
+             INSERT INTO flashcards (id, category, subcategory, question_html, answer_html) VALUES
+                 (1, 'Rust', 'Types', '<h3>Question:</h3>\n<p>What does <code>Box&lt;T&gt;</code> do?</p>\n',
+                     '<h3>Answer:</h3>\n<p>Heap pointer</p>\n'),
+                 (2, 'Rust', 'Basics', '<h3>Question:</h3>\n<p>Removed?</p>\n', '<h3>Answer:</h3>\n<p>B</p>\n'),
+                 (3, 'Rust', 'Basics', '<h3>Question:</h3>\n<p>Repeated?</p>\n', '<h3>Answer:</h3>\n<p>C</p>\n'),
+                 (4, 'Rust', 'Basics', '<h3>Question:</h3>\n<p>Repeated?</p>\n', '<h3>Answer:</h3>\n<p>C</p>\n'),
+                 (5, 'Rust', 'Basics', '<h3>Question:</h3>\n<p>Twin?</p>\n', '<h3>Answer:</h3>\n<p>E</p>\n'),
+                 (6, 'Rust', 'Basics', '<h3>Question:</h3>\n<p>Twin?</p>\n', '<h3>Answer:</h3>\n<p>F</p>\n'),
+                 (7, NULL, NULL, '<h3>Question:</h3>\n',
+                     '<h3>Answer:</h3>\n<p align=\"center\"><img src=''/static/deck/img/maths/bayes.png'' class=''img-fluid''></p>');
+             INSERT INTO reviews VALUES (1, 2.5, 6, 2, 0, 100, 50), (2, 2.5, 1, 1, 0, 100, 50), (3, 2.5, 1, 1, 0, 100, 50),
+                                        (4, 2.5, 1, 1, 0, 100, 50), (6, 2.5, 3, 1, 0, 100, 50), (7, 2.5, 9, 3, 0, 100, 50);
+             INSERT INTO review_log (card_id, reviewed_at, grade)
+                 VALUES (1, 40, 'good'), (1, 50, 'easy'), (2, 50, 'hard'), (7, 60, 'good');",
+        )
+        .unwrap();
+        drop(conn);
+
+        init_database(&pool).unwrap();
+        assert_eq!(migrate_legacy_reviews(&pool).unwrap(), 0);
+
+        // Content reloaded: card 1 is rendered differently, card 2 was removed, cards 3 and 4 both match
+        // the same card, card 6 is told from card 5 by its answer, card 7 took a category from its directory
+        let conn = pool.get().unwrap();
+        conn.execute_batch(
+            "DELETE FROM flashcards;
+             INSERT INTO flashcards
+                 (card_key, kind, source_path, category, subcategory, question_html, answer_html, question_text, answer_text)
+             VALUES
+                 ('box', 'markdown', 'md/rust.md', 'Rust', 'Types',
+                     '<h3>Question:</h3>\n<p>What does <code class=\"language-rust\">Box&lt;T&gt;</code> do?</p>\n',
+                     '<h3>Answer:</h3>\n<p>Heap pointer</p>\n', 'What does Box<T> do?', 'Heap pointer'),
+                 ('new', 'markdown', 'md/rust.md', 'Rust', 'Basics', '<p>New?</p>', '<p>D</p>', 'New?', 'D'),
+                 ('repeated', 'markdown', 'md/rust.md', 'Rust', 'Basics', '<p>Repeated?</p>', '<p>C</p>', 'Repeated?', 'C'),
+                 ('twin', 'markdown', 'md/rust.md', 'Rust', 'Basics', '<p>Twin?</p>', '<p>E</p>', 'Twin?', 'E'),
+                 ('twin-2', 'markdown', 'md/rust.md', 'Rust', 'Basics', '<p>Twin?</p>', '<p>F</p>', 'Twin?', 'F'),
+                 ('bayes', 'image', 'img/maths/bayes.png', 'maths', NULL, '', '<img src=''/static/deck/img/maths/bayes.png''>',
+                     '', '');",
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_eq!(migrate_legacy_reviews(&pool).unwrap(), 6);
+
+        let conn = pool.get().unwrap();
+        let reviews: Vec<(String, i64)> = conn
+            .prepare("SELECT card_key, interval_days FROM reviews ORDER BY card_key")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(reviews, vec![("bayes".to_string(), 9), ("box".to_string(), 6), ("twin-2".to_string(), 3)]);
+        let log: Vec<(String, String)> = conn
+            .prepare("SELECT card_key, grade FROM review_log ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            log,
+            vec![
+                ("box".to_string(), "good".to_string()),
+                ("box".to_string(), "easy".to_string()),
+                ("bayes".to_string(), "good".to_string())
+            ]
+        );
+        for table in ["legacy_flashcards", "legacy_reviews", "legacy_review_log"] {
+            assert!(!table_exists(&conn, table).unwrap());
+        }
+    }
+
+    #[rstest]
+    #[case("<h3>Question:</h3>\n<p>What does <code>Box&lt;T&gt;</code> do?</p>\n", "Question: What does Box<T> do?")]
+    #[case("<p>Tom &amp; Jerry&#39;s &#x2F;path&nbsp;here</p>", "Tom & Jerry's /path here")]
+    #[case("<p>&bogus; stays</p>", "&bogus; stays")]
+    fn test_html_text(#[case] html: &str, #[case] expected: &str) {
+        assert_eq!(html_text(html), expected);
+    }
+
+    #[test]
+    fn test_answer_id_compares_image_names() {
+        let old = answer_id("Versions exist", "<p>Versions exist</p><img src=\"/static/deck/md/covers/my%20book.webp\"/>");
+        let new = answer_id("Versions  exist", "<p>Versions exist</p><img src='/static/deck/md/covers/my%20book.webp'>");
+        let other = answer_id("Versions exist", "<p>Versions exist</p><img src='/static/deck/md/covers/other.webp'>");
+
+        assert_eq!(old, ("Versionsexist".to_string(), vec!["my book.webp".to_string()]));
+        assert_eq!(old, new);
+        assert_ne!(old, other);
+    }
+
+    #[test]
+    fn test_init_database_keeps_current_reviews() {
+        let pool = setup_test_pool();
+        init_database(&pool).unwrap();
+        pool.get()
+            .unwrap()
+            .execute("INSERT INTO reviews VALUES ('abc', 2.5, 6, 2, 0, 100, 50, 0, 0)", [])
+            .unwrap();
+
+        init_database(&pool).unwrap();
+
+        let count: i64 = pool
+            .get()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM reviews", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+}
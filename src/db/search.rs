@@ -0,0 +1,396 @@
+// Rust guideline compliant 2025-01
+//! Search query language for keyword filtering.
+//!
+//! User input is parsed here and compiled to an FTS5 `MATCH` expression in
+//! which every term is quoted, so characters meaningful to FTS5 (`+`, `-`, `"`,
+//! `*`, `:`, parentheses) never cause syntax errors. Supported syntax:
+//!
+//! | Input | Matches cards |
+//! |-------|---------------|
+//! | `train split` | containing both words |
+//! | `"train test split"` | containing the exact phrase |
+//! | `pandas OR polars` | containing either word |
+//! | `-sklearn` | not containing the word |
+//! | `tokeni*` | containing a word starting with `tokeni` |
+//! | `q:borrow`, `a:borrow`, `cat:rust` | with the word in the question, answer, or category/subcategory |
+//!
+//! `OR` binds tighter than the implicit AND: `rust async OR tokio` is
+//! `rust AND (async OR tokio)`.
+
+use std::fmt;
+
+/// FTS5 column filter of each searchable field.
+const QUESTION_COLUMNS: &str = "question";
+const ANSWER_COLUMNS: &str = "answer";
+const CATEGORY_COLUMNS: &str = "{category subcategory}";
+
+/// Invalid search query, with a message meant for users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchError {
+    /// A `"` without closing quote.
+    UnclosedQuote,
+    /// `-` or a field prefix with nothing to search after it.
+    EmptyTerm(String),
+    /// `""` with nothing to search between the quotes.
+    EmptyPhrase,
+    /// `*` not following a word.
+    LonePrefix,
+    /// `OR` at the start or end of the query, or next to another `OR`.
+    MisplacedOr,
+    /// Excluded term on one side of an `OR`.
+    ExcludedInOr,
+    /// Only excluded terms: FTS5 cannot search for "everything but".
+    OnlyExclusions,
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::UnclosedQuote => f.write_str("missing closing quote"),
+            SearchError::EmptyTerm(after) => write!(f, "nothing to search after '{}'", after),
+            SearchError::EmptyPhrase => f.write_str("nothing to search between quotes"),
+            SearchError::LonePrefix => f.write_str("'*' must follow a word (e.g. tokeni*)"),
+            SearchError::MisplacedOr => f.write_str("OR must be placed between two search terms"),
+            SearchError::ExcludedInOr => f.write_str("excluded terms (-word) cannot be combined with OR"),
+            SearchError::OnlyExclusions => f.write_str("add a term to search for besides excluded ones"),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+/// Part of a card a term is searched in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Question,
+    Answer,
+    Category,
+}
+
+impl Field {
+    /// Parses a `q:` / `a:` / `cat:` prefix name (case-insensitive).
+    fn from_prefix(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "q" => Some(Field::Question),
+            "a" => Some(Field::Answer),
+            "cat" => Some(Field::Category),
+            _ => None,
+        }
+    }
+
+    fn columns(self) -> &'static str {
+        match self {
+            Field::Question => QUESTION_COLUMNS,
+            Field::Answer => ANSWER_COLUMNS,
+            Field::Category => CATEGORY_COLUMNS,
+        }
+    }
+}
+
+/// Word or phrase to search for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    /// Word, or words of a quoted phrase.
+    pub text: String,
+    /// Field the term is restricted to (None = anywhere).
+    pub field: Option<Field>,
+    /// Matches words starting with the (last) word.
+    pub prefix: bool,
+}
+
+impl Term {
+    /// Compiles to an FTS5 phrase, quoted so its content is never FTS5 syntax.
+    fn to_fts5(&self) -> String {
+        let mut phrase = format!("\"{}\"", self.text.replace('"', "\"\""));
+        if self.prefix {
+            phrase.push_str(" *");
+        }
+        match self.field {
+            Some(field) => format!("{} : {}", field.columns(), phrase),
+            None => phrase,
+        }
+    }
+}
+
+/// Parsed search query: every group must match, and no excluded term.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Groups of alternatives (`a OR b`), all required.
+    pub groups: Vec<Vec<Term>>,
+    /// Terms cards must not contain.
+    pub excluded: Vec<Term>,
+}
+
+/// Token of the query input.
+enum Token {
+    Term { term: Term, excluded: bool },
+    Or,
+}
+
+impl SearchQuery {
+    /// Parses a search query.
+    ///
+    /// # Errors
+    /// Returns error describing the problem if the query is malformed.
+    pub fn parse(input: &str) -> Result<Self, SearchError> {
+        let mut query = SearchQuery::default();
+        // Whether the previous token is an excluded term (None at start and after OR)
+        let mut previous_excluded = None;
+        let mut or_pending = false;
+
+        for token in tokenize(input)? {
+            match token {
+                Token::Or => {
+                    match previous_excluded {
+                        None => return Err(SearchError::MisplacedOr),
+                        Some(true) => return Err(SearchError::ExcludedInOr),
+                        Some(false) => {}
+                    }
+                    or_pending = true;
+                    previous_excluded = None;
+                }
+                Token::Term { term, excluded: true } => {
+                    if or_pending {
+                        return Err(SearchError::ExcludedInOr);
+                    }
+                    query.excluded.push(term);
+                    previous_excluded = Some(true);
+                }
+                Token::Term { term, excluded: false } => {
+                    match query.groups.last_mut() {
+                        Some(group) if or_pending => group.push(term),
+                        _ => query.groups.push(vec![term]),
+                    }
+                    or_pending = false;
+                    previous_excluded = Some(false);
+                }
+            }
+        }
+
+        if or_pending {
+            return Err(SearchError::MisplacedOr);
+        }
+        if query.groups.is_empty() && !query.excluded.is_empty() {
+            return Err(SearchError::OnlyExclusions);
+        }
+        Ok(query)
+    }
+
+    /// Compiles to an FTS5 `MATCH` expression (None for an empty query).
+    pub fn to_fts5(&self) -> Option<String> {
+        if self.groups.is_empty() {
+            return None;
+        }
+
+        let required = self
+            .groups
+            .iter()
+            .map(|group| match group.as_slice() {
+                [term] => term.to_fts5(),
+                terms => format!("({})", terms.iter().map(Term::to_fts5).collect::<Vec<_>>().join(" OR ")),
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        if self.excluded.is_empty() {
+            return Some(required);
+        }
+        let excluded = self.excluded.iter().map(Term::to_fts5).collect::<Vec<_>>().join(" OR ");
+        Some(format!("({}) NOT ({})", required, excluded))
+    }
+}
+
+/// Parses `input` and compiles it to an FTS5 `MATCH` expression (None for an empty query).
+///
+/// # Errors
+/// Returns error describing the problem if the query is malformed.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::db::search::compile;
+///
+/// let fts = compile("\"train test split\" -sklearn").unwrap();
+/// assert_eq!(fts.as_deref(), Some("(\"train test split\") NOT (\"sklearn\")"));
+/// ```
+pub fn compile(input: &str) -> Result<Option<String>, SearchError> {
+    Ok(SearchQuery::parse(input)?.to_fts5())
+}
+
+/// Splits the input into terms and `OR` operators.
+fn tokenize(input: &str) -> Result<Vec<Token>, SearchError> {
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if &rest[..word_end] == "OR" {
+            tokens.push(Token::Or);
+            rest = rest[word_end..].trim_start();
+            continue;
+        }
+
+        let excluded = rest.starts_with('-');
+        if excluded {
+            rest = &rest[1..];
+        }
+
+        // Field prefix, only for known fields ("std::vec" is a word)
+        let mut field = None;
+        let word = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+        if let Some((name, _)) = word.split_once(':')
+            && let Some(known) = Field::from_prefix(name)
+        {
+            field = Some((known, &rest[..=name.len()]));
+            rest = &rest[name.len() + 1..];
+        }
+
+        let (text, prefix, remaining) = if let Some(phrase) = rest.strip_prefix('"') {
+            let end = phrase.find('"').ok_or(SearchError::UnclosedQuote)?;
+            let after = &phrase[end + 1..];
+            match after.strip_prefix('*') {
+                Some(after) => (&phrase[..end], true, after),
+                None => (&phrase[..end], false, after),
+            }
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let word = rest[..end].trim_end_matches('*');
+            if word.is_empty() && end > 0 {
+                return Err(SearchError::LonePrefix);
+            }
+            (word, word.len() < end, &rest[end..])
+        };
+
+        if text.trim().is_empty() {
+            return Err(match field {
+                Some((_, prefix)) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+                    SearchError::EmptyTerm(prefix.to_string())
+                }
+                None if excluded && !rest.starts_with('"') => SearchError::EmptyTerm("-".to_string()),
+                _ => SearchError::EmptyPhrase,
+            });
+        }
+
+        tokens.push(Token::Term {
+            term: Term {
+                text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+                field: field.map(|(field, _)| field),
+                prefix,
+            },
+            excluded,
+        });
+        rest = remaining.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", None)]
+    #[case("   ", None)]
+    #[case("rust", Some(r#""rust""#))]
+    #[case("rust async", Some(r#""rust" AND "async""#))]
+    #[case(r#""train test split""#, Some(r#""train test split""#))]
+    #[case(r#""train   test""#, Some(r#""train test""#))]
+    #[case("pandas OR polars", Some(r#"("pandas" OR "polars")"#))]
+    #[case("rust async OR tokio", Some(r#""rust" AND ("async" OR "tokio")"#))]
+    #[case("a OR b OR c", Some(r#"("a" OR "b" OR "c")"#))]
+    #[case("or and not", Some(r#""or" AND "and" AND "not""#))]
+    #[case(r#""train test split" -sklearn"#, Some(r#"("train test split") NOT ("sklearn")"#))]
+    #[case("rust -async -tokio", Some(r#"("rust") NOT ("async" OR "tokio")"#))]
+    #[case("tokeni*", Some(r#""tokeni" *"#))]
+    #[case(r#""train te"*"#, Some(r#""train te" *"#))]
+    #[case("q:borrow", Some(r#"question : "borrow""#))]
+    #[case("A:borrow*", Some(r#"answer : "borrow" *"#))]
+    #[case(r#"cat:"machine learning""#, Some(r#"{category subcategory} : "machine learning""#))]
+    #[case("rust -q:async", Some(r#"("rust") NOT (question : "async")"#))]
+    #[case("C++", Some(r#""C++""#))]
+    #[case("std::vec", Some(r#""std::vec""#))]
+    #[case("x:y", Some(r#""x:y""#))]
+    #[case("a:b:c", Some(r#"answer : "b:c""#))]
+    #[case(r#"say"hi"#, Some(r#""say""hi""#))]
+    #[case("(NEAR) AND", Some(r#""(NEAR)" AND "AND""#))]
+    #[case("well-known", Some(r#""well-known""#))]
+    fn test_compile(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(compile(input).unwrap().as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(r#""unclosed"#, SearchError::UnclosedQuote)]
+    #[case(r#"rust "train test"#, SearchError::UnclosedQuote)]
+    #[case(r#""""#, SearchError::EmptyPhrase)]
+    #[case(r#"rust -"  ""#, SearchError::EmptyPhrase)]
+    #[case("rust -", SearchError::EmptyTerm("-".to_string()))]
+    #[case("q: rust", SearchError::EmptyTerm("q:".to_string()))]
+    #[case("rust CAT:", SearchError::EmptyTerm("CAT:".to_string()))]
+    #[case("*", SearchError::LonePrefix)]
+    #[case("rust -**", SearchError::LonePrefix)]
+    #[case("OR rust", SearchError::MisplacedOr)]
+    #[case("rust OR", SearchError::MisplacedOr)]
+    #[case("rust OR OR tokio", SearchError::MisplacedOr)]
+    #[case("rust OR -tokio", SearchError::ExcludedInOr)]
+    #[case("-rust OR tokio", SearchError::ExcludedInOr)]
+    #[case("-sklearn", SearchError::OnlyExclusions)]
+    fn test_parse_errors(#[case] input: &str, #[case] expected: SearchError) {
+        assert_eq!(SearchQuery::parse(input), Err(expected));
+    }
+
+    #[test]
+    fn test_parse_groups_and_exclusions() {
+        let query = SearchQuery::parse("rust async OR q:tokio* -python").unwrap();
+
+        let term = |text: &str, field, prefix| Term { text: text.to_string(), field, prefix };
+        assert_eq!(
+            query.groups,
+            vec![
+                vec![term("rust", None, false)],
+                vec![term("async", None, false), term("tokio", Some(Field::Question), true)],
+            ]
+        );
+        assert_eq!(query.excluded, vec![term("python", None, false)]);
+    }
+
+    #[test]
+    fn test_error_messages_are_readable() {
+        assert_eq!(SearchError::UnclosedQuote.to_string(), "missing closing quote");
+        assert_eq!(SearchError::EmptyTerm("q:".to_string()).to_string(), "nothing to search after 'q:'");
+    }
+
+    // ========== Property-Based Tests ==========
+
+    /// Compiled queries must never be rejected by FTS5, whatever the user typed.
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Creates an FTS5 table with the searchable columns of the deck index.
+        fn fts_connection() -> rusqlite::Connection {
+            let conn = rusqlite::Connection::open_in_memory().unwrap();
+            conn.execute_batch(
+                "CREATE VIRTUAL TABLE flashcards_fts USING fts5(id UNINDEXED, category, subcategory, question, answer);
+                 INSERT INTO flashcards_fts VALUES (1, 'Python', 'Pandas', '<p>train test split?</p>', '<p>C++ -x \"q\"</p>');",
+            )
+            .unwrap();
+            conn
+        }
+
+        #[test]
+        fn prop_compiled_query_is_valid_fts5() {
+            let conn = fts_connection();
+            proptest!(|(input in r#"[a-zA-Z0-9 "*:+()^{}.\-]{0,24}|(OR|q:|a:|cat:|-|"|\*| )*"#)| {
+                if let Ok(Some(fts)) = compile(&input) {
+                    let result = conn.query_row(
+                        "SELECT COUNT(*) FROM flashcards_fts WHERE flashcards_fts MATCH ?",
+                        [&fts],
+                        |row| row.get::<_, i64>(0),
+                    );
+                    prop_assert!(result.is_ok(), "{:?} compiled to invalid {:?}: {:?}", input, fts, result);
+                }
+            });
+        }
+    }
+}
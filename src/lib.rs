@@ -5,4 +5,5 @@ pub mod config;
 pub mod content;
 pub mod db;
 pub mod routes;
+pub mod scheduler;
 pub mod session;
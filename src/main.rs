@@ -28,17 +28,42 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Starting rust-flashcards application");
 
     // Load configuration (CLI args override env vars)
-    let config = config::Config::from_env(cli_args.deck.clone(), cli_args.deck_name.clone())?;
+    let mut config = config::Config::from_env(cli_args.deck.clone(), cli_args.deck_name.clone())?;
+    if let Some(scheduler) = cli_args.scheduler {
+        config.scheduler = scheduler;
+    }
+    if let Some(fts_tokenizer) = cli_args.fts_tokenizer {
+        config.fts_tokenizer = fts_tokenizer;
+    }
     tracing::info!(
-        "Configuration loaded: port={}, database={}, deck_id={}, deck_display_name={}, md_path={}, img_path={}",
+        "Configuration loaded: port={}, database={}, deck_id={}, deck_display_name={}, md_path={}, img_path={}, scheduler={}, fts_tokenizer={}",
         config.port,
         config.database_url,
         config.deck_id,
         config.deck_display_name,
         config.md_path,
-        config.img_path
+        config.img_path,
+        config.scheduler,
+        config.fts_tokenizer
     );
 
+    // Lint deck content and exit, without touching the database
+    if cli_args.command == Some(cli::Command::Check) {
+        if content::validate_content_directory(&config.md_path) != content::ContentDirStatus::Valid {
+            eprintln!("ERROR: markdown directory unavailable: {}", config.md_path);
+            std::process::exit(1);
+        }
+
+        let diagnostics = content::check::check_markdown_dir(&config.md_path, std::path::Path::new("."));
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic);
+        }
+
+        let errors = diagnostics.iter().filter(|d| d.rule.severity() == content::check::Severity::Error).count();
+        println!("{} error(s), {} warning(s)", errors, diagnostics.len() - errors);
+        std::process::exit(if errors > 0 { 1 } else { 0 });
+    }
+
     // Handle database rebuild if requested
     if let Some(deck_id) = cli_args.rebuild_deck.as_ref() {
         // Rebuild specified deck (may differ from loaded deck)
@@ -46,14 +71,14 @@ async fn main() -> anyhow::Result<()> {
         let path = std::path::Path::new(&db_path_to_rebuild);
 
         if path.exists() {
-            tracing::info!("Deleting existing database: {}", db_path_to_rebuild);
-            std::fs::remove_file(path).unwrap_or_else(|e| {
-                tracing::error!("Failed to delete database: {}", e);
-                std::process::exit(1);
-            });
-            tracing::info!("Database deleted, will rebuild from content");
+            // Only flashcards are cleared: review history is keyed by stable card keys and survives
+            tracing::info!("Clearing flashcards of existing database: {}", db_path_to_rebuild);
+            let rebuild_pool = db::create_pool(&db_path_to_rebuild)?;
+            db::init_database(&rebuild_pool)?;
+            db::queries::clear_flashcards(&rebuild_pool)?;
+            tracing::info!("Flashcards cleared, will rebuild from content");
         } else {
-            tracing::warn!("Database file not found: {}, nothing to delete", db_path_to_rebuild);
+            tracing::warn!("Database file not found: {}, nothing to rebuild", db_path_to_rebuild);
         }
 
         // Warn if rebuilding different deck than loading
@@ -68,59 +93,71 @@ async fn main() -> anyhow::Result<()> {
     // Initialize database schema
     db::init_database(&pool)?;
 
-    // Load content only if database is empty (fast startup optimization)
-    if db::queries::is_database_empty(&pool)? {
-        tracing::info!("No database in place, create it");
-
-        // Validate content directories before attempting to load
-        let md_status = content::validate_content_directory(&config.md_path);
-        let img_status = content::validate_content_directory(&config.img_path);
-
-        // Check if at least one directory is valid
-        let has_valid_content = matches!(md_status, content::ContentDirStatus::Valid) || matches!(img_status, content::ContentDirStatus::Valid);
-
-        if !has_valid_content {
-            // Both directories are invalid - exit with error
-            eprintln!("\nERROR: Cannot start application - no content directories found\n");
-            eprintln!("The application requires at least one of the following directories:");
-            eprintln!("  - {}  (for markdown flashcards)", config.md_path);
-            eprintln!("  - {} (for image-only flashcards)\n", config.img_path);
-            eprintln!("Current status:");
-            eprintln!("  {}  -> {:?}", config.md_path, md_status);
-            eprintln!("  {} -> {:?}\n", config.img_path, img_status);
-            eprintln!("Setup instructions:");
-            eprintln!("  1. Create at least one directory:");
-            eprintln!("     mkdir static/md");
-            eprintln!("     OR");
-            eprintln!("     mkdir static/img\n");
-            eprintln!("  2. Add content files:");
-            eprintln!("     - For markdown: place .md files in static/md/");
-            eprintln!("     - For images: place .png or .webp files in static/img/\n");
-            eprintln!("For more information, see the README.md file.");
-            std::process::exit(1);
-        }
+    // Cards loaded by older versions have no stable key, plain text, kind or image metadata,
+    // or were made from content in an older way: reload them
+    if db::schema::content_version(&pool)? < db::schema::CONTENT_VERSION
+        || db::queries::has_cards_without_key(&pool)?
+        || db::queries::has_cards_without_text(&pool)?
+        || db::queries::has_cards_without_kind(&pool)?
+        || db::queries::has_image_cards_without_text(&pool)?
+        || db::queries::has_image_cards_without_thumbnail(&pool)?
+    {
+        tracing::info!("Database predates the current card format, reloading content");
+        db::queries::clear_flashcards(&pool)?;
+    }
 
-        // Load content from valid directories
-        if matches!(md_status, content::ContentDirStatus::Valid) {
-            content::load_markdown(&pool, &config.md_path)?;
-        } else {
-            tracing::warn!("Content directory unavailable: {} (reason: {:?})", config.md_path, md_status);
-            tracing::warn!("Continuing with image-only flashcards");
-        }
+    // Search index built with another tokenizer is recreated, then refilled below
+    db::schema::set_fts_tokenizer(&pool, config.fts_tokenizer)?;
+
+    // Validate content directories before attempting to load
+    let md_status = content::validate_content_directory(&config.md_path);
+    let img_status = content::validate_content_directory(&config.img_path);
+
+    // Check if at least one directory is valid
+    let has_valid_content = matches!(md_status, content::ContentDirStatus::Valid) || matches!(img_status, content::ContentDirStatus::Valid);
+
+    if !has_valid_content && db::queries::is_database_empty(&pool)? {
+        eprintln!("\nERROR: Cannot start application - no content directories found\n");
+        eprintln!("The application requires at least one of the following directories:");
+        eprintln!("  - {}  (for markdown flashcards)", config.md_path);
+        eprintln!("  - {} (for image-only flashcards)\n", config.img_path);
+        eprintln!("Current status:");
+        eprintln!("  {}  -> {:?}", config.md_path, md_status);
+        eprintln!("  {} -> {:?}\n", config.img_path, img_status);
+        eprintln!("Setup instructions:");
+        eprintln!("  1. Create at least one directory:");
+        eprintln!("     mkdir static/md");
+        eprintln!("     OR");
+        eprintln!("     mkdir static/img\n");
+        eprintln!("  2. Add content files:");
+        eprintln!("     - For markdown: place .md files in static/md/");
+        eprintln!("     - For images: place .png or .webp files in static/img/\n");
+        eprintln!("For more information, see the README.md file.");
+        std::process::exit(1);
+    }
 
-        if matches!(img_status, content::ContentDirStatus::Valid) {
-            content::load_images(&pool, &config.img_path)?;
-        } else {
-            tracing::warn!("Content directory unavailable: {} (reason: {:?})", config.img_path, img_status);
-            tracing::warn!("Continuing with markdown-only flashcards");
-        }
+    // Load content added or changed since last run (only changed files are parsed)
+    if matches!(md_status, content::ContentDirStatus::Valid) {
+        content::load_markdown(&pool, &config.md_path)?;
+    } else {
+        tracing::warn!("Content directory unavailable: {} (reason: {:?})", config.md_path, md_status);
+        tracing::warn!("Keeping markdown flashcards already in database, if any");
+    }
 
-        db::queries::populate_fts_table(&pool)?;
+    if matches!(img_status, content::ContentDirStatus::Valid) {
+        content::load_images(&pool, &config.img_path)?;
     } else {
-        let count = db::queries::get_total_count(&pool)?;
-        tracing::info!("Database in place no need to create it - {} cards loaded", count);
+        tracing::warn!("Content directory unavailable: {} (reason: {:?})", config.img_path, img_status);
+        tracing::warn!("Keeping image flashcards already in database, if any");
     }
 
+    db::queries::populate_fts_table(&pool)?;
+    db::schema::migrate_legacy_reviews(&pool)?;
+    db::schema::set_content_version(&pool, db::schema::CONTENT_VERSION)?;
+
+    let count = db::queries::get_total_count(&pool)?;
+    tracing::info!("{} cards loaded", count);
+
     tracing::info!("Content loaded successfully. Starting web server...");
 
     // Setup session store
@@ -130,15 +167,40 @@ async fn main() -> anyhow::Result<()> {
     // Save port before moving config
     let port = config.port;
 
+    // Live reload events for open browser tabs (--live-reload)
+    let live_reload = cli_args.live_reload.then(|| tokio::sync::broadcast::channel(16).0);
+
+    // Watch content while running (--watch); watching stops when _watcher is dropped
+    let _watcher = if cli_args.watch {
+        Some(content::watch::watch_content(
+            pool.clone(),
+            config.md_path.clone(),
+            config.img_path.clone(),
+            live_reload.clone(),
+        )?)
+    } else {
+        None
+    };
+
     // Create app state with config and pool
-    let app_state = routes::AppState { pool, config };
+    let app_state = routes::AppState { pool, config, live_reload };
 
     // Build Axum router
     let app = Router::new()
         .route("/", get(routes::landing))
         .route("/apply_filters", post(routes::apply_filters))
         .route("/practice", get(routes::practice))
+        .route("/card/:card_key", get(routes::card))
+        .route("/grade", post(routes::grade))
+        .route("/check_answer", post(routes::check_answer))
+        .route("/quiz", get(routes::quiz))
+        .route("/quiz_answer", post(routes::quiz_answer))
+        .route("/quiz_results", get(routes::quiz_results))
+        .route("/search", get(routes::search))
+        .route("/browse", get(routes::browse))
+        .route("/stats", get(routes::stats))
         .route("/reset_session", get(routes::reset_session))
+        .route("/events", get(routes::events))
         .nest_service("/static", ServeDir::new("static"))
         .layer(session_layer)
         .with_state(app_state);
@@ -0,0 +1,305 @@
+// Rust guideline compliant 2025-01
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+};
+use serde::Deserialize;
+use tower_sessions::Session;
+
+use crate::db::connection::DbPool;
+use crate::db::models::{CardKind, CardOrder, FilterCriteria, Flashcard};
+use crate::db::queries;
+use crate::routes::AppState;
+use crate::routes::practice::card_url;
+use crate::session::SessionData;
+
+/// Number of cards per page.
+const PAGE_SIZE: i64 = 50;
+
+/// Available sort orders: (order, query string value, label).
+const SORTS: [(CardOrder, &str, &str); 3] = [
+    (CardOrder::Source, "source", "File order"),
+    (CardOrder::Question, "question", "Question"),
+    (CardOrder::Difficulty, "difficulty", "Difficulty"),
+];
+
+/// Query string of the browse page.
+#[derive(Debug, Deserialize)]
+pub struct BrowseParams {
+    /// Order of the cards within each group.
+    #[serde(default)]
+    pub sort: CardOrder,
+    /// Page number, starting at 1.
+    pub page: Option<i64>,
+}
+
+/// Cards of one category/subcategory, in listing order.
+///
+/// `category` is None for uncategorized (e.g. image-only) cards.
+#[derive(Debug, PartialEq)]
+struct Group {
+    category: Option<String>,
+    subcategory: Option<String>,
+    count: i64,
+}
+
+impl Group {
+    fn title(&self) -> String {
+        match (&self.category, &self.subcategory) {
+            (Some(cat), Some(subcat)) => format!("{} - {}", cat, subcat),
+            (Some(cat), None) => cat.clone(),
+            (None, _) => "Uncategorized".to_string(),
+        }
+    }
+}
+
+/// Link to a group from the table of contents.
+struct TocEntry {
+    title: String,
+    count: i64,
+    url: String,
+}
+
+/// One card of the listing.
+struct CardItem {
+    url: String,
+    difficulty: Option<String>,
+    /// Image without question: the answer is shown at once.
+    answer_only: bool,
+    q_html: String,
+    a_html: String,
+}
+
+/// Part of a group shown on the current page.
+struct Section {
+    anchor: String,
+    title: String,
+    count: i64,
+    /// Whether the group started on a previous page.
+    continued: bool,
+    cards: Vec<CardItem>,
+}
+
+/// Sort order link.
+struct SortLink {
+    label: &'static str,
+    url: String,
+    active: bool,
+}
+
+/// Template for the deck listing.
+#[derive(Template)]
+#[template(path = "browse.html")]
+struct BrowseTemplate {
+    deck_name: String,
+    total: i64,
+    page: i64,
+    page_count: i64,
+    sort_links: Vec<SortLink>,
+    toc: Vec<TocEntry>,
+    sections: Vec<Section>,
+    prev_url: Option<String>,
+    next_url: Option<String>,
+    live_reload: bool,
+}
+
+/// Builds the URL of a listing page.
+fn page_url(sort: CardOrder, page: i64) -> String {
+    let sort = SORTS.iter().find(|(order, _, _)| *order == sort).map_or("source", |(_, name, _)| name);
+    format!("/browse?sort={}&page={}", sort, page)
+}
+
+/// Lists the non-empty groups of cards matching filters, in listing order.
+///
+/// Categorized cards come first, each category followed by its cards without
+/// subcategory, then the uncategorized cards as a single group (see
+/// [`queries::get_filtered_flashcards`]).
+///
+/// # Errors
+/// Returns error if database query fails.
+fn filtered_groups(pool: &DbPool, filters: &FilterCriteria) -> anyhow::Result<Vec<Group>> {
+    let mut groups: Vec<Group> = Vec::new();
+
+    for (category, subcategory, count) in queries::count_filtered_groups(pool, filters)? {
+        match groups.last_mut() {
+            // Uncategorized cards are listed together, whatever their subcategory
+            Some(last) if last.category.is_none() && category.is_none() => last.count += count,
+            _ => {
+                let subcategory = category.as_ref().and(subcategory);
+                groups.push(Group { category, subcategory, count });
+            }
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Splits the cards of a page into sections of their groups.
+///
+/// `offset` is the position of the first card of the page in the whole listing.
+fn page_sections(groups: &[Group], cards: Vec<Flashcard>, offset: i64) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut cards = cards.into_iter();
+    let mut start = 0;
+    let mut position = offset;
+
+    for (index, group) in groups.iter().enumerate() {
+        let end = start + group.count;
+        if position < end {
+            let items: Vec<CardItem> = cards
+                .by_ref()
+                .take((end - position) as usize)
+                .map(|card| CardItem {
+                    url: card_url(&card.card_key),
+                    difficulty: card.difficulty,
+                    answer_only: card.kind == CardKind::Image && !card.has_question,
+                    q_html: card.question_html,
+                    a_html: card.answer_html,
+                })
+                .collect();
+            if items.is_empty() {
+                break;
+            }
+            position += items.len() as i64;
+            sections.push(Section {
+                anchor: format!("group-{}", index + 1),
+                title: group.title(),
+                count: group.count,
+                continued: start < offset,
+                cards: items,
+            });
+        }
+        start = end;
+    }
+
+    sections
+}
+
+/// Lists the cards matching the current filters, like a review sheet.
+///
+/// Cards are grouped by category and subcategory with their counts, answers
+/// collapsed, and sorted within each group by `sort`. Filters are those saved
+/// from the landing form (see [`crate::routes::landing::apply_filters`]).
+///
+/// # Errors
+/// Returns error if database query or session operation fails.
+pub async fn browse(
+    State(state): State<AppState>,
+    session: Session,
+    Query(params): Query<BrowseParams>,
+) -> Result<impl IntoResponse, String> {
+    let pool = &state.pool;
+    let session_data: SessionData = session
+        .get("data")
+        .await
+        .map_err(|e| format!("Session get error: {}", e))?
+        .unwrap_or_default();
+    let filters = session_data.filter_criteria();
+
+    let groups = filtered_groups(pool, &filters).map_err(|e| format!("Failed to count cards: {}", e))?;
+    let total: i64 = groups.iter().map(|group| group.count).sum();
+    let page_count = (total + PAGE_SIZE - 1) / PAGE_SIZE;
+
+    // Out of range pages show the last one
+    let page = params.page.unwrap_or(1).min(page_count).max(1);
+    let offset = (page - 1) * PAGE_SIZE;
+
+    let cards = queries::get_filtered_flashcards(pool, &filters, params.sort, PAGE_SIZE, offset)
+        .map_err(|e| format!("Failed to list cards: {}", e))?;
+
+    let mut start = 0;
+    let toc = groups
+        .iter()
+        .enumerate()
+        .map(|(index, group)| {
+            let entry = TocEntry {
+                title: group.title(),
+                count: group.count,
+                url: format!("{}#group-{}", page_url(params.sort, start / PAGE_SIZE + 1), index + 1),
+            };
+            start += group.count;
+            entry
+        })
+        .collect();
+
+    let sort_links = SORTS
+        .iter()
+        .map(|&(order, _, label)| SortLink { label, url: page_url(order, 1), active: order == params.sort })
+        .collect();
+
+    let template = BrowseTemplate {
+        deck_name: state.config.deck_display_name.clone(),
+        total,
+        page,
+        page_count,
+        sort_links,
+        toc,
+        sections: page_sections(&groups, cards, offset),
+        prev_url: (page > 1).then(|| page_url(params.sort, page - 1)),
+        next_url: (page < page_count).then(|| page_url(params.sort, page + 1)),
+        live_reload: state.live_reload.is_some(),
+    };
+
+    Ok(Html(template.render().map_err(|e| format!("Template error: {}", e))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn group(category: Option<&str>, subcategory: Option<&str>, count: i64) -> Group {
+        Group { category: category.map(String::from), subcategory: subcategory.map(String::from), count }
+    }
+
+    fn card(card_key: &str) -> Flashcard {
+        Flashcard {
+            id: 0,
+            card_key: card_key.to_string(),
+            category: None,
+            subcategory: None,
+            difficulty: None,
+            kind: CardKind::Markdown,
+            question_html: "<p>Q</p>".to_string(),
+            answer_html: "<p>A</p>".to_string(),
+            has_question: true,
+            thumbnail_html: None,
+        }
+    }
+
+    /// Renders sections as `title[+]:count` (+ when continued) for readable assertions.
+    fn render(sections: &[Section]) -> Vec<String> {
+        sections
+            .iter()
+            .map(|s| format!("{}{}:{}", s.title, if s.continued { "+" } else { "" }, s.cards.len()))
+            .collect()
+    }
+
+    #[rstest]
+    #[case(Some("Math"), Some("Algebra"), "Math - Algebra")]
+    #[case(Some("Math"), None, "Math")]
+    #[case(None, None, "Uncategorized")]
+    fn test_group_title(#[case] category: Option<&str>, #[case] subcategory: Option<&str>, #[case] expected: &str) {
+        assert_eq!(group(category, subcategory, 1).title(), expected);
+    }
+
+    #[rstest]
+    #[case(0, 4, &["A:2", "B:2"])]
+    #[case(2, 3, &["B:3"])]
+    #[case(3, 3, &["B+:2", "C:1"])]
+    #[case(6, 3, &["C+:1"])]
+    fn test_page_sections(#[case] offset: i64, #[case] nb_cards: usize, #[case] expected: &[&str]) {
+        let groups = [group(Some("A"), None, 2), group(Some("B"), None, 3), group(Some("C"), None, 2)];
+        let cards = (0..nb_cards).map(|i| card(&format!("card-{}", i))).collect();
+
+        assert_eq!(render(&page_sections(&groups, cards, offset)), expected);
+    }
+
+    #[rstest]
+    #[case(CardOrder::Source, 1, "/browse?sort=source&page=1")]
+    #[case(CardOrder::Difficulty, 3, "/browse?sort=difficulty&page=3")]
+    fn test_page_url(#[case] sort: CardOrder, #[case] page: i64, #[case] expected: &str) {
+        assert_eq!(page_url(sort, page), expected);
+    }
+}
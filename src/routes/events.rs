@@ -0,0 +1,29 @@
+// Rust guideline compliant 2025-01
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use std::convert::Infallible;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+use crate::routes::AppState;
+
+/// Streams live reload events to open browser tabs (Server-Sent Events).
+///
+/// Sends a `reload` event each time watched content changed. Only available
+/// with `--watch --live-reload`: answers 404 otherwise, so pages stop listening.
+///
+/// # Errors
+/// Returns 404 if live reload is disabled.
+pub async fn events(
+    State(state): State<AppState>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let reload_tx = state.live_reload.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+
+    // Lagged receivers only missed duplicate reloads: skip the error
+    let stream = BroadcastStream::new(reload_tx.subscribe())
+        .filter_map(|message| message.ok().map(|()| Ok(Event::default().event("reload").data("content changed"))));
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -5,16 +5,22 @@ use axum::{
     response::{Html, IntoResponse, Redirect},
     // Form,
 };
+use percent_encoding::{NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
 // use serde::Deserialize;
 use tower_sessions::Session;
 
-use crate::db::{models::FilterCriteria, queries};
+use crate::db::models::{Subcategory, TagMatch};
+use crate::db::{queries, search};
 use crate::routes::AppState;
-use crate::session::SessionData;
+use crate::session::{QuizSession, SessionData};
 
 /// Checks if any filters are active (non-default).
 fn has_active_filters(session: &SessionData) -> bool {
-    !session.filter_keywords.is_empty() || session.filter_categories.is_some() || session.filter_subcategories.is_some() || !session.filter_include_images
+    !session.filter_keywords.is_empty()
+        || session.filter_categories.is_some()
+        || session.filter_subcategories.is_some()
+        || !session.filter_tags.is_empty()
+        || !session.filter_include_images
 }
 
 /// Category with selection state.
@@ -29,6 +35,36 @@ struct CategoryItem {
 struct SubcategoryItem {
     name: String,
     category: String,
+    /// Checkbox value (see [`subcategory_value`]).
+    value: String,
+    selected: bool,
+}
+
+/// Encodes a subcategory as a checkbox value: both names percent-encoded, joined by `/`.
+///
+/// Subcategory names are reused across categories, the value keeps the pair.
+fn subcategory_value(subcategory: &Subcategory) -> String {
+    format!(
+        "{}/{}",
+        utf8_percent_encode(&subcategory.category, NON_ALPHANUMERIC),
+        utf8_percent_encode(&subcategory.name, NON_ALPHANUMERIC)
+    )
+}
+
+/// Decodes a subcategory checkbox value (see [`subcategory_value`]). Returns None if malformed.
+fn parse_subcategory_value(value: &str) -> Option<Subcategory> {
+    let (category, name) = value.split_once('/')?;
+    Some(Subcategory {
+        category: percent_decode_str(category).decode_utf8_lossy().into_owned(),
+        name: percent_decode_str(name).decode_utf8_lossy().into_owned(),
+    })
+}
+
+/// Tag facet with its number of cards and selection state.
+#[derive(Clone)]
+struct TagItem {
+    name: String,
+    count: i64,
     selected: bool,
 }
 
@@ -44,8 +80,12 @@ struct LandingTemplate {
     filter_keywords: String,
     all_categories_checked: bool,
     all_subcategories_checked: bool,
+    tags: Vec<TagItem>,
+    match_all_tags: bool,
     filter_include_images: bool,
+    typed_answers: bool,
     error_message: Option<String>,
+    live_reload: bool,
 }
 
 /// Form data from filter submission.
@@ -58,13 +98,19 @@ pub struct FilterForm {
     pub all_categories: Option<String>,
     pub categories: Vec<String>,
     pub all_subcategories: Option<String>,
-    pub subcategories: Vec<String>,
+    pub subcategories: Vec<Subcategory>,
+    pub tags: Vec<String>,
+    pub tag_match: Option<String>,
     pub all_images: Option<String>,
+    /// Typed-answer mode checkbox.
+    pub typed_answers: Option<String>,
+    /// Submit button used: `browse` opens the deck listing, `quiz` the multiple-choice quiz, instead of practice.
+    pub action: Option<String>,
 }
 
 /// Displays landing page with filter form.
 ///
-/// Shows category/subcategory selection, keyword input, and image inclusion toggle.
+/// Shows category/subcategory selection, tag facets, keyword input, and image inclusion toggle.
 /// Displays current filter state from session. Shows error message if present.
 ///
 /// # Errors
@@ -79,7 +125,7 @@ pub async fn landing(State(state): State<AppState>, session: Session) -> Result<
     session.insert("data", &session_data).await.map_err(|e| format!("Session insert error: {}", e))?;
 
     // Query available categories
-    let all_categories = queries::get_distinct_categories(&pool).map_err(|e| format!("Failed to get categories: {}", e))?;
+    let all_categories = queries::get_distinct_categories(pool).map_err(|e| format!("Failed to get categories: {}", e))?;
 
     // Build category items with selection state
     let all_categories_checked = session_data.filter_categories.is_none();
@@ -93,30 +139,40 @@ pub async fn landing(State(state): State<AppState>, session: Session) -> Result<
 
     // ALWAYS render ALL subcategories regardless of category filter
     // JavaScript will handle client-side filtering for visibility
-    let all_subcategories_list = queries::get_distinct_subcategories(&pool, None).map_err(|e| format!("Failed to get subcategories: {}", e))?;
+    let all_subcategories_list = queries::get_distinct_subcategories(pool, None).map_err(|e| format!("Failed to get subcategories: {}", e))?;
 
     // Build subcategory items with selection state and parent category
     let all_subcategories_checked = session_data.filter_subcategories.is_none();
     let subcategories: Vec<SubcategoryItem> = all_subcategories_list
         .into_iter()
-        .map(|(name, category)| SubcategoryItem {
-            selected: session_data.filter_subcategories.as_ref().map(|subcats| subcats.contains(&name)).unwrap_or(false),
+        .map(|(name, category)| {
+            let subcategory = Subcategory { category, name };
+            SubcategoryItem {
+                value: subcategory_value(&subcategory),
+                selected: session_data.filter_subcategories.as_ref().map(|subcats| subcats.contains(&subcategory)).unwrap_or(false),
+                name: subcategory.name,
+                category: subcategory.category,
+            }
+        })
+        .collect();
+
+    // Tag facets (not rendered when no card is tagged)
+    let tags: Vec<TagItem> = queries::get_tag_counts(pool)
+        .map_err(|e| format!("Failed to get tags: {}", e))?
+        .into_iter()
+        .map(|(name, count)| TagItem {
+            selected: session_data.filter_tags.contains(&name),
             name,
-            category,
+            count,
         })
         .collect();
 
-    let total_count = queries::get_total_count(&pool).map_err(|e| format!("Failed to get total count: {}", e))?;
+    let total_count = queries::get_total_count(pool).map_err(|e| format!("Failed to get total count: {}", e))?;
 
     // Count filtered cards if filters active
     let filtered_count = if has_active_filters(&session_data) {
-        let criteria = FilterCriteria {
-            keywords: session_data.filter_keywords.clone(),
-            categories: session_data.filter_categories.clone(),
-            subcategories: session_data.filter_subcategories.clone(),
-            include_images: session_data.filter_include_images,
-        };
-        let count = queries::count_filtered_flashcards(&pool, &criteria).map_err(|e| format!("Failed to count filtered cards: {}", e))?;
+        let criteria = session_data.filter_criteria();
+        let count = queries::count_filtered_flashcards(pool, &criteria).map_err(|e| format!("Failed to count filtered cards: {}", e))?;
         Some(count)
     } else {
         None
@@ -131,8 +187,12 @@ pub async fn landing(State(state): State<AppState>, session: Session) -> Result<
         filter_keywords: session_data.filter_keywords.join(" "),
         all_categories_checked,
         all_subcategories_checked,
+        tags,
+        match_all_tags: session_data.filter_tag_match == TagMatch::All,
         filter_include_images: session_data.filter_include_images,
+        typed_answers: session_data.typed_answers,
         error_message,
+        live_reload: state.live_reload.is_some(),
     };
 
     let html = template.render().map_err(|e| format!("Template render error: {}", e))?;
@@ -142,8 +202,9 @@ pub async fn landing(State(state): State<AppState>, session: Session) -> Result<
 
 /// Processes filter form submission and redirects to practice.
 ///
-/// Parses form data, saves filter state to session, and redirects to /practice.
-/// Resets seen cards list for new practice session.
+/// Parses form data, saves filter state to session, and redirects to /practice
+/// (or to /browse or /quiz when submitted with the Browse or Quiz button).
+/// Resets seen cards list and quiz score for new practice session.
 ///
 /// # Errors
 /// Returns error if session operation fails.
@@ -156,11 +217,15 @@ pub async fn apply_filters(session: Session, body: String) -> Result<impl IntoRe
         categories: Vec::new(),
         all_subcategories: None,
         subcategories: Vec::new(),
+        tags: Vec::new(),
+        tag_match: None,
         all_images: None,
+        typed_answers: None,
+        action: None,
     };
 
-    // Simple URL decoder: replaces '+' with space (sufficient for our form data)
-    let url_decode = |s: &str| s.replace('+', " ");
+    // URL decoder: '+' is a space, then %XX escapes (quotes, ':' in search queries...)
+    let url_decode = |s: &str| percent_decode_str(&s.replace('+', " ")).decode_utf8_lossy().into_owned();
 
     // Parse form body: split on '&' for pairs, then '=' for key/value
     // Multiple values with same key are collected into Vec
@@ -174,15 +239,24 @@ pub async fn apply_filters(session: Session, body: String) -> Result<impl IntoRe
                 "all_categories" => form.all_categories = Some(value),
                 "categories" => form.categories.push(value), // Collect multiple values
                 "all_subcategories" => form.all_subcategories = Some(value),
-                "subcategories" => form.subcategories.push(value), // Collect multiple values
+                "subcategories" => form.subcategories.extend(parse_subcategory_value(&value)), // Collect multiple values
+                "tags" => form.tags.push(value),                   // Collect multiple values
+                "tag_match" => form.tag_match = Some(value),
                 "all_images" => form.all_images = Some(value),
+                "typed_answers" => form.typed_answers = Some(value),
+                "action" => form.action = Some(value),
                 _ => {} // Ignore unknown fields
             }
         }
     }
     let mut session_data: SessionData = session.get("data").await.map_err(|e| format!("Session get error: {}", e))?.unwrap_or_default();
 
-    // Parse keywords
+    // Parse keywords, rejecting malformed search queries
+    if let Err(e) = search::SearchQuery::parse(&form.keywords) {
+        session_data.error_message = Some(format!("Invalid search: {}", e));
+        session.insert("data", &session_data).await.map_err(|e| format!("Session insert error: {}", e))?;
+        return Ok(Redirect::to("/"));
+    }
     session_data.filter_keywords = form.keywords.split_whitespace().map(String::from).collect();
 
     // Parse categories: None means "all categories", Some(vec![]) means "no categories"
@@ -213,19 +287,36 @@ pub async fn apply_filters(session: Session, body: String) -> Result<impl IntoRe
         Some(form.subcategories) // Specific subcategories selected
     };
 
+    // Parse tags: none selected means no tag filter
+    session_data.filter_tags = form.tags;
+    session_data.filter_tag_match = if form.tag_match.as_deref() == Some("all") {
+        TagMatch::All
+    } else {
+        TagMatch::Any
+    };
+
     // Parse images
     session_data.filter_include_images = form.all_images.is_some();
 
-    // Reset seen cards for new practice session
-    session_data.seen_ids.clear();
+    // Parse practice mode
+    session_data.typed_answers = form.typed_answers.is_some();
+
+    // Reset seen cards and quiz score for new practice session
+    session_data.seen_keys.clear();
+    session_data.quiz = QuizSession::default();
     session_data.filtered_card_count = None;
 
     session.insert("data", &session_data).await.map_err(|e| format!("Session insert error: {}", e))?;
 
-    Ok(Redirect::to("/practice"))
+    match form.action.as_deref() {
+        Some("browse") => Ok(Redirect::to("/browse")),
+        Some("quiz") => Ok(Redirect::to("/quiz")),
+        _ => Ok(Redirect::to("/practice")),
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)] // Tests tweak one field of the defaults at a time
 mod tests {
     use super::*;
     use rstest::rstest;
@@ -255,7 +346,14 @@ mod tests {
     #[test]
     fn test_has_active_filters_with_subcategories() {
         let mut session = SessionData::default();
-        session.filter_subcategories = Some(vec!["Rust".to_string()]);
+        session.filter_subcategories = Some(vec![Subcategory::new("Programming", "Rust")]);
+        assert!(has_active_filters(&session));
+    }
+
+    #[test]
+    fn test_has_active_filters_with_tags() {
+        let mut session = SessionData::default();
+        session.filter_tags = vec!["pandas".to_string()];
         assert!(has_active_filters(&session));
     }
 
@@ -294,10 +392,14 @@ mod tests {
             categories: Vec::new(),
             all_subcategories: None,
             subcategories: Vec::new(),
+            tags: Vec::new(),
+            tag_match: None,
             all_images: None,
+            typed_answers: None,
+            action: None,
         };
 
-        let url_decode = |s: &str| s.replace('+', " ");
+        let url_decode = |s: &str| percent_decode_str(&s.replace('+', " ")).decode_utf8_lossy().into_owned();
 
         for pair in body.split('&') {
             if let Some((key, value)) = pair.split_once('=') {
@@ -309,8 +411,12 @@ mod tests {
                     "all_categories" => form.all_categories = Some(value),
                     "categories" => form.categories.push(value),
                     "all_subcategories" => form.all_subcategories = Some(value),
-                    "subcategories" => form.subcategories.push(value),
+                    "subcategories" => form.subcategories.extend(parse_subcategory_value(&value)),
+                    "tags" => form.tags.push(value),
+                    "tag_match" => form.tag_match = Some(value),
                     "all_images" => form.all_images = Some(value),
+                    "typed_answers" => form.typed_answers = Some(value),
+                    "action" => form.action = Some(value),
                     _ => {}
                 }
             }
@@ -341,13 +447,64 @@ mod tests {
 
     #[test]
     fn test_parse_form_specific_subcategories() {
-        let body = "all_categories=on&subcategories=Algebra&subcategories=Geometry&all_images=on";
+        let body = "all_categories=on&subcategories=Math%2FAlgebra&subcategories=Math%2FGeometry&all_images=on";
         let form = parse_form_body(body);
 
-        assert_eq!(form.subcategories, vec!["Algebra", "Geometry"]);
+        assert_eq!(
+            form.subcategories,
+            vec![Subcategory::new("Math", "Algebra"), Subcategory::new("Math", "Geometry")]
+        );
         assert_eq!(form.all_subcategories, None); // Not checked
     }
 
+    #[test]
+    fn test_parse_form_colliding_subcategories() {
+        let body = "categories=Rust&categories=Python&subcategories=Rust%2FBasics&subcategories=Python%2FBasics";
+        let form = parse_form_body(body);
+
+        assert_eq!(
+            form.subcategories,
+            vec![Subcategory::new("Rust", "Basics"), Subcategory::new("Python", "Basics")]
+        );
+    }
+
+    #[rstest]
+    #[case("Math", "Algebra", "Math/Algebra")]
+    #[case("Big Data", "Spark's stack", "Big%20Data/Spark%27s%20stack")]
+    #[case("CI/CD", "a/b", "CI%2FCD/a%2Fb")]
+    fn test_subcategory_value_roundtrip(#[case] category: &str, #[case] name: &str, #[case] expected: &str) {
+        let subcategory = Subcategory::new(category, name);
+
+        let value = subcategory_value(&subcategory);
+
+        assert_eq!(value, expected);
+        assert_eq!(parse_subcategory_value(&value), Some(subcategory));
+    }
+
+    #[test]
+    fn test_parse_subcategory_value_malformed() {
+        assert_eq!(parse_subcategory_value("Algebra"), None);
+    }
+
+    #[test]
+    fn test_parse_form_tags() {
+        let body = "all_categories=on&all_subcategories=on&tags=pandas&tags=performance&tag_match=all&all_images=on";
+        let form = parse_form_body(body);
+
+        assert_eq!(form.tags, vec!["pandas", "performance"]);
+        assert_eq!(form.tag_match, Some("all".to_string()));
+    }
+
+    #[rstest]
+    #[case("all_categories=on&all_subcategories=on", None)]
+    #[case("all_categories=on&all_subcategories=on&action=browse", Some("browse"))]
+    #[case("all_categories=on&all_subcategories=on&action=quiz", Some("quiz"))]
+    fn test_parse_form_action(#[case] body: &str, #[case] expected: Option<&str>) {
+        let form = parse_form_body(body);
+
+        assert_eq!(form.action.as_deref(), expected);
+    }
+
     #[test]
     fn test_parse_form_images_excluded() {
         let body = "keywords=&all_categories=on&all_subcategories=on";
@@ -356,6 +513,15 @@ mod tests {
         assert_eq!(form.all_images, None); // Checkbox not checked
     }
 
+    #[rstest]
+    #[case("all_categories=on&all_subcategories=on", false)]
+    #[case("all_categories=on&all_subcategories=on&typed_answers=on", true)]
+    fn test_parse_form_typed_answers(#[case] body: &str, #[case] expected: bool) {
+        let form = parse_form_body(body);
+
+        assert_eq!(form.typed_answers.is_some(), expected);
+    }
+
     #[test]
     fn test_parse_form_empty_categories_images_only() {
         let body = "all_subcategories=on&all_images=on";
@@ -446,6 +612,7 @@ mod tests {
         let item = SubcategoryItem {
             name: "Algebra".to_string(),
             category: "Math".to_string(),
+            value: "Math/Algebra".to_string(),
             selected: false,
         };
 
@@ -1,10 +1,22 @@
+pub mod browse;
 pub mod debug;
+pub mod events;
 pub mod landing;
 pub mod practice;
+pub mod quiz;
+pub mod search;
+pub mod stats;
 
+pub use browse::browse;
 pub use debug::reset_session;
+pub use events::events;
 pub use landing::{apply_filters, landing};
-pub use practice::practice;
+pub use practice::{card, check_answer, grade, practice};
+pub use quiz::{quiz, quiz_answer, quiz_results};
+pub use search::search;
+pub use stats::stats;
+
+use tokio::sync::broadcast;
 
 use crate::config::Config;
 use crate::db::connection::DbPool;
@@ -19,4 +31,6 @@ pub struct AppState {
     pub pool: DbPool,
     /// Application configuration loaded from environment.
     pub config: Config,
+    /// Content change notifications for live reload (None unless `--live-reload`).
+    pub live_reload: Option<broadcast::Sender<()>>,
 }
@@ -1,41 +1,103 @@
 // Rust guideline compliant 2024-01
 use askama::Template;
 use axum::{
-    extract::State,
+    Form,
+    extract::{Path, State},
+    http::StatusCode,
     response::{Html, IntoResponse, Redirect},
 };
+use serde::Deserialize;
 use tower_sessions::Session;
 
-use crate::db::{models::FilterCriteria, queries};
+use crate::content::typed_answer::{self, DiffSegment, ExpectedAnswer};
+use crate::db::{
+    connection::DbPool,
+    models::{CardKind, FilterCriteria, Flashcard, ReviewLogEntry},
+    queries,
+};
 use crate::routes::AppState;
+use crate::scheduler::{self, Grade};
 use crate::session::SessionData;
 
-/// Determines if flashcard is PNG-only (no question content).
-///
-/// PNG-only cards have minimal question HTML from image loading.
-/// These cards display answer immediately without hide/reveal logic.
-fn is_png_only_card(question_html: &str) -> bool {
-    question_html.trim() == "<h3>Question:</h3>"
-}
-
 /// Template for practice session flashcard display.
 #[derive(Template)]
 #[template(path = "practice.html")]
 struct PracticeTemplate {
     deck_name: String,
+    card_key: String,
     category: Option<String>,
     subcategory: Option<String>,
+    difficulty: Option<String>,
+    tags: Vec<String>,
     q_html: String,
     a_html: String,
+    /// Hints (HTML), revealed one at a time before the answer.
+    hints: Vec<String>,
     nb_cards: i64,
-    is_png_only: bool,
+    due_count: i64,
+    /// Image cards without question: their answer is displayed at once, without hide/reveal logic.
+    answer_only: bool,
+    /// Path of the card permalink, for the "Copy link" button.
+    permalink: String,
+    /// Shown alone from its permalink, not drawn from the practice session.
+    is_single_card: bool,
+    /// Typed-answer mode: the answer is typed and checked before the reveal.
+    typed_answer: bool,
+    live_reload: bool,
+}
+
+/// Part of the diff shown after a typed answer, with its CSS class suffix.
+struct DiffItem {
+    class: &'static str,
+    text: String,
+}
+
+/// Template for the feedback on a typed answer, inserted in the practice page.
+#[derive(Template)]
+#[template(path = "answer_check.html")]
+struct AnswerCheckTemplate {
+    correct: bool,
+    /// Answer as typed.
+    response: String,
+    /// Diff from the closest accepted answer to the response (empty for patterns).
+    diff: Vec<DiffItem>,
+    suggested_grade: &'static str,
+    suggested_label: &'static str,
+}
+
+/// Returns the permalink path of a card.
+pub fn card_url(card_key: &str) -> String {
+    format!("/card/{}", card_key)
+}
+
+/// Form data from grading buttons.
+///
+/// Timings and the number of hints shown are measured client-side, and omitted
+/// when JavaScript could not measure them.
+#[derive(Debug, Deserialize)]
+pub struct GradeForm {
+    pub card_key: String,
+    pub grade: Grade,
+    pub time_to_reveal_ms: Option<i64>,
+    pub time_on_card_ms: Option<i64>,
+    pub hints_used: Option<i64>,
+}
+
+/// Form data from the typed answer of a card.
+#[derive(Debug, Deserialize)]
+pub struct CheckAnswerForm {
+    pub card_key: String,
+    #[serde(default)]
+    pub answer: String,
 }
 
 /// Displays filtered flashcard for practice session.
 ///
-/// Retrieves random flashcard matching current filter criteria from session.
-/// Tracks seen cards to avoid repetition. Resets seen list when all filtered
-/// cards viewed.
+/// Picks the next due flashcard matching current filter criteria from session
+/// (overdue cards first, then new cards). When nothing is due, falls back to a
+/// random matching card. Tracks seen cards to avoid repetition. Resets seen list
+/// when all filtered cards viewed. Siblings (reversed pairs, clozes of a block)
+/// are never shown in a row (see [`next_card`]).
 ///
 /// # Errors
 /// Returns error if database query fails. Redirects to landing page if no cards match filters.
@@ -51,18 +113,13 @@ pub async fn practice(
         .unwrap_or_default();
 
     // Build filter criteria from session
-    let criteria = FilterCriteria {
-        keywords: session_data.filter_keywords.clone(),
-        categories: session_data.filter_categories.clone(),
-        subcategories: session_data.filter_subcategories.clone(),
-        include_images: session_data.filter_include_images,
-    };
+    let criteria = session_data.filter_criteria();
 
     // Get or calculate filtered card count
     let nb_cards = if let Some(cached) = session_data.filtered_card_count {
         cached
     } else {
-        let count = queries::count_filtered_flashcards(&pool, &criteria)
+        let count = queries::count_filtered_flashcards(pool, &criteria)
             .map_err(|e| format!("Failed to count cards: {}", e))?;
         session_data.filtered_card_count = Some(count);
         count
@@ -79,18 +136,34 @@ pub async fn practice(
         return Ok(Redirect::to("/").into_response());
     }
 
-    // Reset seen_ids if all filtered cards seen
-    if session_data.seen_ids.len() >= nb_cards as usize {
-        session_data.seen_ids.clear();
+    // Last card shown: neither it nor its siblings come next, even after a reset
+    let previous = session_data.seen_keys.last().cloned();
+
+    // Reset seen_keys if all filtered cards seen
+    if session_data.seen_keys.len() >= nb_cards as usize {
+        session_data.seen_keys.clear();
     }
 
-    // Get random filtered flashcard
-    let card = queries::get_filtered_random_flashcard(&pool, &session_data.seen_ids, &criteria)
+    let now = scheduler::unix_now();
+    let due_count = queries::count_due_flashcards(pool, &criteria, now)
+        .map_err(|e| format!("Failed to count due cards: {}", e))?;
+
+    // Get next due flashcard, or a random one when nothing is due
+    let card = next_card(pool, &session_data.seen_keys, previous.as_deref(), &criteria, now)
         .map_err(|e| format!("Failed to get flashcard: {}", e))?
         .ok_or_else(|| "No cards available".to_string())?;
 
+    let tags = queries::get_card_tags(pool, &card.card_key)
+        .map_err(|e| format!("Failed to get card tags: {}", e))?;
+    let hints = queries::get_card_hints(pool, &card.card_key)
+        .map_err(|e| format!("Failed to get card hints: {}", e))?;
+    let typed_answer = session_data.typed_answers
+        && queries::get_expected_answer(pool, &card.card_key)
+            .map_err(|e| format!("Failed to get expected answer: {}", e))?
+            .is_some();
+
     // Add card to seen list
-    session_data.seen_ids.push(card.id);
+    session_data.seen_keys.push(card.card_key.clone());
 
     // Save session
     session
@@ -99,13 +172,11 @@ pub async fn practice(
         .map_err(|e| format!("Session insert error: {}", e))?;
 
     let template = PracticeTemplate {
-        deck_name: state.config.deck_display_name.clone(),
-        category: card.category.clone(),
-        subcategory: card.subcategory.clone(),
-        q_html: card.question_html.clone(),
-        a_html: card.answer_html,
         nb_cards,
-        is_png_only: is_png_only_card(&card.question_html),
+        due_count,
+        is_single_card: false,
+        typed_answer,
+        ..PracticeTemplate::new(&state, card, tags, hints)
     };
 
     let html = template
@@ -114,3 +185,186 @@ pub async fn practice(
 
     Ok(Html(html).into_response())
 }
+
+/// Picks the next card to practice.
+///
+/// Due cards come first (overdue, then new), else a random card, among the
+/// cards not seen yet. The previous card and its siblings (other side of a
+/// reversed card, other clozes of its block) are avoided, by showing a seen card
+/// again if needed: they only come next when nothing else matches the filters.
+///
+/// # Errors
+/// Returns error if database query fails.
+fn next_card(
+    pool: &DbPool,
+    seen_keys: &[String],
+    previous: Option<&str>,
+    criteria: &FilterCriteria,
+    now: i64,
+) -> anyhow::Result<Option<Flashcard>> {
+    let mut avoided = Vec::new();
+    if let Some(previous) = previous {
+        avoided.push(previous.to_string());
+        avoided.extend(queries::get_sibling_keys(pool, previous)?);
+    }
+    let excluded: Vec<String> = seen_keys.iter().chain(&avoided).cloned().collect();
+
+    if let Some(card) = queries::get_next_due_flashcard(pool, &excluded, criteria, now)? {
+        return Ok(Some(card));
+    }
+    for exclude in [&excluded, &avoided, &Vec::new()] {
+        if let Some(card) = queries::get_filtered_random_flashcard(pool, exclude, criteria)? {
+            return Ok(Some(card));
+        }
+    }
+    Ok(None)
+}
+
+impl PracticeTemplate {
+    /// Displays `card` alone (practice fills in the session counts).
+    fn new(state: &AppState, card: Flashcard, tags: Vec<String>, hints: Vec<String>) -> Self {
+        PracticeTemplate {
+            deck_name: state.config.deck_display_name.clone(),
+            answer_only: card.kind == CardKind::Image && !card.has_question,
+            permalink: card_url(&card.card_key),
+            card_key: card.card_key,
+            category: card.category,
+            subcategory: card.subcategory,
+            difficulty: card.difficulty,
+            tags,
+            q_html: card.question_html,
+            a_html: card.answer_html,
+            hints,
+            nb_cards: 0,
+            due_count: 0,
+            is_single_card: true,
+            typed_answer: false,
+            live_reload: state.live_reload.is_some(),
+        }
+    }
+}
+
+/// Displays one card from its permalink (`/card/{card_key}`).
+///
+/// Same hide/reveal and grading as practice, without touching the practice
+/// session (filters and seen cards).
+///
+/// # Errors
+/// Returns error if database query fails. Responds 404 if no card has this key.
+pub async fn card(State(state): State<AppState>, Path(card_key): Path<String>) -> Result<impl IntoResponse, String> {
+    let pool = &state.pool;
+
+    let Some(card) = queries::get_flashcard_by_key(pool, &card_key)
+        .map_err(|e| format!("Failed to get flashcard: {}", e))?
+    else {
+        return Ok((StatusCode::NOT_FOUND, format!("No card with key {}", card_key)).into_response());
+    };
+
+    let tags = queries::get_card_tags(pool, &card.card_key)
+        .map_err(|e| format!("Failed to get card tags: {}", e))?;
+    let hints = queries::get_card_hints(pool, &card.card_key)
+        .map_err(|e| format!("Failed to get card hints: {}", e))?;
+
+    let html = PracticeTemplate::new(&state, card, tags, hints)
+        .render()
+        .map_err(|e| format!("Template render error: {}", e))?;
+
+    Ok(Html(html).into_response())
+}
+
+/// Records a grade for a flashcard and moves on to the next card.
+///
+/// Updates the card's spaced repetition state with the deck's configured
+/// scheduler (SM-2 or FSRS), appends the review to the review log, then
+/// redirects to /practice.
+///
+/// # Errors
+/// Returns error if database query fails. Responds 404 if no card has this key.
+pub async fn grade(State(state): State<AppState>, Form(form): Form<GradeForm>) -> Result<impl IntoResponse, String> {
+    let pool = &state.pool;
+
+    if !queries::card_key_exists(pool, &form.card_key).map_err(|e| format!("Failed to check card key: {}", e))? {
+        return Ok((StatusCode::NOT_FOUND, format!("No card with key {}", form.card_key)).into_response());
+    }
+
+    let now = scheduler::unix_now();
+    let scheduler = state.config.scheduler.build();
+
+    let current = queries::get_review_state(pool, &form.card_key)
+        .map_err(|e| format!("Failed to get review state: {}", e))?
+        .unwrap_or_else(|| scheduler.new_state(&form.card_key, now));
+
+    let next = scheduler.schedule(&current, form.grade, now);
+    tracing::debug!(
+        "Card {} graded {:?} ({}): next review in {} day(s)",
+        form.card_key,
+        form.grade,
+        scheduler.name(),
+        next.interval_days
+    );
+
+    queries::upsert_review_state(pool, &next).map_err(|e| format!("Failed to save review state: {}", e))?;
+
+    let entry = ReviewLogEntry {
+        card_key: form.card_key,
+        reviewed_at: now,
+        grade: form.grade,
+        time_to_reveal_ms: form.time_to_reveal_ms,
+        time_on_card_ms: form.time_on_card_ms,
+        hints_used: form.hints_used,
+    };
+    queries::insert_review_log(pool, &entry).map_err(|e| format!("Failed to save review log: {}", e))?;
+
+    Ok(Redirect::to("/practice").into_response())
+}
+
+/// Checks the answer typed for a card against its `Expected:` answer.
+///
+/// Returns the feedback fragment shown above the revealed answer: whether the
+/// answer is accepted, its character-level diff and a suggested grade (see
+/// [`typed_answer::check`]).
+///
+/// # Errors
+/// Returns error if database query fails. Responds 404 if the card has no valid expected answer.
+pub async fn check_answer(
+    State(state): State<AppState>,
+    Form(form): Form<CheckAnswerForm>,
+) -> Result<impl IntoResponse, String> {
+    let spec = queries::get_expected_answer(&state.pool, &form.card_key)
+        .map_err(|e| format!("Failed to get expected answer: {}", e))?;
+    let Some(expected) = spec.and_then(|spec| ExpectedAnswer::parse(&spec).ok().flatten()) else {
+        return Ok((StatusCode::NOT_FOUND, format!("No expected answer for card {}", form.card_key)).into_response());
+    };
+
+    let result = typed_answer::check(&expected, &form.answer);
+    let diff = result
+        .diff
+        .into_iter()
+        .map(|segment| match segment {
+            DiffSegment::Same(text) => DiffItem { class: "same", text },
+            DiffSegment::Missing(text) => DiffItem { class: "missing", text },
+            DiffSegment::Extra(text) => DiffItem { class: "extra", text },
+        })
+        .collect();
+
+    let template = AnswerCheckTemplate {
+        correct: result.correct,
+        response: form.answer.trim().to_string(),
+        diff,
+        suggested_grade: result.suggested_grade.as_str(),
+        suggested_label: grade_label(result.suggested_grade),
+    };
+    let html = template.render().map_err(|e| format!("Template render error: {}", e))?;
+
+    Ok(Html(html).into_response())
+}
+
+/// Label of a grade, as on the grading buttons.
+fn grade_label(grade: Grade) -> &'static str {
+    match grade {
+        Grade::Again => "Again",
+        Grade::Hard => "Hard",
+        Grade::Good => "Good",
+        Grade::Easy => "Easy",
+    }
+}
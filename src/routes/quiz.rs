@@ -0,0 +1,288 @@
+// Rust guideline compliant 2025-01
+use askama::Template;
+use axum::{
+    Form,
+    extract::State,
+    response::{Html, IntoResponse, Redirect},
+};
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use tower_sessions::Session;
+
+use crate::db::{connection::DbPool, models::Flashcard, queries};
+use crate::routes::AppState;
+use crate::session::{QuizQuestion, QuizSession, SessionData};
+
+/// Number of wrong options drawn from other cards.
+const RANDOM_DISTRACTORS: usize = 3;
+
+/// Option of a question, with its state once answered.
+struct OptionItem {
+    html: String,
+    /// The card's answer (only set once answered).
+    is_answer: bool,
+    /// Picked by the user (only set once answered).
+    is_choice: bool,
+}
+
+/// Template for a quiz question, before or after it is answered.
+#[derive(Template)]
+#[template(path = "quiz.html")]
+struct QuizTemplate {
+    deck_name: String,
+    card_key: String,
+    category: Option<String>,
+    subcategory: Option<String>,
+    q_html: String,
+    options: Vec<OptionItem>,
+    /// Number of the question in the quiz (1-based).
+    number: u32,
+    /// Score so far.
+    correct: u32,
+    answered: u32,
+    /// Whether the options show the answer (None before it is answered).
+    is_correct: Option<bool>,
+    live_reload: bool,
+}
+
+/// Template for the score at the end of a quiz.
+#[derive(Template)]
+#[template(path = "quiz_results.html")]
+struct QuizResultsTemplate {
+    deck_name: String,
+    correct: u32,
+    answered: u32,
+    /// Score in percent (0 without answers).
+    percent: u32,
+    live_reload: bool,
+}
+
+/// Form data from the option picked for a question.
+#[derive(Debug, Deserialize)]
+pub struct QuizAnswerForm {
+    pub card_key: String,
+    pub choice: usize,
+}
+
+/// Builds the question of a card, None if no wrong option is available.
+///
+/// # Errors
+/// Returns error if database query fails.
+fn build_question(pool: &DbPool, card: &Flashcard) -> anyhow::Result<Option<QuizQuestion>> {
+    // Declared distractors take precedence over drawn ones
+    let mut options = queries::get_card_distractors(pool, &card.card_key)?;
+    if options.is_empty() {
+        options = queries::get_random_distractors(pool, &card.card_key, RANDOM_DISTRACTORS)?;
+    }
+    if options.is_empty() {
+        return Ok(None);
+    }
+
+    // Shuffled positions: the last one is the answer
+    let mut order: Vec<usize> = (0..=options.len()).collect();
+    order.shuffle(&mut rand::rng());
+    options.push(card.answer_html.clone());
+    let answer_index = order.iter().position(|&index| index == options.len() - 1).unwrap_or_default();
+    let options = order.into_iter().map(|index| options[index].clone()).collect();
+
+    Ok(Some(QuizQuestion { card_key: card.card_key.clone(), options, answer_index }))
+}
+
+/// Picks the next card of the quiz and builds its question.
+///
+/// Cards are drawn at random among those matching the filters and not asked
+/// yet; cards without wrong options are marked as seen and skipped. Returns
+/// None when no card is left.
+///
+/// # Errors
+/// Returns error if database query fails.
+fn next_question(pool: &DbPool, session_data: &mut SessionData) -> anyhow::Result<Option<(Flashcard, QuizQuestion)>> {
+    let criteria = session_data.filter_criteria();
+
+    while let Some(card) = queries::get_filtered_random_flashcard(pool, &session_data.quiz.seen_keys, &criteria)? {
+        session_data.quiz.seen_keys.push(card.card_key.clone());
+        if let Some(question) = build_question(pool, &card)? {
+            return Ok(Some((card, question)));
+        }
+    }
+    Ok(None)
+}
+
+impl QuizTemplate {
+    /// Displays `question`, with the answer when `choice` is given.
+    fn new(state: &AppState, card: Flashcard, question: QuizQuestion, quiz: &QuizSession, choice: Option<usize>) -> Self {
+        let options = question
+            .options
+            .into_iter()
+            .enumerate()
+            .map(|(index, html)| OptionItem {
+                html,
+                is_answer: choice.is_some() && index == question.answer_index,
+                is_choice: choice == Some(index),
+            })
+            .collect();
+
+        QuizTemplate {
+            deck_name: state.config.deck_display_name.clone(),
+            card_key: card.card_key,
+            category: card.category,
+            subcategory: card.subcategory,
+            q_html: card.question_html,
+            options,
+            number: if choice.is_some() { quiz.answered } else { quiz.answered + 1 },
+            correct: quiz.correct,
+            answered: quiz.answered,
+            is_correct: choice.map(|choice| choice == question.answer_index),
+            live_reload: state.live_reload.is_some(),
+        }
+    }
+}
+
+/// Displays the next quiz question.
+///
+/// Each card matching the filters is asked once, as a multiple-choice
+/// question: its answer among wrong options, the card's `Distractors:` if it
+/// declares some (see [`crate::content::distractors`]), else answers of other
+/// cards of its subcategory. Cards without any wrong option are skipped. Quiz
+/// answers do not touch spaced repetition.
+///
+/// A question shown and not answered yet is displayed again (on reload). When
+/// every matching card has been asked, redirects to the score (/quiz_results).
+///
+/// # Errors
+/// Returns error if database query or session operation fails.
+pub async fn quiz(State(state): State<AppState>, session: Session) -> Result<impl IntoResponse, String> {
+    let pool = &state.pool;
+    let mut session_data: SessionData = session
+        .get("data")
+        .await
+        .map_err(|e| format!("Session get error: {}", e))?
+        .unwrap_or_default();
+
+    let pending = match session_data.quiz.pending.clone() {
+        Some(question) => queries::get_flashcard_by_key(pool, &question.card_key)
+            .map_err(|e| format!("Failed to get flashcard: {}", e))?
+            .map(|card| (card, question)),
+        None => None,
+    };
+    // Pending card removed by a reload: ask another one
+    let next = match pending {
+        Some(pending) => Some(pending),
+        None => next_question(pool, &mut session_data).map_err(|e| format!("Failed to get quiz question: {}", e))?,
+    };
+
+    let Some((card, question)) = next else {
+        session_data.quiz.pending = None;
+        session
+            .insert("data", &session_data)
+            .await
+            .map_err(|e| format!("Session insert error: {}", e))?;
+        return Ok(Redirect::to("/quiz_results").into_response());
+    };
+
+    session_data.quiz.pending = Some(question.clone());
+    session
+        .insert("data", &session_data)
+        .await
+        .map_err(|e| format!("Session insert error: {}", e))?;
+
+    let template = QuizTemplate::new(&state, card, question, &session_data.quiz, None);
+    let html = template.render().map_err(|e| format!("Template render error: {}", e))?;
+
+    Ok(Html(html).into_response())
+}
+
+/// Records the option picked for the pending question and shows the answer.
+///
+/// Answers to a question that is not pending (resubmitted form, another tab)
+/// are not counted: they redirect to /quiz.
+///
+/// # Errors
+/// Returns error if database query or session operation fails.
+pub async fn quiz_answer(
+    State(state): State<AppState>,
+    session: Session,
+    Form(form): Form<QuizAnswerForm>,
+) -> Result<impl IntoResponse, String> {
+    let pool = &state.pool;
+    let mut session_data: SessionData = session
+        .get("data")
+        .await
+        .map_err(|e| format!("Session get error: {}", e))?
+        .unwrap_or_default();
+
+    let Some(question) = session_data.quiz.pending.take_if(|question| question.card_key == form.card_key) else {
+        return Ok(Redirect::to("/quiz").into_response());
+    };
+    let Some(card) = queries::get_flashcard_by_key(pool, &question.card_key)
+        .map_err(|e| format!("Failed to get flashcard: {}", e))?
+    else {
+        return Ok(Redirect::to("/quiz").into_response());
+    };
+
+    session_data.quiz.answered += 1;
+    if form.choice == question.answer_index {
+        session_data.quiz.correct += 1;
+    }
+    session
+        .insert("data", &session_data)
+        .await
+        .map_err(|e| format!("Session insert error: {}", e))?;
+
+    let template = QuizTemplate::new(&state, card, question, &session_data.quiz, Some(form.choice));
+    let html = template.render().map_err(|e| format!("Template render error: {}", e))?;
+
+    Ok(Html(html).into_response())
+}
+
+/// Shows the score of the quiz, and starts a new one.
+///
+/// # Errors
+/// Returns error if session operation fails.
+pub async fn quiz_results(State(state): State<AppState>, session: Session) -> Result<impl IntoResponse, String> {
+    let mut session_data: SessionData = session
+        .get("data")
+        .await
+        .map_err(|e| format!("Session get error: {}", e))?
+        .unwrap_or_default();
+
+    let quiz = std::mem::take(&mut session_data.quiz);
+    session
+        .insert("data", &session_data)
+        .await
+        .map_err(|e| format!("Session insert error: {}", e))?;
+
+    let template = QuizResultsTemplate {
+        deck_name: state.config.deck_display_name.clone(),
+        correct: quiz.correct,
+        answered: quiz.answered,
+        percent: score_percent(quiz.correct, quiz.answered),
+        live_reload: state.live_reload.is_some(),
+    };
+    let html = template.render().map_err(|e| format!("Template render error: {}", e))?;
+
+    Ok(Html(html))
+}
+
+/// Share of correct answers, rounded to the nearest percent (0 without answers).
+fn score_percent(correct: u32, answered: u32) -> u32 {
+    if answered == 0 {
+        return 0;
+    }
+    (f64::from(correct) * 100.0 / f64::from(answered)).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 0, 0)]
+    #[case(3, 4, 75)]
+    #[case(2, 3, 67)]
+    #[case(5, 5, 100)]
+    fn test_score_percent(#[case] correct: u32, #[case] answered: u32, #[case] expected: u32) {
+        assert_eq!(score_percent(correct, answered), expected);
+    }
+}
@@ -0,0 +1,183 @@
+// Rust guideline compliant 2025-01
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::Deserialize;
+
+use crate::db::models::{MATCH_END, MATCH_START, SearchHit};
+use crate::db::{queries, search};
+use crate::routes::AppState;
+use crate::routes::practice::card_url;
+
+/// Number of results per page.
+const PAGE_SIZE: i64 = 20;
+
+/// Query string of the search page.
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    /// Search query (see [`crate::db::search`]).
+    #[serde(default)]
+    pub q: String,
+    /// Page number, starting at 1.
+    pub page: Option<i64>,
+}
+
+/// Part of an excerpt, highlighted when it is a matched term.
+struct Segment {
+    text: String,
+    is_match: bool,
+}
+
+/// One search result.
+struct ResultItem {
+    url: String,
+    category: Option<String>,
+    subcategory: Option<String>,
+    difficulty: Option<String>,
+    /// Thumbnail `<img>` tag of image cards.
+    thumbnail_html: Option<String>,
+    question: Vec<Segment>,
+    answer: Vec<Segment>,
+}
+
+/// Template for search results.
+#[derive(Template)]
+#[template(path = "search.html")]
+struct SearchTemplate {
+    deck_name: String,
+    query: String,
+    error_message: Option<String>,
+    total: i64,
+    page: i64,
+    page_count: i64,
+    results: Vec<ResultItem>,
+    prev_url: Option<String>,
+    next_url: Option<String>,
+    live_reload: bool,
+}
+
+/// Splits an excerpt into plain and matched segments (see [`MATCH_START`]).
+fn excerpt_segments(excerpt: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut rest = excerpt.trim();
+
+    while let Some(start) = rest.find(MATCH_START) {
+        let after = &rest[start + MATCH_START.len_utf8()..];
+        let end = after.find(MATCH_END).unwrap_or(after.len());
+        segments.push(Segment { text: rest[..start].to_string(), is_match: false });
+        segments.push(Segment { text: after[..end].to_string(), is_match: true });
+        rest = after.get(end + MATCH_END.len_utf8()..).unwrap_or("");
+    }
+    segments.push(Segment { text: rest.to_string(), is_match: false });
+
+    segments.retain(|s| !s.text.is_empty());
+    segments
+}
+
+/// Builds the URL of a result page.
+fn page_url(query: &str, page: i64) -> String {
+    format!("/search?q={}&page={}", utf8_percent_encode(query, NON_ALPHANUMERIC), page)
+}
+
+impl From<SearchHit> for ResultItem {
+    fn from(hit: SearchHit) -> Self {
+        ResultItem {
+            url: card_url(&hit.card.card_key),
+            category: hit.card.category,
+            subcategory: hit.card.subcategory,
+            difficulty: hit.card.difficulty,
+            thumbnail_html: hit.card.thumbnail_html,
+            question: excerpt_segments(&hit.question_excerpt),
+            answer: excerpt_segments(&hit.answer_excerpt),
+        }
+    }
+}
+
+/// Lists the cards matching a search query, best matches first.
+///
+/// Results are ranked by relevance and paginated, with the matched terms
+/// highlighted in excerpts of each card. A malformed query is reported above
+/// the search field.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub async fn search(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<impl IntoResponse, String> {
+    let pool = &state.pool;
+    let query = params.q.trim().to_string();
+    let mut page = params.page.unwrap_or(1).max(1);
+
+    let mut error_message = None;
+    let mut total = 0;
+    let mut results = Vec::new();
+
+    match search::compile(&query) {
+        Err(e) => error_message = Some(format!("Invalid search: {}", e)),
+        Ok(None) => {}
+        Ok(Some(match_query)) => {
+            total = queries::count_search_results(pool, &match_query)
+                .map_err(|e| format!("Failed to count search results: {}", e))?;
+
+            // Out of range pages show the last one
+            page = page.min((total + PAGE_SIZE - 1) / PAGE_SIZE).max(1);
+
+            results = queries::search_flashcards(pool, &match_query, PAGE_SIZE, (page - 1) * PAGE_SIZE)
+                .map_err(|e| format!("Failed to search cards: {}", e))?
+                .into_iter()
+                .map(ResultItem::from)
+                .collect();
+        }
+    }
+
+    let page_count = (total + PAGE_SIZE - 1) / PAGE_SIZE;
+
+    let template = SearchTemplate {
+        deck_name: state.config.deck_display_name.clone(),
+        prev_url: (page > 1).then(|| page_url(&query, page - 1)),
+        next_url: (page < page_count).then(|| page_url(&query, page + 1)),
+        query,
+        error_message,
+        total,
+        page,
+        page_count,
+        results,
+        live_reload: state.live_reload.is_some(),
+    };
+
+    Ok(Html(template.render().map_err(|e| format!("Template error: {}", e))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Renders segments as `[match]` for readable assertions.
+    fn render(segments: &[Segment]) -> String {
+        segments
+            .iter()
+            .map(|s| if s.is_match { format!("[{}]", s.text) } else { s.text.clone() })
+            .collect()
+    }
+
+    #[rstest]
+    #[case("no match", "no match")]
+    #[case("a \u{2}b\u{3} c", "a [b] c")]
+    #[case("\u{2}first\u{3} and \u{2}last\u{3}", "[first] and [last]")]
+    #[case(" \u{2}x\u{3}\u{2}y\u{3} ", "[x][y]")]
+    #[case("unclosed \u{2}match", "unclosed [match]")]
+    #[case("", "")]
+    fn test_excerpt_segments(#[case] excerpt: &str, #[case] expected: &str) {
+        assert_eq!(render(&excerpt_segments(excerpt)), expected);
+    }
+
+    #[test]
+    fn test_page_url_encodes_query() {
+        assert_eq!(page_url("\"a b\" -c", 2), "/search?q=%22a%20b%22%20%2Dc&page=2");
+    }
+}
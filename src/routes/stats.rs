@@ -0,0 +1,236 @@
+// Rust guideline compliant 2025-01
+use askama::Template;
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse},
+};
+
+use crate::db::{
+    models::{DailyCount, RetentionStats},
+    queries,
+};
+use crate::routes::AppState;
+use crate::scheduler;
+
+/// Number of days covered by the review history and the due forecast.
+const STATS_DAYS: i64 = 30;
+
+/// Retention (in percent) below which a topic is highlighted as weak.
+const WEAK_RETENTION_PERCENT: f64 = 80.0;
+
+/// One bar of a per-day chart.
+struct BarItem {
+    day: String,
+    count: i64,
+    /// Bar width relative to the busiest day (0-100).
+    width_pct: i64,
+}
+
+/// One row of the retention table (category, or subcategory below it).
+struct RetentionRow {
+    name: String,
+    is_category: bool,
+    reviews: i64,
+    /// Formatted retention ("85%"), None without reviews.
+    retention: Option<String>,
+    is_weak: bool,
+}
+
+/// Template for statistics dashboard.
+#[derive(Template)]
+#[template(path = "stats.html")]
+struct StatsTemplate {
+    deck_name: String,
+    days: i64,
+    total_reviews: i64,
+    overall_retention: Option<String>,
+    reviews_per_day: Vec<BarItem>,
+    retention: Vec<RetentionRow>,
+    forecast: Vec<BarItem>,
+}
+
+/// Converts daily counts to bars scaled on the largest count.
+fn bar_items(counts: Vec<DailyCount>) -> Vec<BarItem> {
+    let max = counts.iter().map(|c| c.count).max().unwrap_or(0).max(1);
+    counts
+        .into_iter()
+        .map(|c| BarItem {
+            width_pct: c.count * 100 / max,
+            day: c.day,
+            count: c.count,
+        })
+        .collect()
+}
+
+/// Builds a retention row from aggregated stats.
+fn retention_row(name: String, is_category: bool, stats: &RetentionStats) -> RetentionRow {
+    let percent = stats.retention_percent();
+    RetentionRow {
+        name,
+        is_category,
+        reviews: stats.reviews,
+        retention: percent.map(|p| format!("{:.0}%", p)),
+        is_weak: percent.is_some_and(|p| p < WEAK_RETENTION_PERCENT),
+    }
+}
+
+/// Sums retention stats into a single group.
+fn sum_stats<'a>(stats: impl Iterator<Item = &'a RetentionStats>) -> RetentionStats {
+    stats.fold(
+        RetentionStats {
+            category: None,
+            subcategory: None,
+            reviews: 0,
+            recalled: 0,
+        },
+        |mut acc, s| {
+            acc.reviews += s.reviews;
+            acc.recalled += s.recalled;
+            acc
+        },
+    )
+}
+
+/// Builds retention rows: each category followed by its subcategories.
+///
+/// Every category and subcategory of the deck is listed, reviewed or not.
+/// Cards without category (markdown or image) get an "Uncategorized" row when reviewed.
+fn retention_rows(categories: &[String], subcategories: &[(String, String)], stats: &[RetentionStats]) -> Vec<RetentionRow> {
+    let mut rows = Vec::new();
+
+    for category in categories {
+        let in_category = || stats.iter().filter(|s| s.category.as_ref() == Some(category));
+        rows.push(retention_row(category.clone(), true, &sum_stats(in_category())));
+
+        for (subcategory, _) in subcategories.iter().filter(|(_, parent)| parent == category) {
+            let sub_stats = sum_stats(in_category().filter(|s| s.subcategory.as_ref() == Some(subcategory)));
+            rows.push(retention_row(subcategory.clone(), false, &sub_stats));
+        }
+    }
+
+    let uncategorized = sum_stats(stats.iter().filter(|s| s.category.is_none()));
+    if uncategorized.reviews > 0 {
+        rows.push(retention_row("Uncategorized".to_string(), true, &uncategorized));
+    }
+
+    rows
+}
+
+/// Displays study statistics.
+///
+/// Shows reviews per day over the last 30 days, retention per category and
+/// subcategory (share of reviews not graded "Again"), and the number of graded
+/// cards coming due each day over the next 30 days.
+///
+/// # Errors
+/// Returns error if database query fails.
+pub async fn stats(State(state): State<AppState>) -> Result<impl IntoResponse, String> {
+    let pool = &state.pool;
+    let now = scheduler::unix_now();
+
+    let reviews_per_day =
+        queries::get_reviews_per_day(pool, now, STATS_DAYS).map_err(|e| format!("Failed to get reviews per day: {}", e))?;
+    let forecast = queries::get_due_forecast(pool, now, STATS_DAYS).map_err(|e| format!("Failed to get due forecast: {}", e))?;
+    let stats = queries::get_retention_stats(pool).map_err(|e| format!("Failed to get retention: {}", e))?;
+    let categories = queries::get_distinct_categories(pool).map_err(|e| format!("Failed to get categories: {}", e))?;
+    let subcategories =
+        queries::get_distinct_subcategories(pool, None).map_err(|e| format!("Failed to get subcategories: {}", e))?;
+
+    let overall = sum_stats(stats.iter());
+
+    let template = StatsTemplate {
+        deck_name: state.config.deck_display_name.clone(),
+        days: STATS_DAYS,
+        total_reviews: overall.reviews,
+        overall_retention: overall.retention_percent().map(|p| format!("{:.0}%", p)),
+        reviews_per_day: bar_items(reviews_per_day),
+        retention: retention_rows(&categories, &subcategories, &stats),
+        forecast: bar_items(forecast),
+    };
+
+    let html = template.render().map_err(|e| format!("Template render error: {}", e))?;
+
+    Ok(Html(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(category: Option<&str>, subcategory: Option<&str>, reviews: i64, recalled: i64) -> RetentionStats {
+        RetentionStats {
+            category: category.map(String::from),
+            subcategory: subcategory.map(String::from),
+            reviews,
+            recalled,
+        }
+    }
+
+    #[test]
+    fn test_bar_items_scaled_on_max() {
+        let counts = vec![
+            DailyCount { day: "2024-01-01".to_string(), count: 0 },
+            DailyCount { day: "2024-01-02".to_string(), count: 5 },
+            DailyCount { day: "2024-01-03".to_string(), count: 10 },
+        ];
+
+        let widths: Vec<i64> = bar_items(counts).iter().map(|b| b.width_pct).collect();
+
+        assert_eq!(widths, vec![0, 50, 100]);
+    }
+
+    #[test]
+    fn test_bar_items_all_zero() {
+        let counts = vec![DailyCount { day: "2024-01-01".to_string(), count: 0 }];
+        assert_eq!(bar_items(counts)[0].width_pct, 0);
+    }
+
+    #[test]
+    fn test_retention_rows_lists_unreviewed_topics() {
+        let categories = vec!["Math".to_string(), "Science".to_string()];
+        let subcategories = vec![
+            ("Algebra".to_string(), "Math".to_string()),
+            ("Geometry".to_string(), "Math".to_string()),
+            ("Physics".to_string(), "Science".to_string()),
+        ];
+        let stats = vec![
+            stat(Some("Math"), Some("Algebra"), 4, 1),
+            stat(Some("Math"), Some("Geometry"), 6, 6),
+        ];
+
+        let rows = retention_rows(&categories, &subcategories, &stats);
+
+        let names: Vec<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Math", "Algebra", "Geometry", "Science", "Physics"]);
+
+        // Category row aggregates its subcategories
+        assert_eq!(rows[0].reviews, 10);
+        assert_eq!(rows[0].retention.as_deref(), Some("70%"));
+        assert!(rows[0].is_weak);
+
+        assert_eq!(rows[1].retention.as_deref(), Some("25%"));
+        assert!(rows[1].is_weak);
+        assert_eq!(rows[2].retention.as_deref(), Some("100%"));
+        assert!(!rows[2].is_weak);
+
+        // Never reviewed
+        assert_eq!(rows[3].reviews, 0);
+        assert!(rows[3].retention.is_none());
+        assert!(!rows[3].is_weak);
+    }
+
+    #[test]
+    fn test_retention_rows_adds_uncategorized_row() {
+        let categories = vec!["Maths".to_string()];
+        let subcategories = vec![("Probability".to_string(), "Maths".to_string())];
+        // Image cards may take a category from their directory
+        let stats = vec![stat(None, None, 2, 2), stat(Some("Maths"), Some("Probability"), 3, 1)];
+
+        let rows = retention_rows(&categories, &subcategories, &stats);
+
+        let names: Vec<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Maths", "Probability", "Uncategorized"]);
+        assert_eq!(rows[0].reviews, 3);
+        assert_eq!(rows[2].reviews, 2);
+    }
+}
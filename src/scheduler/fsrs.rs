@@ -0,0 +1,412 @@
+// Rust guideline compliant 2025-01
+//! FSRS scheduling algorithm.
+//!
+//! Free Spaced Repetition Scheduler (FSRS-4.5) with its published default
+//! weights. Each card carries a memory stability (days until recall probability
+//! drops to the target retention) and a difficulty (1 = easiest, 10 = hardest),
+//! both updated after every review from the grade and the elapsed time.
+
+use super::{Grade, RELEARN_DELAY_SECS, SECONDS_PER_DAY, Scheduler};
+use crate::db::models::ReviewState;
+
+/// FSRS-4.5 default weights.
+pub const DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072, 0.0793, 0.3246,
+    1.587, 0.2272, 2.8755,
+];
+
+/// Default probability of recall targeted when a card comes due.
+pub const DEFAULT_RETENTION: f64 = 0.9;
+
+/// Longest interval ever scheduled, in days.
+pub const MAX_INTERVAL_DAYS: i64 = 36_500;
+
+/// Exponent of the forgetting curve.
+const DECAY: f64 = -0.5;
+
+/// Forgetting curve factor, chosen so that recall probability is 0.9 after `stability` days.
+const FACTOR: f64 = 19.0 / 81.0;
+
+/// FSRS scheduler.
+///
+/// Cards graded `Again` are relearned after [`RELEARN_DELAY_SECS`]; other
+/// grades schedule the card when its predicted recall probability falls to
+/// the target retention, with `Hard <= Good < Easy`.
+///
+/// Cards previously scheduled with SM-2 (no stability yet) start from their
+/// current interval as stability.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::scheduler::{Grade, Scheduler, fsrs::Fsrs};
+///
+/// let fsrs = Fsrs::default();
+/// let state = fsrs.new_state("my-card", 0);
+/// let next = fsrs.schedule(&state, Grade::Good, 0);
+/// assert_eq!(next.interval_days, 4);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fsrs {
+    /// Model weights (w0..w16).
+    pub weights: [f64; 17],
+    /// Target probability of recall (0 < retention < 1).
+    pub desired_retention: f64,
+}
+
+impl Default for Fsrs {
+    fn default() -> Self {
+        Self {
+            weights: DEFAULT_WEIGHTS,
+            desired_retention: DEFAULT_RETENTION,
+        }
+    }
+}
+
+/// Memory state of a card as modelled by FSRS.
+#[derive(Debug, Clone, Copy)]
+struct Memory {
+    stability: f64,
+    difficulty: f64,
+}
+
+impl Fsrs {
+    /// Returns the probability of recalling a card `elapsed_days` after its last review.
+    fn retrievability(elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FACTOR * elapsed_days / stability).powf(DECAY)
+    }
+
+    /// Returns the interval (in days) after which recall probability falls to the target retention.
+    fn interval(&self, stability: f64) -> i64 {
+        let days = stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        (days.round() as i64).clamp(1, MAX_INTERVAL_DAYS)
+    }
+
+    fn init_stability(&self, grade: Grade) -> f64 {
+        self.weights[usize::from(grade.rating()) - 1].max(0.1)
+    }
+
+    fn init_difficulty(&self, grade: Grade) -> f64 {
+        let w = &self.weights;
+        (w[4] - (f64::from(grade.rating()) - 3.0) * w[5]).clamp(1.0, 10.0)
+    }
+
+    fn next_difficulty(&self, difficulty: f64, grade: Grade) -> f64 {
+        let w = &self.weights;
+        let next = difficulty - w[6] * (f64::from(grade.rating()) - 3.0);
+        // Mean reversion towards the initial difficulty of a "Good" card
+        (w[7] * w[4] + (1.0 - w[7]) * next).clamp(1.0, 10.0)
+    }
+
+    fn recall_stability(&self, memory: Memory, retrievability: f64, grade: Grade) -> f64 {
+        let w = &self.weights;
+        let hard_penalty = if grade == Grade::Hard { w[15] } else { 1.0 };
+        let easy_bonus = if grade == Grade::Easy { w[16] } else { 1.0 };
+        memory.stability
+            * (1.0
+                + w[8].exp()
+                    * (11.0 - memory.difficulty)
+                    * memory.stability.powf(-w[9])
+                    * ((w[10] * (1.0 - retrievability)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus)
+    }
+
+    fn forget_stability(&self, memory: Memory, retrievability: f64) -> f64 {
+        let w = &self.weights;
+        let stability = w[11]
+            * memory.difficulty.powf(-w[12])
+            * ((memory.stability + 1.0).powf(w[13]) - 1.0)
+            * (w[14] * (1.0 - retrievability)).exp();
+        // Forgetting never makes the memory stronger
+        stability.min(memory.stability)
+    }
+
+    /// Returns the memory state after grading, or `None` for a card never reviewed.
+    fn current_memory(state: &ReviewState) -> Option<Memory> {
+        if state.stability > 0.0 {
+            Some(Memory {
+                stability: state.stability,
+                difficulty: state.difficulty.clamp(1.0, 10.0),
+            })
+        } else if state.repetitions > 0 || state.lapses > 0 {
+            // Card scheduled with SM-2 so far: its interval is the best stability estimate
+            Some(Memory {
+                stability: state.interval_days.max(1) as f64,
+                difficulty: 5.0,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Computes the memory state following a review graded `grade`.
+    fn next_memory(&self, state: &ReviewState, grade: Grade, now: i64) -> Memory {
+        match Self::current_memory(state) {
+            None => Memory {
+                stability: self.init_stability(grade),
+                difficulty: self.init_difficulty(grade),
+            },
+            Some(memory) => {
+                let elapsed_days = (now - state.last_reviewed_at).max(0) as f64 / SECONDS_PER_DAY as f64;
+                let retrievability = Self::retrievability(elapsed_days, memory.stability);
+                let stability = if grade == Grade::Again {
+                    self.forget_stability(memory, retrievability)
+                } else {
+                    self.recall_stability(memory, retrievability, grade)
+                };
+                Memory {
+                    stability,
+                    difficulty: self.next_difficulty(memory.difficulty, grade),
+                }
+            }
+        }
+    }
+}
+
+impl Scheduler for Fsrs {
+    fn name(&self) -> &'static str {
+        "fsrs"
+    }
+
+    fn schedule(&self, state: &ReviewState, grade: Grade, now: i64) -> ReviewState {
+        let memory = self.next_memory(state, grade, now);
+
+        let mut next = state.clone();
+        next.last_reviewed_at = now;
+        next.stability = memory.stability;
+        next.difficulty = memory.difficulty;
+
+        if grade == Grade::Again {
+            if state.repetitions > 0 {
+                next.lapses += 1;
+            }
+            next.repetitions = 0;
+            next.interval_days = 0;
+            next.due_at = now + RELEARN_DELAY_SECS;
+            return next;
+        }
+
+        // Keep Hard <= Good < Easy whatever the weights, leaving Easy a day above Good at the interval cap
+        let hard = self.interval(self.next_memory(state, Grade::Hard, now).stability).min(MAX_INTERVAL_DAYS - 1);
+        let good = self.interval(self.next_memory(state, Grade::Good, now).stability).clamp(hard, MAX_INTERVAL_DAYS - 1);
+        let interval_days = match grade {
+            Grade::Hard => hard,
+            Grade::Good => good,
+            Grade::Easy => self.interval(memory.stability).max(good + 1),
+            Grade::Again => unreachable!("handled above"),
+        };
+
+        next.repetitions += 1;
+        next.interval_days = interval_days;
+        next.due_at = now + interval_days * SECONDS_PER_DAY;
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn new_state() -> ReviewState {
+        Fsrs::default().new_state("card", NOW)
+    }
+
+    #[rstest]
+    #[case(Grade::Hard, 1)]
+    #[case(Grade::Good, 4)]
+    #[case(Grade::Easy, 14)]
+    fn test_first_review_intervals(#[case] grade: Grade, #[case] expected_days: i64) {
+        let next = Fsrs::default().schedule(&new_state(), grade, NOW);
+
+        assert_eq!(next.interval_days, expected_days);
+        assert_eq!(next.repetitions, 1);
+        assert_eq!(next.due_at, NOW + expected_days * SECONDS_PER_DAY);
+    }
+
+    #[rstest]
+    #[case(Grade::Again, 7.62)]
+    #[case(Grade::Hard, 6.39)]
+    #[case(Grade::Good, 5.16)]
+    #[case(Grade::Easy, 3.93)]
+    fn test_first_review_difficulty(#[case] grade: Grade, #[case] expected: f64) {
+        let next = Fsrs::default().schedule(&new_state(), grade, NOW);
+        assert!((next.difficulty - expected).abs() < 0.01, "difficulty {}", next.difficulty);
+    }
+
+    #[test]
+    fn test_interval_equals_stability_at_default_retention() {
+        let fsrs = Fsrs::default();
+        assert_eq!(fsrs.interval(10.0), 10);
+        assert_eq!(fsrs.interval(100.0), 100);
+    }
+
+    #[test]
+    fn test_higher_retention_shortens_intervals() {
+        let strict = Fsrs {
+            desired_retention: 0.95,
+            ..Fsrs::default()
+        };
+        assert!(strict.interval(100.0) < Fsrs::default().interval(100.0));
+    }
+
+    #[test]
+    fn test_successive_good_reviews_grow_interval() {
+        let fsrs = Fsrs::default();
+        let mut state = new_state();
+        let mut now = NOW;
+        let mut previous = 0;
+
+        for _ in 0..5 {
+            state = fsrs.schedule(&state, Grade::Good, now);
+            assert!(state.interval_days > previous);
+            previous = state.interval_days;
+            now = state.due_at;
+        }
+    }
+
+    #[test]
+    fn test_again_relearns_soon_and_lowers_stability() {
+        let fsrs = Fsrs::default();
+        let learned = fsrs.schedule(&new_state(), Grade::Good, NOW);
+        let now = learned.due_at;
+
+        let next = fsrs.schedule(&learned, Grade::Again, now);
+
+        assert_eq!(next.due_at, now + RELEARN_DELAY_SECS);
+        assert_eq!(next.interval_days, 0);
+        assert_eq!(next.repetitions, 0);
+        assert_eq!(next.lapses, 1);
+        assert!(next.stability < learned.stability);
+        assert!(next.difficulty > learned.difficulty);
+    }
+
+    #[test]
+    fn test_again_on_new_card_is_not_a_lapse() {
+        let next = Fsrs::default().schedule(&new_state(), Grade::Again, NOW);
+        assert_eq!(next.lapses, 0);
+    }
+
+    #[test]
+    fn test_sm2_state_uses_interval_as_stability() {
+        let sm2_state = ReviewState {
+            interval_days: 30,
+            repetitions: 4,
+            last_reviewed_at: NOW - 30 * SECONDS_PER_DAY,
+            ..new_state()
+        };
+
+        let next = Fsrs::default().schedule(&sm2_state, Grade::Good, NOW);
+
+        assert!(next.interval_days > 30);
+        assert!(next.stability > 30.0);
+    }
+
+    #[test]
+    fn test_overdue_review_grows_stability_more() {
+        let fsrs = Fsrs::default();
+        let learned = fsrs.schedule(&new_state(), Grade::Good, NOW);
+
+        let on_time = fsrs.schedule(&learned, Grade::Good, learned.due_at);
+        let late = fsrs.schedule(&learned, Grade::Good, learned.due_at + 10 * SECONDS_PER_DAY);
+
+        assert!(late.stability > on_time.stability);
+    }
+
+    #[test]
+    fn test_easy_beyond_good_at_interval_cap() {
+        let fsrs = Fsrs::default();
+        let learned = ReviewState {
+            stability: 100_000.0,
+            difficulty: 1.0,
+            repetitions: 10,
+            last_reviewed_at: NOW - 100_000 * SECONDS_PER_DAY,
+            ..new_state()
+        };
+
+        let good = fsrs.schedule(&learned, Grade::Good, NOW);
+        let easy = fsrs.schedule(&learned, Grade::Easy, NOW);
+
+        assert_eq!(good.interval_days, MAX_INTERVAL_DAYS - 1);
+        assert_eq!(easy.interval_days, MAX_INTERVAL_DAYS);
+    }
+
+    // ========== Property-Based Tests ==========
+
+    /// Property-based tests using proptest for FSRS memory invariants.
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Generates learned review states (stability, difficulty, days since last review).
+        fn arb_learned() -> impl Strategy<Value = ReviewState> {
+            (0.1f64..3650.0, 1.0f64..=10.0, 0i64..1000, 1i64..20).prop_map(|(stability, difficulty, elapsed, repetitions)| {
+                ReviewState {
+                    stability,
+                    difficulty,
+                    repetitions,
+                    last_reviewed_at: NOW - elapsed * SECONDS_PER_DAY,
+                    ..new_state()
+                }
+            })
+        }
+
+        fn arb_grade() -> impl Strategy<Value = Grade> {
+            prop::sample::select(Grade::ALL.to_vec())
+        }
+
+        /// Tests that difficulty stays within its [1, 10] range.
+        ///
+        /// Invariant: 1 <= schedule(state, grade).difficulty <= 10.
+        #[test]
+        fn prop_difficulty_in_range() {
+            proptest!(|(state in arb_learned(), grade in arb_grade())| {
+                let next = Fsrs::default().schedule(&state, grade, NOW);
+                prop_assert!((1.0..=10.0).contains(&next.difficulty));
+            });
+        }
+
+        /// Tests that stability stays positive and only drops on failure.
+        ///
+        /// Invariant: successful reviews never decrease stability, Again never increases it.
+        #[test]
+        fn prop_stability_moves_with_grade() {
+            proptest!(|(state in arb_learned(), grade in arb_grade())| {
+                let next = Fsrs::default().schedule(&state, grade, NOW);
+
+                prop_assert!(next.stability > 0.0);
+                if grade == Grade::Again {
+                    prop_assert!(next.stability <= state.stability);
+                } else {
+                    prop_assert!(next.stability >= state.stability);
+                }
+            });
+        }
+
+        /// Tests that easier grades schedule further out.
+        ///
+        /// Invariant: interval(Hard) <= interval(Good) < interval(Easy), up to the interval cap.
+        #[test]
+        fn prop_intervals_ordered_by_grade() {
+            proptest!(|(state in arb_learned())| {
+                let fsrs = Fsrs::default();
+                let [hard, good, easy] =
+                    [Grade::Hard, Grade::Good, Grade::Easy].map(|grade| fsrs.schedule(&state, grade, NOW).interval_days);
+                prop_assert!(hard <= good && good < easy);
+            });
+        }
+
+        /// Tests that intervals stay within bounds.
+        ///
+        /// Invariant: 1 <= interval_days <= MAX_INTERVAL_DAYS for successful reviews.
+        #[test]
+        fn prop_interval_bounded() {
+            proptest!(|(state in arb_learned(), grade in prop::sample::select(vec![Grade::Hard, Grade::Good, Grade::Easy]))| {
+                let next = Fsrs::default().schedule(&state, grade, NOW);
+                prop_assert!((1..=MAX_INTERVAL_DAYS).contains(&next.interval_days));
+            });
+        }
+    }
+}
@@ -0,0 +1,282 @@
+// Rust guideline compliant 2025-01
+//! Spaced repetition scheduling.
+//!
+//! Turns a self-assessed [`Grade`] into the next
+//! [`ReviewState`] of a card
+//! (ease or stability/difficulty, interval, due date, lapses).
+//!
+//! The algorithm sits behind the [`Scheduler`] trait and is selected per deck
+//! with [`SchedulerKind`] (`SCHEDULER` env var or `--scheduler` CLI argument).
+
+pub mod fsrs;
+pub mod sm2;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::db::models::ReviewState;
+
+/// Number of seconds in one day.
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Delay before a failed card is shown again, in seconds.
+pub const RELEARN_DELAY_SECS: i64 = 600;
+
+/// Self-assessed recall quality submitted from the practice page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    /// Forgotten: the card comes back within minutes.
+    Again,
+    /// Recalled with serious difficulty.
+    Hard,
+    /// Recalled correctly after some hesitation.
+    Good,
+    /// Recalled instantly.
+    Easy,
+}
+
+impl Grade {
+    /// All grades, ordered from worst to best.
+    pub const ALL: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+    /// Returns the lowercase name of the grade, as submitted by the practice page.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+
+    /// Returns the numeric rating used by scheduling formulas (1 = Again ... 4 = Easy).
+    pub fn rating(self) -> u8 {
+        match self {
+            Grade::Again => 1,
+            Grade::Hard => 2,
+            Grade::Good => 3,
+            Grade::Easy => 4,
+        }
+    }
+}
+
+/// Spaced repetition algorithm.
+///
+/// Implementations are stateless policies: they compute the next review state
+/// of a card from its current state, the grade given, and the current time.
+pub trait Scheduler: Send + Sync {
+    /// Short identifier of the algorithm (as accepted by [`SchedulerKind`]).
+    fn name(&self) -> &'static str;
+
+    /// Returns the review state of a card that was never graded.
+    fn new_state(&self, card_key: &str, now: i64) -> ReviewState {
+        ReviewState {
+            card_key: card_key.to_string(),
+            ease: sm2::INITIAL_EASE,
+            interval_days: 0,
+            repetitions: 0,
+            lapses: 0,
+            due_at: now,
+            last_reviewed_at: now,
+            stability: 0.0,
+            difficulty: 0.0,
+        }
+    }
+
+    /// Computes the next review state after grading a card at `now`.
+    ///
+    /// Implementations guarantee `due_at >= now` and, for a given state,
+    /// `Hard <= Good < Easy` in terms of due date.
+    fn schedule(&self, state: &ReviewState, grade: Grade, now: i64) -> ReviewState;
+}
+
+/// Available scheduling algorithms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchedulerKind {
+    /// SuperMemo-2 (ease factor based).
+    #[default]
+    Sm2,
+    /// Free Spaced Repetition Scheduler with its default weights.
+    Fsrs,
+}
+
+impl SchedulerKind {
+    /// Instantiates the scheduler implementing this algorithm.
+    pub fn build(self) -> Box<dyn Scheduler> {
+        match self {
+            SchedulerKind::Sm2 => Box::new(sm2::Sm2),
+            SchedulerKind::Fsrs => Box::new(fsrs::Fsrs::default()),
+        }
+    }
+}
+
+impl FromStr for SchedulerKind {
+    type Err = String;
+
+    /// Parses an algorithm name (case-insensitive): `sm2` or `fsrs`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as clap::ValueEnum>::from_str(s.trim(), true).map_err(|_| format!("Unknown scheduler '{}' (expected sm2 or fsrs)", s))
+    }
+}
+
+impl fmt::Display for SchedulerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.build().name())
+    }
+}
+
+/// Returns current time as seconds since the Unix epoch.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_grade_deserializes_lowercase() {
+        let grade: Grade = serde_json::from_str("\"again\"").unwrap();
+        assert_eq!(grade, Grade::Again);
+
+        let grade: Grade = serde_json::from_str("\"easy\"").unwrap();
+        assert_eq!(grade, Grade::Easy);
+    }
+
+    #[test]
+    fn test_grade_as_str_matches_serde() {
+        for grade in Grade::ALL {
+            let json = serde_json::to_string(&grade).unwrap();
+            assert_eq!(json, format!("\"{}\"", grade.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_unix_now_is_after_2024() {
+        // 2024-01-01T00:00:00Z
+        assert!(unix_now() > 1_704_067_200);
+    }
+
+    #[rstest]
+    #[case("sm2", SchedulerKind::Sm2)]
+    #[case("SM2", SchedulerKind::Sm2)]
+    #[case("fsrs", SchedulerKind::Fsrs)]
+    #[case(" Fsrs ", SchedulerKind::Fsrs)]
+    fn test_scheduler_kind_from_str(#[case] input: &str, #[case] expected: SchedulerKind) {
+        assert_eq!(input.parse::<SchedulerKind>().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_scheduler_kind_from_str_rejects_unknown() {
+        let err = "anki".parse::<SchedulerKind>().unwrap_err();
+        assert!(err.contains("anki"));
+    }
+
+    #[test]
+    fn test_scheduler_kind_display_matches_name() {
+        assert_eq!(SchedulerKind::Sm2.to_string(), "sm2");
+        assert_eq!(SchedulerKind::Fsrs.to_string(), "fsrs");
+    }
+
+    // ========== Property-Based Tests ==========
+
+    /// Property-based tests shared by every scheduler implementation.
+    ///
+    /// Verifies that scheduling maintains critical properties across randomly
+    /// generated review histories.
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        const NOW: i64 = 1_700_000_000;
+
+        /// Generates any scheduler implementation.
+        fn arb_kind() -> impl Strategy<Value = SchedulerKind> {
+            prop_oneof![Just(SchedulerKind::Sm2), Just(SchedulerKind::Fsrs)]
+        }
+
+        /// Generates any grade.
+        fn arb_grade() -> impl Strategy<Value = Grade> {
+            prop::sample::select(Grade::ALL.to_vec())
+        }
+
+        /// Generates grade sequences (review histories).
+        fn arb_history() -> impl Strategy<Value = Vec<(Grade, i64)>> {
+            // (grade, seconds elapsed since previous review)
+            prop::collection::vec((arb_grade(), 0i64..(400 * SECONDS_PER_DAY)), 0..12)
+        }
+
+        /// Replays a review history and returns the resulting state and time.
+        fn replay(scheduler: &dyn Scheduler, history: &[(Grade, i64)]) -> (ReviewState, i64) {
+            let mut now = NOW;
+            let mut state = scheduler.new_state("card", now);
+            for (grade, elapsed) in history {
+                now += elapsed;
+                state = scheduler.schedule(&state, *grade, now);
+            }
+            (state, now)
+        }
+
+        /// Tests that the next review is never scheduled in the past.
+        ///
+        /// Invariant: schedule(state, grade, now).due_at >= now.
+        #[test]
+        fn prop_due_date_not_in_past() {
+            proptest!(|(kind in arb_kind(), history in arb_history(), grade in arb_grade())| {
+                let scheduler = kind.build();
+                let (state, now) = replay(scheduler.as_ref(), &history);
+
+                let next = scheduler.schedule(&state, grade, now);
+
+                prop_assert!(next.due_at >= now);
+                prop_assert!(next.interval_days >= 0);
+                prop_assert_eq!(next.last_reviewed_at, now);
+            });
+        }
+
+        /// Tests that better grades never schedule a card sooner.
+        ///
+        /// Invariant: due(Again) < due(Hard) <= due(Good) < due(Easy).
+        #[test]
+        fn prop_grades_are_ordered() {
+            proptest!(|(kind in arb_kind(), history in arb_history(), elapsed in 0i64..(100 * SECONDS_PER_DAY))| {
+                let scheduler = kind.build();
+                let (state, now) = replay(scheduler.as_ref(), &history);
+                let now = now + elapsed;
+
+                let again = scheduler.schedule(&state, Grade::Again, now);
+                let hard = scheduler.schedule(&state, Grade::Hard, now);
+                let good = scheduler.schedule(&state, Grade::Good, now);
+                let easy = scheduler.schedule(&state, Grade::Easy, now);
+
+                prop_assert!(again.due_at < hard.due_at);
+                prop_assert!(hard.due_at <= good.due_at);
+                prop_assert!(good.due_at < easy.due_at);
+            });
+        }
+
+        /// Tests that failing a card always counts as a lapse once learned.
+        ///
+        /// Invariant: lapses increase by one on Again iff repetitions > 0.
+        #[test]
+        fn prop_again_counts_lapses() {
+            proptest!(|(kind in arb_kind(), history in arb_history())| {
+                let scheduler = kind.build();
+                let (state, now) = replay(scheduler.as_ref(), &history);
+
+                let next = scheduler.schedule(&state, Grade::Again, now);
+
+                let expected = state.lapses + i64::from(state.repetitions > 0);
+                prop_assert_eq!(next.lapses, expected);
+                prop_assert_eq!(next.repetitions, 0);
+            });
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// Rust guideline compliant 2025-01
+//! SM-2 scheduling algorithm.
+//!
+//! Variant of the SuperMemo-2 algorithm with four grades: failed cards are
+//! relearned after a short delay, successful cards see their interval grow
+//! by the card ease factor.
+
+use super::{Grade, RELEARN_DELAY_SECS, SECONDS_PER_DAY, Scheduler};
+use crate::db::models::ReviewState;
+
+/// Ease factor assigned to cards never reviewed.
+pub const INITIAL_EASE: f64 = 2.5;
+
+/// Lower bound of the ease factor.
+pub const MIN_EASE: f64 = 1.3;
+
+/// SM-2 scheduler.
+///
+/// - `Again` resets the card: due again in [`RELEARN_DELAY_SECS`], ease decreases,
+///   and a lapse is counted if the card had already been learned.
+/// - `Hard`, `Good` and `Easy` grow the interval, with `Hard <= Good < Easy`.
+///
+/// # Examples
+/// ```
+/// use rust_flashcards::scheduler::{Grade, Scheduler, sm2::Sm2};
+///
+/// let state = Sm2.new_state("my-card", 0);
+/// let next = Sm2.schedule(&state, Grade::Good, 0);
+/// assert_eq!(next.interval_days, 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sm2;
+
+impl Scheduler for Sm2 {
+    fn name(&self) -> &'static str {
+        "sm2"
+    }
+
+    fn schedule(&self, state: &ReviewState, grade: Grade, now: i64) -> ReviewState {
+        schedule(state, grade, now)
+    }
+}
+
+/// Computes the next SM-2 review state after grading a card at `now`.
+fn schedule(state: &ReviewState, grade: Grade, now: i64) -> ReviewState {
+    let mut next = state.clone();
+    next.last_reviewed_at = now;
+
+    if grade == Grade::Again {
+        if state.repetitions > 0 {
+            next.lapses += 1;
+        }
+        next.repetitions = 0;
+        next.interval_days = 0;
+        next.ease = (state.ease - 0.20).max(MIN_EASE);
+        next.due_at = now + RELEARN_DELAY_SECS;
+        return next;
+    }
+
+    let good_interval = match state.repetitions {
+        0 => 1,
+        1 => 6,
+        _ => (state.interval_days as f64 * state.ease).round() as i64,
+    }
+    .max(1);
+
+    let (interval_days, ease) = match grade {
+        Grade::Hard => {
+            let hard_interval = match state.repetitions {
+                0 => 1,
+                _ => ((state.interval_days as f64 * 1.2).round() as i64).max(state.interval_days + 1),
+            };
+            (hard_interval.min(good_interval), (state.ease - 0.15).max(MIN_EASE))
+        }
+        Grade::Good => (good_interval, state.ease),
+        Grade::Easy => {
+            let easy_interval = match state.repetitions {
+                0 => 4,
+                _ => (good_interval as f64 * 1.3).round() as i64,
+            };
+            (easy_interval.max(good_interval + 1), state.ease + 0.15)
+        }
+        Grade::Again => unreachable!("handled above"),
+    };
+
+    next.repetitions += 1;
+    next.interval_days = interval_days;
+    next.ease = ease;
+    next.due_at = now + interval_days * SECONDS_PER_DAY;
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn new_state(card_key: &str, now: i64) -> ReviewState {
+        Sm2.new_state(card_key, now)
+    }
+
+    /// Builds a learned card with the given interval and repetitions.
+    fn learned(interval_days: i64, repetitions: i64) -> ReviewState {
+        ReviewState {
+            interval_days,
+            repetitions,
+            ..new_state("card", NOW)
+        }
+    }
+
+    #[rstest]
+    #[case(Grade::Hard, 1)]
+    #[case(Grade::Good, 1)]
+    #[case(Grade::Easy, 4)]
+    fn test_first_review_intervals(#[case] grade: Grade, #[case] expected_days: i64) {
+        let next = schedule(&new_state("card", NOW), grade, NOW);
+
+        assert_eq!(next.interval_days, expected_days);
+        assert_eq!(next.repetitions, 1);
+        assert_eq!(next.due_at, NOW + expected_days * SECONDS_PER_DAY);
+    }
+
+    #[test]
+    fn test_second_good_review_is_six_days() {
+        let next = schedule(&learned(1, 1), Grade::Good, NOW);
+        assert_eq!(next.interval_days, 6);
+    }
+
+    #[test]
+    fn test_good_multiplies_interval_by_ease() {
+        let next = schedule(&learned(10, 3), Grade::Good, NOW);
+        assert_eq!(next.interval_days, 25);
+        assert_eq!(next.ease, INITIAL_EASE);
+    }
+
+    #[test]
+    fn test_again_relearns_soon_and_counts_lapse() {
+        let next = schedule(&learned(10, 3), Grade::Again, NOW);
+
+        assert_eq!(next.due_at, NOW + RELEARN_DELAY_SECS);
+        assert_eq!(next.interval_days, 0);
+        assert_eq!(next.repetitions, 0);
+        assert_eq!(next.lapses, 1);
+        assert!(next.ease < INITIAL_EASE);
+    }
+
+    #[test]
+    fn test_again_on_new_card_is_not_a_lapse() {
+        let next = schedule(&new_state("card", NOW), Grade::Again, NOW);
+        assert_eq!(next.lapses, 0);
+    }
+
+    #[test]
+    fn test_ease_never_below_minimum() {
+        let mut state = learned(10, 3);
+        for _ in 0..20 {
+            state = schedule(&state, Grade::Again, NOW);
+        }
+        assert_eq!(state.ease, MIN_EASE);
+    }
+
+    #[test]
+    fn test_failed_card_comes_back_before_known_card() {
+        let failed = schedule(&learned(10, 3), Grade::Again, NOW);
+        let known = schedule(&learned(10, 3), Grade::Good, NOW);
+        assert!(failed.due_at < known.due_at);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(2)]
+    #[case(5)]
+    fn test_grades_are_ordered(#[case] repetitions: i64) {
+        let state = learned(repetitions * 3, repetitions);
+
+        let hard = schedule(&state, Grade::Hard, NOW);
+        let good = schedule(&state, Grade::Good, NOW);
+        let easy = schedule(&state, Grade::Easy, NOW);
+
+        assert!(hard.due_at <= good.due_at);
+        assert!(good.due_at < easy.due_at);
+    }
+}
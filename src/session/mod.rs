@@ -1,14 +1,16 @@
 // Rust guideline compliant 2024-01
 use serde::{Deserialize, Serialize};
 
+use crate::db::models::{FilterCriteria, Subcategory, TagMatch};
+
 /// Session data for tracking user state across requests.
 ///
 /// Stores flashcard browsing state including seen cards, search/filter settings,
 /// and cached counts. Session persists filter preferences across page visits.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SessionData {
-    /// IDs of flashcards already shown in current session.
-    pub seen_ids: Vec<i64>,
+    /// Keys of flashcards already shown in current session.
+    pub seen_keys: Vec<String>,
     /// Legacy: IDs of searched cards (kept for compatibility).
     pub searched_ids: Vec<i64>,
     /// Legacy: search keywords (kept for compatibility).
@@ -21,39 +23,92 @@ pub struct SessionData {
     pub filter_keywords: Vec<String>,
     /// Selected categories (None = all categories).
     pub filter_categories: Option<Vec<String>>,
-    /// Selected subcategories (None = all subcategories).
-    pub filter_subcategories: Option<Vec<String>>,
+    /// Selected subcategories, with their category (None = all subcategories).
+    pub filter_subcategories: Option<Vec<Subcategory>>,
+    /// Selected tags (empty = no tag filter).
+    #[serde(default)]
+    pub filter_tags: Vec<String>,
+    /// Whether practiced cards need all selected tags or any of them.
+    #[serde(default)]
+    pub filter_tag_match: TagMatch,
     /// Whether to include image-only cards in practice.
     pub filter_include_images: bool,
+    /// Whether to type the answer of cards with an `Expected:` answer before the reveal.
+    #[serde(default)]
+    pub typed_answers: bool,
+    /// Multiple-choice quiz of the session, reset with the filters.
+    #[serde(default)]
+    pub quiz: QuizSession,
     /// Cached count of cards matching current filters.
     pub filtered_card_count: Option<i64>,
     /// Error message to display on landing page.
     pub error_message: Option<String>,
 }
 
+/// Multiple-choice quiz: cards asked so far and score.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct QuizSession {
+    /// Keys of the cards already asked, or skipped for lack of wrong options.
+    pub seen_keys: Vec<String>,
+    /// Number of questions answered.
+    pub answered: u32,
+    /// Number of questions answered correctly.
+    pub correct: u32,
+    /// Question shown and not answered yet.
+    pub pending: Option<QuizQuestion>,
+}
+
+/// Multiple-choice question asked from a card.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuizQuestion {
+    pub card_key: String,
+    /// Options (HTML), in display order.
+    pub options: Vec<String>,
+    /// Position of the card's answer among the options.
+    pub answer_index: usize,
+}
 
 impl Default for SessionData {
     /// Returns session with filter defaults.
     ///
     /// Filters default to include everything: empty keywords, all categories,
-    /// all subcategories, and all images.
+    /// all subcategories, no tag filter, and all images.
     fn default() -> Self {
         Self {
-            seen_ids: Vec::new(),
+            seen_keys: Vec::new(),
             searched_ids: Vec::new(),
             keywords: Vec::new(),
             nb_cards: None,
             filter_keywords: Vec::new(),
             filter_categories: None,
             filter_subcategories: None,
+            filter_tags: Vec::new(),
+            filter_tag_match: TagMatch::Any,
             filter_include_images: true,
+            typed_answers: false,
+            quiz: QuizSession::default(),
             filtered_card_count: None,
             error_message: None,
         }
     }
 }
 
+impl SessionData {
+    /// Returns the filter criteria saved from the landing form.
+    pub fn filter_criteria(&self) -> FilterCriteria {
+        FilterCriteria {
+            keywords: self.filter_keywords.clone(),
+            categories: self.filter_categories.clone(),
+            subcategories: self.filter_subcategories.clone(),
+            tags: self.filter_tags.clone(),
+            tag_match: self.filter_tag_match,
+            include_images: self.filter_include_images,
+        }
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)] // Tests tweak one field of the defaults at a time
 mod tests {
     use super::*;
     use serde_json;
@@ -63,7 +118,7 @@ mod tests {
         let session = SessionData::default();
 
         // Verify all vectors start empty
-        assert!(session.seen_ids.is_empty());
+        assert!(session.seen_keys.is_empty());
         assert!(session.searched_ids.is_empty());
         assert!(session.keywords.is_empty());
         assert!(session.filter_keywords.is_empty());
@@ -82,10 +137,12 @@ mod tests {
     #[test]
     fn test_serialization_roundtrip() {
         let mut session = SessionData::default();
-        session.seen_ids = vec![1, 2, 3];
+        session.seen_keys = vec!["a".to_string(), "b".to_string(), "c".to_string()];
         session.filter_keywords = vec!["rust".to_string(), "async".to_string()];
         session.filter_categories = Some(vec!["Programming".to_string()]);
-        session.filter_subcategories = Some(vec!["Rust".to_string()]);
+        session.filter_subcategories = Some(vec![Subcategory::new("Programming", "Rust")]);
+        session.filter_tags = vec!["async".to_string()];
+        session.filter_tag_match = TagMatch::All;
         session.filter_include_images = false;
         session.filtered_card_count = Some(42);
         session.error_message = Some("Test error".to_string());
@@ -97,7 +154,7 @@ mod tests {
         let deserialized: SessionData = serde_json::from_str(&json).unwrap();
 
         // Verify all fields match
-        assert_eq!(deserialized.seen_ids, vec![1, 2, 3]);
+        assert_eq!(deserialized.seen_keys, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
         assert_eq!(
             deserialized.filter_keywords,
             vec!["rust".to_string(), "async".to_string()]
@@ -108,8 +165,10 @@ mod tests {
         );
         assert_eq!(
             deserialized.filter_subcategories,
-            Some(vec!["Rust".to_string()])
+            Some(vec![Subcategory::new("Programming", "Rust")])
         );
+        assert_eq!(deserialized.filter_tags, vec!["async".to_string()]);
+        assert_eq!(deserialized.filter_tag_match, TagMatch::All);
         assert!(!deserialized.filter_include_images);
         assert_eq!(deserialized.filtered_card_count, Some(42));
         assert_eq!(deserialized.error_message, Some("Test error".to_string()));
@@ -118,13 +177,13 @@ mod tests {
     #[test]
     fn test_clone_trait() {
         let mut session = SessionData::default();
-        session.seen_ids = vec![1, 2, 3];
+        session.seen_keys = vec!["a".to_string(), "b".to_string(), "c".to_string()];
         session.filter_keywords = vec!["test".to_string()];
 
         let cloned = session.clone();
 
         // Verify clone has same values
-        assert_eq!(cloned.seen_ids, vec![1, 2, 3]);
+        assert_eq!(cloned.seen_keys, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
         assert_eq!(cloned.filter_keywords, vec!["test".to_string()]);
     }
 
@@ -135,7 +194,7 @@ mod tests {
         // Simulate user setting filters
         session.filter_keywords = vec!["machine learning".to_string()];
         session.filter_categories = Some(vec!["Science".to_string()]);
-        session.filter_subcategories = Some(vec!["AI".to_string()]);
+        session.filter_subcategories = Some(vec![Subcategory::new("Science", "AI")]);
         session.filter_include_images = false;
 
         // Simulate caching filter count
@@ -150,26 +209,26 @@ mod tests {
             session.filter_categories,
             Some(vec!["Science".to_string()])
         );
-        assert_eq!(session.filter_subcategories, Some(vec!["AI".to_string()]));
+        assert_eq!(session.filter_subcategories, Some(vec![Subcategory::new("Science", "AI")]));
         assert!(!session.filter_include_images);
         assert_eq!(session.filtered_card_count, Some(15));
     }
 
     #[test]
-    fn test_seen_ids_tracking() {
+    fn test_seen_keys_tracking() {
         let mut session = SessionData::default();
 
         // Simulate viewing flashcards
-        session.seen_ids.push(1);
-        session.seen_ids.push(2);
-        session.seen_ids.push(3);
+        session.seen_keys.push("a".to_string());
+        session.seen_keys.push("b".to_string());
+        session.seen_keys.push("c".to_string());
 
-        assert_eq!(session.seen_ids.len(), 3);
-        assert_eq!(session.seen_ids, vec![1, 2, 3]);
+        assert_eq!(session.seen_keys.len(), 3);
+        assert_eq!(session.seen_keys, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
 
         // Simulate reset
-        session.seen_ids.clear();
-        assert!(session.seen_ids.is_empty());
+        session.seen_keys.clear();
+        assert!(session.seen_keys.is_empty());
     }
 
     #[test]
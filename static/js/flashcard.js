@@ -1,48 +1,169 @@
-// Flashcard hide/reveal logic
+// Flashcard hide/reveal and grading logic
 (function() {
     const answerDiv = document.getElementById('answer-content');
     const actionBtn = document.getElementById('action-btn');
     const backBtn = document.getElementById('back-btn');
-    const isPngOnly = actionBtn.dataset.pngOnly === 'true';
+    const copyLinkBtn = document.getElementById('copy-link-btn');
+    const hintBtn = document.getElementById('hint-btn');
+    const hintDivs = document.querySelectorAll('.card-hint');
+    const hintsUsedInput = document.getElementById('hints-used');
+    const gradeForm = document.getElementById('grade-form');
+    const timeToRevealInput = document.getElementById('time-to-reveal');
+    const timeOnCardInput = document.getElementById('time-on-card');
+    const typedForm = document.getElementById('typed-answer-form');
+    const typedInput = document.getElementById('typed-answer');
+    const answerCheckDiv = document.getElementById('answer-check');
+    const shownAt = Date.now();
+    const answerOnly = actionBtn.dataset.answerOnly === 'true';
 
-    let isRevealed = isPngOnly;
+    let isRevealed = answerOnly;
+    let hintsUsed = 0;
+    let isChecking = false;
+    // Grade of ENTER once revealed: Good, or the one suggested for a typed answer
+    let suggestedGrade = 'good';
+
+    // Grade keys: 1 = Again, 2 = Hard, 3 = Good, 4 = Easy
+    const gradeKeys = { '1': 'again', '2': 'hard', '3': 'good', '4': 'easy' };
 
     // Initialize UI state
-    if (!isPngOnly) {
+    if (answerOnly) {
+        gradeForm.style.display = 'block';
+    } else {
         answerDiv.style.display = 'none';
         actionBtn.textContent = 'Show Answer';
     }
+    hintDivs.forEach(function(div) { div.style.display = 'none'; });
+    hintsUsedInput.disabled = false;
 
-    function handleAction() {
-        if (!isRevealed) {
-            // First action: reveal answer
-            answerDiv.style.display = 'block';
-            actionBtn.textContent = 'Next';
-            isRevealed = true;
-        } else {
-            // Second action: navigate
-            window.location.href = actionBtn.href;
+    // Fills a timing field so it gets submitted with the grade
+    function setTiming(input, ms) {
+        input.value = Math.round(ms);
+        input.disabled = false;
+    }
+
+    // Shows the next hint, until the answer is revealed
+    function showHint() {
+        if (isRevealed || hintsUsed >= hintDivs.length) {
+            return;
+        }
+        hintDivs[hintsUsed].style.display = 'block';
+        hintsUsed += 1;
+        hintsUsedInput.value = hintsUsed;
+        if (hintsUsed === hintDivs.length) {
+            hintBtn.style.display = 'none';
         }
     }
 
-    // Button click
+    function reveal() {
+        setTiming(timeToRevealInput, Date.now() - shownAt);
+        answerDiv.style.display = 'block';
+        gradeForm.style.display = 'block';
+        actionBtn.textContent = 'Skip';
+        if (hintBtn) {
+            hintBtn.style.display = 'none';
+        }
+        isRevealed = true;
+    }
+
+    // Highlights the grade suggested for a typed answer, and makes it the ENTER grade
+    function suggestGrade(grade) {
+        const button = gradeForm.querySelector('button[value="' + grade + '"]');
+        if (button) {
+            button.classList.add('suggested-grade');
+            suggestedGrade = grade;
+        }
+    }
+
+    // Checks the typed answer (if any), shows the feedback, then reveals the answer
+    function checkAndReveal() {
+        if (isChecking) {
+            return;
+        }
+        if (!typedForm || typedInput.value.trim() === '') {
+            reveal();
+            return;
+        }
+        isChecking = true;
+        typedInput.disabled = true;
+        const body = new URLSearchParams({ card_key: typedForm.dataset.cardKey, answer: typedInput.value });
+        fetch('/check_answer', { method: 'POST', body: body })
+            .then(function(response) { return response.ok ? response.text() : ''; })
+            .then(function(html) {
+                answerCheckDiv.innerHTML = html;
+                const check = answerCheckDiv.querySelector('.answer-check');
+                if (check) {
+                    suggestGrade(check.dataset.suggestedGrade);
+                }
+            })
+            .catch(function() {}) // Feedback unavailable: reveal anyway
+            .finally(reveal);
+    }
+
+    gradeForm.addEventListener('submit', function() {
+        setTiming(timeOnCardInput, Date.now() - shownAt);
+    });
+
+    function submitGrade(grade) {
+        gradeForm.querySelector('button[value="' + grade + '"]').click();
+    }
+
+    // Button click: first click reveals, then it skips to next card without grading
     actionBtn.addEventListener('click', function(e) {
         if (!isRevealed) {
             e.preventDefault();
-            handleAction();
+            checkAndReveal();
+        }
+    });
+
+    // ENTER in the typed answer checks it
+    if (typedForm) {
+        typedForm.addEventListener('submit', function(e) {
+            e.preventDefault();
+            if (!isRevealed) {
+                checkAndReveal();
+            }
+        });
+    }
+
+    if (hintBtn) {
+        hintBtn.addEventListener('click', showHint);
+    }
+
+    // Copies the absolute permalink of the card (to share it)
+    copyLinkBtn.addEventListener('click', function() {
+        const url = new URL(copyLinkBtn.dataset.permalink, window.location.origin).href;
+        // Clipboard unavailable (e.g. plain HTTP on another host): let the user copy it
+        const askToCopy = function() { window.prompt('Card link', url); };
+        if (!navigator.clipboard) {
+            askToCopy();
+            return;
         }
+        navigator.clipboard.writeText(url).then(function() {
+            copyLinkBtn.textContent = 'Link copied';
+        }, askToCopy);
     });
 
-    // ENTER key - only intercept if back button doesn't have focus
+    // ENTER reveals the answer, then grades the card as Good (or as suggested); H shows the next hint
     document.addEventListener('keydown', function(e) {
+        // Let browser follow focused links and buttons, and the typed answer take keys
+        if (document.activeElement === typedInput || document.activeElement === backBtn
+            || document.activeElement === copyLinkBtn || (hintBtn && document.activeElement === hintBtn) || gradeForm.contains(document.activeElement)) {
+            return;
+        }
+
         if (e.key === 'Enter') {
-            // If back button has focus, let browser follow the link
-            if (document.activeElement === backBtn) {
-                return;
+            e.preventDefault();
+            if (!isRevealed) {
+                checkAndReveal();
+            } else {
+                submitGrade(suggestedGrade);
             }
-
+        } else if (e.key === 'h' || e.key === 'H') {
+            e.preventDefault();
+            showHint();
+        } else if (isRevealed && gradeKeys[e.key]) {
             e.preventDefault();
-            handleAction();
+            submitGrade(gradeKeys[e.key]);
         }
     });
 })();
@@ -0,0 +1,3 @@
+// Live reload (--watch --live-reload): reload the page when the server reports changed cards
+const liveReload = new EventSource("/events");
+liveReload.addEventListener("reload", () => window.location.reload());
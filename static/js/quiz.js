@@ -0,0 +1,18 @@
+// Quiz keyboard shortcuts: keys 1-9 pick an option, ENTER goes to the next question
+(function() {
+    const quizForm = document.getElementById('quiz-form');
+    const nextBtn = document.getElementById('next-btn');
+
+    document.addEventListener('keydown', function(e) {
+        if (quizForm) {
+            const option = quizForm.querySelector('button[name="choice"][value="' + (Number(e.key) - 1) + '"]');
+            if (option) {
+                e.preventDefault();
+                option.click();
+            }
+        } else if (nextBtn && e.key === 'Enter' && document.activeElement !== nextBtn) {
+            e.preventDefault();
+            nextBtn.click();
+        }
+    });
+})();
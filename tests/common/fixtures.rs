@@ -99,6 +99,9 @@ pub fn sample_flashcards() -> Vec<TestCard> {
     ]
 }
 
+/// Filter combination: (description, keywords, categories, subcategories, include_images, expected_count).
+pub type FilterCase = (&'static str, Vec<String>, Option<Vec<String>>, Option<Vec<String>>, bool, i64);
+
 /// Sample filter criteria combinations for parametrized tests.
 ///
 /// Returns vector of tuples: (description, keywords, categories, subcategories, include_images, expected_count)
@@ -111,14 +114,7 @@ pub fn sample_flashcards() -> Vec<TestCard> {
 ///     // Test each filter combination
 /// }
 /// ```
-pub fn sample_filter_criteria() -> Vec<(
-    &'static str,
-    Vec<String>,
-    Option<Vec<String>>,
-    Option<Vec<String>>,
-    bool,
-    i64,
-)> {
+pub fn sample_filter_criteria() -> Vec<FilterCase> {
     vec![
         // (description, keywords, categories, subcategories, include_images, expected_count)
         ("No filters - all cards", vec![], None, None, true, 10),
@@ -44,8 +44,8 @@ pub fn create_test_pool() -> Result<(DbPool, TempDir)> {
 
 /// Initializes test database schema.
 ///
-/// Creates flashcards table and flashcards_fts virtual table
-/// matching production schema.
+/// Creates flashcards table, flashcards_fts virtual table, content_files, tags,
+/// card_tags, card_hints, card_distractors, reviews and review_log tables matching production schema.
 ///
 /// # Errors
 /// Returns error if table creation fails.
@@ -54,24 +54,116 @@ pub fn init_test_schema(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS flashcards (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_key TEXT,
+            source_path TEXT,
             category TEXT,
             subcategory TEXT,
+            difficulty TEXT,
+            kind TEXT,
+            sibling_key TEXT,
+            expected_answer TEXT,
             question_html TEXT NOT NULL,
-            answer_html TEXT NOT NULL
+            answer_html TEXT NOT NULL,
+            question_text TEXT,
+            answer_text TEXT,
+            thumbnail_html TEXT
         )",
         [],
     )?;
 
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_flashcards_card_key ON flashcards(card_key)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_flashcards_source_path ON flashcards(source_path)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_flashcards_sibling_key ON flashcards(sibling_key)", [])?;
+
     // FTS5 virtual table for full-text search
     conn.execute(
         "CREATE VIRTUAL TABLE IF NOT EXISTS flashcards_fts USING fts5(
             id UNINDEXED,
             category,
             subcategory,
-            question_html,
-            answer_html,
-            content='flashcards',
-            content_rowid='id'
+            question,
+            answer,
+            tokenize = 'unicode61 remove_diacritics 2'
+        )",
+        [],
+    )?;
+
+    // Content file tracking table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS content_files (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            hash TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Tag tables
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS card_tags (
+            card_key TEXT NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (card_key, tag_id)
+        )",
+        [],
+    )?;
+
+    // Hint table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS card_hints (
+            card_key TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            hint_html TEXT NOT NULL,
+            PRIMARY KEY (card_key, position)
+        )",
+        [],
+    )?;
+
+    // Distractor table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS card_distractors (
+            card_key TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            distractor_html TEXT NOT NULL,
+            PRIMARY KEY (card_key, position)
+        )",
+        [],
+    )?;
+
+    // Spaced repetition state table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reviews (
+            card_key TEXT PRIMARY KEY,
+            ease REAL NOT NULL,
+            interval_days INTEGER NOT NULL,
+            repetitions INTEGER NOT NULL,
+            lapses INTEGER NOT NULL,
+            due_at INTEGER NOT NULL,
+            last_reviewed_at INTEGER NOT NULL,
+            stability REAL NOT NULL DEFAULT 0,
+            difficulty REAL NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+
+    // Review history table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS review_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_key TEXT NOT NULL,
+            reviewed_at INTEGER NOT NULL,
+            grade TEXT NOT NULL,
+            time_to_reveal_ms INTEGER,
+            time_on_card_ms INTEGER,
+            hints_used INTEGER
         )",
         [],
     )?;
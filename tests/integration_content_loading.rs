@@ -5,6 +5,7 @@ mod common;
 
 use rust_flashcards::content::{images, markdown};
 use rust_flashcards::db::queries;
+use rust_flashcards::scheduler::{Grade, Scheduler, sm2::Sm2};
 use std::fs;
 use tempfile::TempDir;
 
@@ -337,6 +338,62 @@ async fn test_content_reload() {
     assert_eq!(category, Some("Version".to_string()));
 }
 
+/// Tests review history stays attached to a card across reloads.
+///
+/// Fixing a typo in an answer must not reset the card's review state.
+#[tokio::test]
+async fn test_review_state_survives_reload() {
+    let (pool, _temp_dir) = common::create_test_pool().unwrap();
+
+    let md_dir = TempDir::new().unwrap();
+    let md_path = md_dir.path().to_str().unwrap();
+    let card_key = |pool: &common::DbPool| -> String {
+        pool.get()
+            .unwrap()
+            .query_row("SELECT card_key FROM flashcards", [], |row| row.get(0))
+            .unwrap()
+    };
+
+    fs::write(md_dir.path().join("deck.md"), "Question : Cat - Sub - Q?\nAnswer : Typo").unwrap();
+    markdown::load_markdown(&pool, md_path).unwrap();
+
+    let key = card_key(&pool);
+    let state = Sm2.schedule(&Sm2.new_state(&key, 0), Grade::Good, 0);
+    queries::upsert_review_state(&pool, &state).unwrap();
+
+    fs::write(md_dir.path().join("deck.md"), "Question : Cat - Sub - Q?\nAnswer : Fixed").unwrap();
+    markdown::load_markdown(&pool, md_path).unwrap();
+
+    assert_eq!(card_key(&pool), key);
+    assert_eq!(queries::get_review_state(&pool, &key).unwrap(), Some(state));
+}
+
+/// Tests incremental reload: changed files re-parsed, removed files dropped, FTS in sync.
+#[tokio::test]
+async fn test_incremental_reload_keeps_fts_in_sync() {
+    let (pool, _temp_dir) = common::create_test_pool().unwrap();
+
+    let md_dir = TempDir::new().unwrap();
+    let md_path = md_dir.path().to_str().unwrap();
+    let count = |sql: &str| -> i64 { pool.get().unwrap().query_row(sql, [], |row| row.get(0)).unwrap() };
+
+    fs::write(md_dir.path().join("keep.md"), "Question : A - B - Kept?\nAnswer : Kept").unwrap();
+    fs::write(md_dir.path().join("gone.md"), "Question : A - B - Obsolete?\nAnswer : Obsolete").unwrap();
+    markdown::load_markdown(&pool, md_path).unwrap();
+    queries::populate_fts_table(&pool).unwrap();
+
+    fs::remove_file(md_dir.path().join("gone.md")).unwrap();
+    fs::write(md_dir.path().join("new.md"), "Question : A - B - Fresh?\nAnswer : Fresh").unwrap();
+    let report = markdown::load_markdown(&pool, md_path).unwrap();
+    queries::populate_fts_table(&pool).unwrap();
+
+    assert_eq!((report.added, report.removed, report.unchanged), (1, 1, 1));
+    assert_eq!(count("SELECT COUNT(*) FROM flashcards"), 2);
+    assert_eq!(count("SELECT COUNT(*) FROM flashcards_fts"), 2);
+    assert_eq!(count("SELECT COUNT(*) FROM flashcards_fts WHERE flashcards_fts MATCH 'obsolete'"), 0);
+    assert_eq!(count("SELECT COUNT(*) FROM flashcards_fts WHERE flashcards_fts MATCH 'fresh'"), 1);
+}
+
 /// Tests mixed content types (markdown + images).
 #[tokio::test]
 async fn test_mixed_content_types() {
@@ -5,27 +5,51 @@ mod common;
 
 use axum::{routing::{get, post}, Router};
 use axum_test::TestServer;
-use rust_flashcards::{config::Config, content::markdown, db::queries, routes::{self, AppState}};
+use rust_flashcards::{config::Config, content::{images, markdown}, db::queries, routes::{self, AppState}, scheduler::SchedulerKind};
 use tower_http::services::ServeDir;
 use tower_sessions::{MemoryStore, SessionManagerLayer};
 
 /// Creates test server with minimal content for route testing.
 async fn setup_test_server() -> TestServer {
-    let (pool, _temp_dir) = common::create_test_pool().unwrap();
+    setup_test_server_with_pool().await.0
+}
 
-    // Load minimal test content
-    let md_dir = tempfile::TempDir::new().unwrap();
-    std::fs::create_dir_all(&md_dir).unwrap();
+/// Creates test server and returns it with its database pool for state checks.
+///
+/// The returned `TempDir` owns the database file: keep it alive while writing,
+/// SQLite rejects writes to a deleted database file.
+async fn setup_test_server_with_pool() -> (TestServer, common::DbPool, tempfile::TempDir) {
+    setup_test_server_with_scheduler(SchedulerKind::Sm2).await
+}
 
-    let content = r#"Question : Math - Algebra - What is 2+2?
+/// Creates test server using the given scheduling algorithm.
+async fn setup_test_server_with_scheduler(scheduler: SchedulerKind) -> (TestServer, common::DbPool, tempfile::TempDir) {
+    setup_test_server_with_content(TEST_CONTENT, scheduler).await
+}
+
+/// Minimal test content: three cards in two categories.
+const TEST_CONTENT: &str = r#"Question : Math - Algebra - What is 2+2?
+Tags: arithmetic, basics
 Answer : 4
 
 Question : Math - Geometry - What is a triangle?
+Tags: shapes, basics
 Answer : A polygon with three sides
 
 Question : Science - Physics - What is gravity?
 Answer : A fundamental force"#;
 
+/// Creates test server loading `content` as its only markdown file.
+async fn setup_test_server_with_content(
+    content: &str,
+    scheduler: SchedulerKind,
+) -> (TestServer, common::DbPool, tempfile::TempDir) {
+    let (pool, temp_dir) = common::create_test_pool().unwrap();
+
+    // Load test content
+    let md_dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir_all(&md_dir).unwrap();
+
     std::fs::write(md_dir.path().join("test.md"), content).unwrap();
     markdown::load_markdown(&pool, md_dir.path().to_str().unwrap()).unwrap();
     queries::populate_fts_table(&pool).unwrap();
@@ -38,9 +62,11 @@ Answer : A fundamental force"#;
         deck_display_name: "Test Deck".to_string(),
         md_path: "./static/test/md".to_string(),
         img_path: "./static/test/img".to_string(),
+        scheduler,
+        fts_tokenizer: Default::default(),
     };
 
-    let state = AppState { pool, config };
+    let state = AppState { pool: pool.clone(), config, live_reload: None };
 
     // Create session layer
     let session_store = MemoryStore::default();
@@ -51,12 +77,22 @@ Answer : A fundamental force"#;
         .route("/", get(routes::landing))
         .route("/apply_filters", post(routes::apply_filters))
         .route("/practice", get(routes::practice))
+        .route("/card/:card_key", get(routes::card))
+        .route("/grade", post(routes::grade))
+        .route("/check_answer", post(routes::check_answer))
+        .route("/quiz", get(routes::quiz))
+        .route("/quiz_answer", post(routes::quiz_answer))
+        .route("/quiz_results", get(routes::quiz_results))
+        .route("/search", get(routes::search))
+        .route("/browse", get(routes::browse))
+        .route("/stats", get(routes::stats))
         .route("/reset_session", get(routes::reset_session))
+        .route("/events", get(routes::events))
         .nest_service("/static", ServeDir::new("./static"))
         .layer(session_layer)
         .with_state(state);
 
-    TestServer::new(app).unwrap()
+    (TestServer::new(app).unwrap(), pool, temp_dir)
 }
 
 /// Tests landing page renders successfully.
@@ -166,6 +202,37 @@ async fn test_practice_avoids_seen_cards() {
     assert!(seen_questions.len() >= 3);
 }
 
+/// Tests a reversed card and its sibling are never practiced in a row.
+#[tokio::test]
+async fn test_practice_never_shows_siblings_in_a_row() {
+    let content = "Question : Rust - Types - What does `Box<T>` do?\nID: box\nReverse: true\nAnswer : Heap-allocated owned pointer\n\n\
+                   Question : Rust - Types - What does `Rc<T>` do?\nID: rc\nAnswer : Reference-counted pointer";
+    let (mut server, _pool, _temp_dir) = setup_test_server_with_content(content, SchedulerKind::Sm2).await;
+    server.do_save_cookies();
+    server
+        .post("/apply_filters")
+        .form(&[("all_categories", "on"), ("all_subcategories", "on")])
+        .await;
+
+    let mut keys = Vec::new();
+    for _ in 0..12 {
+        let text = server.get("/practice").await.text();
+        keys.push(extract_card_key(&text).expect("practice page shows a card"));
+    }
+
+    for pair in keys.windows(2) {
+        let siblings = pair[0].trim_end_matches(".reverse") == pair[1].trim_end_matches(".reverse");
+        assert!(!siblings, "siblings shown in a row: {:?}", keys);
+    }
+}
+
+/// Helper to extract the card key of the grading form from HTML.
+fn extract_card_key(html: &str) -> Option<String> {
+    let start = html.find(r#"name="card_key" value=""#)? + r#"name="card_key" value=""#.len();
+    let end = html[start..].find('"')?;
+    Some(html[start..start + end].to_string())
+}
+
 /// Helper to extract question text from HTML.
 fn extract_question(html: &str) -> Option<String> {
     // Simplified extraction - look for common question patterns
@@ -217,7 +284,7 @@ async fn test_session_persistence() {
         .form(&[
             ("keywords", "math"),
             ("categories", "Math"),
-            ("subcategories", "Algebra"),
+            ("subcategories", "Math/Algebra"),
             ("all_images", "on"),
         ])
         .await;
@@ -242,7 +309,7 @@ async fn test_reset_session() {
         .form(&[
             ("keywords", "gravity"),
             ("categories", "Science"),
-            ("subcategories", "Physics"),
+            ("subcategories", "Science/Physics"),
             ("all_images", "on"),
         ])
         .await;
@@ -273,8 +340,8 @@ async fn test_filtered_card_count() {
         .post("/apply_filters")
         .form(&[
             ("categories", "Math"),
-            ("subcategories", "Algebra"),
-            ("subcategories", "Geometry"),
+            ("subcategories", "Math/Algebra"),
+            ("subcategories", "Math/Geometry"),
             ("all_images", "on"),
         ])
         .await;
@@ -288,6 +355,31 @@ async fn test_filtered_card_count() {
     assert!(text.contains("2") || text.contains("Math"));
 }
 
+/// Tests subcategories are selected together with their category.
+#[tokio::test]
+async fn test_subcategory_filter_uses_category_pairs() {
+    let mut server = setup_test_server().await;
+    // Filters are kept in the session
+    server.do_save_cookies();
+
+    let landing = server.get("/").await.text();
+    assert!(landing.contains(r#"value="Math/Geometry""#));
+
+    server
+        .post("/apply_filters")
+        .form(&[
+            ("categories", "Math"),
+            ("subcategories", "Math/Geometry"),
+            ("all_images", "on"),
+        ])
+        .await;
+
+    for _ in 0..3 {
+        let text = server.get("/practice").await.text();
+        assert!(text.contains("What is a triangle?"));
+    }
+}
+
 /// Tests keyword filtering.
 #[tokio::test]
 async fn test_keyword_filtering() {
@@ -314,11 +406,104 @@ async fn test_keyword_filtering() {
     // We just verify the page renders successfully with keyword filters applied
 }
 
-/// Tests empty categories (images-only mode).
+/// Tests search syntax (quoted phrase, exclusion) in the keyword filter.
 #[tokio::test]
-async fn test_images_only_mode() {
+async fn test_keyword_filtering_phrase_and_exclusion() {
+    let mut server = setup_test_server().await;
+    // Filters are kept in the session
+    server.do_save_cookies();
+
+    server
+        .post("/apply_filters")
+        .form(&[
+            ("keywords", r#""what is" -triangle -gravity"#),
+            ("all_categories", "on"),
+            ("all_subcategories", "on"),
+            ("all_images", "on"),
+        ])
+        .await;
+
+    for _ in 0..3 {
+        let text = server.get("/practice").await.text();
+        assert!(text.contains("What is 2+2?"));
+    }
+}
+
+/// Tests that a malformed search query is reported on the landing page.
+#[tokio::test]
+async fn test_invalid_search_shows_error() {
+    let mut server = setup_test_server().await;
+    // The error message is kept in the session
+    server.do_save_cookies();
+
+    let response = server
+        .post("/apply_filters")
+        .form(&[
+            ("keywords", r#"rust "train test"#),
+            ("all_categories", "on"),
+            ("all_subcategories", "on"),
+            ("all_images", "on"),
+        ])
+        .await;
+    assert_eq!(response.header("location"), "/");
+
+    let text = server.get("/").await.text();
+    assert!(text.contains("Invalid search: missing closing quote"));
+}
+
+/// Tests tag facets on the landing page.
+#[tokio::test]
+async fn test_landing_shows_tag_facets() {
     let server = setup_test_server().await;
 
+    let text = server.get("/").await.text();
+
+    assert!(text.contains(r#"name="tags" value="basics""#));
+    assert!(text.contains("basics <small"));
+    assert!(text.contains("(2)"));
+}
+
+/// Tests practice restricted to cards having all selected tags.
+#[tokio::test]
+async fn test_tag_filtering_all_tags() {
+    let mut server = setup_test_server().await;
+    // Filters are kept in the session
+    server.do_save_cookies();
+
+    server
+        .post("/apply_filters")
+        .form(&[
+            ("all_categories", "on"),
+            ("all_subcategories", "on"),
+            ("tags", "basics"),
+            ("tags", "shapes"),
+            ("tag_match", "all"),
+            ("all_images", "on"),
+        ])
+        .await;
+
+    for _ in 0..3 {
+        let text = server.get("/practice").await.text();
+        assert!(text.contains("What is a triangle?"));
+        assert!(text.contains(r#"<span class="badge badge-secondary ml-1">shapes</span>"#));
+    }
+}
+
+/// Tests empty categories (images-only mode) practice image cards, even categorized ones.
+#[tokio::test]
+async fn test_images_only_mode() {
+    let content = "Question : Uncategorized question?\nAnswer : Not an image";
+    let (mut server, pool, _temp_dir) = setup_test_server_with_content(content, SchedulerKind::Sm2).await;
+    server.do_save_cookies();
+
+    // Image categorized by its directories
+    let deck_dir = tempfile::TempDir::new().unwrap();
+    let img_dir = deck_dir.path().join("static/deck/img");
+    std::fs::create_dir_all(img_dir.join("maths/probability")).unwrap();
+    std::fs::write(img_dir.join("maths/probability/x.webp"), b"fake webp data").unwrap();
+    images::load_images(&pool, img_dir.to_str().unwrap()).unwrap();
+    queries::populate_fts_table(&pool).unwrap();
+
     // Submit empty categories (images-only mode)
     let response = server
         .post("/apply_filters")
@@ -334,4 +519,439 @@ async fn test_images_only_mode() {
 
     let location = response.header("location");
     assert_eq!(location, "/practice");
+
+    // Only the image is practiced, never the uncategorized markdown card
+    for _ in 0..3 {
+        let text = server.get("/practice").await.text();
+        assert!(text.contains("/static/deck/img/maths/probability/x.webp"));
+        assert!(!text.contains("Not an image"));
+    }
+
+    let text = server.get("/browse").await.text();
+    assert!(text.contains("x.webp"));
+    assert!(!text.contains("Not an image"));
+}
+
+/// Returns the keys of the loaded flashcards, in load order.
+fn card_keys(pool: &common::DbPool) -> Vec<String> {
+    let conn = pool.get().unwrap();
+    let mut stmt = conn.prepare("SELECT card_key FROM flashcards ORDER BY id").unwrap();
+    stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+}
+
+/// Tests grading a card stores its review state and redirects to practice.
+#[tokio::test]
+async fn test_grade_schedules_card() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+    let keys = card_keys(&pool);
+
+    let response = server
+        .post("/grade")
+        .form(&[("card_key", keys[0].as_str()), ("grade", "good")])
+        .await;
+
+    assert_eq!(response.status_code(), 303);
+    assert_eq!(response.header("location"), "/practice");
+
+    let state = queries::get_review_state(&pool, &keys[0]).unwrap().unwrap();
+    assert_eq!(state.interval_days, 1);
+    assert_eq!(state.repetitions, 1);
+}
+
+/// Tests a failed card comes back sooner than a known card.
+#[tokio::test]
+async fn test_grade_again_due_before_easy() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+    let keys = card_keys(&pool);
+
+    server.post("/grade").form(&[("card_key", keys[0].as_str()), ("grade", "again")]).await;
+    server.post("/grade").form(&[("card_key", keys[1].as_str()), ("grade", "easy")]).await;
+
+    let failed = queries::get_review_state(&pool, &keys[0]).unwrap().unwrap();
+    let known = queries::get_review_state(&pool, &keys[1]).unwrap().unwrap();
+    assert!(failed.due_at < known.due_at);
+}
+
+/// Tests invalid grade values are rejected.
+#[tokio::test]
+async fn test_grade_rejects_unknown_grade() {
+    let server = setup_test_server().await;
+
+    let response = server
+        .post("/grade")
+        .form(&[("card_key", "any"), ("grade", "perfect")])
+        .expect_failure()
+        .await;
+
+    assert_eq!(response.status_code(), 422);
+}
+
+/// Tests grading a card that does not exist is rejected without recording anything.
+#[tokio::test]
+async fn test_grade_rejects_unknown_card() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+
+    let response = server
+        .post("/grade")
+        .form(&[("card_key", "unknown"), ("grade", "good")])
+        .expect_failure()
+        .await;
+
+    assert_eq!(response.status_code(), 404);
+    assert!(queries::get_review_state(&pool, "unknown").unwrap().is_none());
+    let log_count: i64 = pool
+        .get()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM review_log", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(log_count, 0);
+}
+
+/// Tests grading uses the configured scheduler.
+#[tokio::test]
+async fn test_grade_uses_fsrs_scheduler() {
+    let (server, pool, _temp_dir) = setup_test_server_with_scheduler(SchedulerKind::Fsrs).await;
+    let keys = card_keys(&pool);
+
+    server.post("/grade").form(&[("card_key", keys[0].as_str()), ("grade", "good")]).await;
+
+    let state = queries::get_review_state(&pool, &keys[0]).unwrap().unwrap();
+    // FSRS schedules a first "good" 4 days out (SM-2: 1 day) and tracks memory stability
+    assert_eq!(state.interval_days, 4);
+    assert!(state.stability > 0.0);
+}
+
+/// Tests grading appends the review and its timings to the review log.
+#[tokio::test]
+async fn test_grade_records_review_log() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+    let keys = card_keys(&pool);
+
+    server
+        .post("/grade")
+        .form(&[
+            ("card_key", keys[1].as_str()),
+            ("grade", "hard"),
+            ("time_to_reveal_ms", "1200"),
+            ("time_on_card_ms", "4500"),
+            ("hints_used", "2"),
+        ])
+        .await;
+    // Timings and hint count are optional (no JavaScript)
+    server.post("/grade").form(&[("card_key", keys[1].as_str()), ("grade", "good")]).await;
+
+    let conn = pool.get().unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT grade, time_to_reveal_ms, time_on_card_ms, hints_used FROM review_log WHERE card_key = ?1 ORDER BY id",
+        )
+        .unwrap();
+    // Grade, time to reveal, time on card, hints used
+    type LogRow = (String, Option<i64>, Option<i64>, Option<i64>);
+    let rows: Vec<LogRow> = stmt
+        .query_map([&keys[1]], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(
+        rows,
+        vec![("hard".to_string(), Some(1200), Some(4500), Some(2)), ("good".to_string(), None, None, None)]
+    );
+}
+
+/// Tests hints are rendered in order, with their button, only for cards having some.
+#[tokio::test]
+async fn test_card_page_shows_hints() {
+    let content = "Question : Maths - Calculus - Derive x squared\nID: derive\nHint : Power rule\nHint : Multiply by the exponent\nAnswer : 2x\n\n\
+                   Question : Maths - Calculus - Derive x\nID: plain\nAnswer : 1";
+    let (server, _pool, _temp_dir) = setup_test_server_with_content(content, SchedulerKind::Sm2).await;
+
+    let text = server.get("/card/derive").await.text();
+    assert!(text.contains(r#"id="hint-btn""#));
+    assert!(text.contains("Hint 1/2"));
+    let first = text.find("Power rule").unwrap();
+    let second = text.find("Multiply by the exponent").unwrap();
+    let answer = text.find(r#"id="answer-content""#).unwrap();
+    assert!(first < second && second < answer);
+
+    let text = server.get("/card/plain").await.text();
+    assert!(!text.contains(r#"id="hint-btn""#));
+    assert!(!text.contains("card-hint"));
+}
+
+/// Tests typed-answer mode: input on practice when enabled, diff and suggested grade from /check_answer.
+#[tokio::test]
+async fn test_typed_answer_check() {
+    let content = "Question : Databases - SQLite - Which full-text search engine does SQLite ship?\nID: fts\n\
+                   Expected: FTS5 | SQLite FTS5\nAnswer : FTS5";
+    let (mut server, _pool, _temp_dir) = setup_test_server_with_content(content, SchedulerKind::Sm2).await;
+    server.do_save_cookies();
+
+    server.post("/apply_filters").form(&[("all_categories", "on"), ("all_subcategories", "on")]).await;
+    assert!(!server.get("/practice").await.text().contains(r#"id="typed-answer""#));
+
+    server
+        .post("/apply_filters")
+        .form(&[("all_categories", "on"), ("all_subcategories", "on"), ("typed_answers", "on")])
+        .await;
+    let text = server.get("/practice").await.text();
+    assert!(text.contains(r#"id="typed-answer""#));
+    assert!(!text.contains("Expected:"));
+
+    let response = server.post("/check_answer").form(&[("card_key", "fts"), ("answer", " sqlite  fts5. ")]).await;
+    response.assert_status_ok();
+    let text = response.text();
+    assert!(text.contains("Correct"));
+    assert!(text.contains(r#"data-suggested-grade="good""#));
+
+    let text = server.post("/check_answer").form(&[("card_key", "fts"), ("answer", "FTS4")]).await.text();
+    assert!(text.contains("Not quite"));
+    assert!(text.contains(r#"data-suggested-grade="hard""#));
+    assert!(text.contains(r#"<span class="diff-missing">5</span>"#));
+
+    let response = server.post("/check_answer").form(&[("card_key", "unknown"), ("answer", "FTS5")]).await;
+    assert_eq!(response.status_code(), 404);
+}
+
+/// Helper to extract the value of the quiz option containing `text`.
+fn extract_quiz_choice(html: &str, text: &str) -> Option<String> {
+    let at = html.find(text)?;
+    let start = html[..at].rfind(r#"name="choice" value=""#)? + r#"name="choice" value=""#.len();
+    let end = html[start..].find('"')?;
+    Some(html[start..start + end].to_string())
+}
+
+/// Tests a quiz over a subcategory: each card asked once with the others' answers, score at the end.
+#[tokio::test]
+async fn test_quiz_scores_answers() {
+    let content = "Question : Geography - Capitals - Capital of France?\nAnswer : Paris\n\n\
+                   Question : Geography - Capitals - Capital of Germany?\nAnswer : Berlin\n\n\
+                   Question : Geography - Capitals - Capital of Italy?\nAnswer : Rome";
+    let (mut server, _pool, _temp_dir) = setup_test_server_with_content(content, SchedulerKind::Sm2).await;
+    server.do_save_cookies();
+
+    let response = server
+        .post("/apply_filters")
+        .form(&[("all_categories", "on"), ("all_subcategories", "on"), ("action", "quiz")])
+        .await;
+    assert_eq!(response.header("location"), "/quiz");
+
+    let answers = [("France", "Paris"), ("Germany", "Berlin"), ("Italy", "Rome")];
+    for number in 1..=3 {
+        let text = server.get("/quiz").await.text();
+        assert!(text.contains(&format!("Question {}", number)));
+        // Reloading shows the same question
+        assert_eq!(extract_card_key(&server.get("/quiz").await.text()), extract_card_key(&text));
+
+        let (_, answer) = answers.iter().find(|(country, _)| text.contains(&format!("Capital of {}", country))).unwrap();
+        for (_, other) in answers {
+            assert!(text.contains(other));
+        }
+        let right = extract_quiz_choice(&text, answer).unwrap();
+        let wrong = (0..3).map(|i| i.to_string()).find(|choice| *choice != right).unwrap();
+        // First question right, the others wrong
+        let choice = if number == 1 { right } else { wrong };
+
+        let card_key = extract_card_key(&text).unwrap();
+        let text = server.post("/quiz_answer").form(&[("card_key", card_key.as_str()), ("choice", choice.as_str())]).await.text();
+        assert!(text.contains(if number == 1 { "Correct" } else { "Wrong" }));
+        assert!(text.contains("quiz-answer"));
+
+        // Answering twice does not count
+        let response = server.post("/quiz_answer").form(&[("card_key", card_key.as_str()), ("choice", "0")]).await;
+        assert_eq!(response.header("location"), "/quiz");
+    }
+
+    let response = server.get("/quiz").await;
+    assert_eq!(response.header("location"), "/quiz_results");
+    assert!(server.get("/quiz_results").await.text().contains("1/3 correct (33%)"));
+
+    // Results start a new quiz
+    assert!(server.get("/quiz").await.text().contains("Question 1"));
+}
+
+/// Tests declared distractors replace drawn ones, and cards without options are skipped.
+#[tokio::test]
+async fn test_quiz_uses_declared_distractors() {
+    let content = "Question : Databases - SQLite - Which full-text search engine does SQLite ship?\n\
+                   Distractors: Lucene | Xapian\nAnswer : FTS5\n\n\
+                   Question : Databases - SQLite - Which command shows the schema?\nAnswer : .schema\n\n\
+                   Question : Databases - Postgres - Default port?\nAnswer : 5432";
+    let (mut server, _pool, _temp_dir) = setup_test_server_with_content(content, SchedulerKind::Sm2).await;
+    server.do_save_cookies();
+
+    server.post("/apply_filters").form(&[("all_categories", "on"), ("all_subcategories", "on")]).await;
+
+    let mut asked = Vec::new();
+    while let Some(card_key) = extract_card_key(&server.get("/quiz").await.text()) {
+        let text = server.get("/quiz").await.text();
+        if text.contains("full-text search") {
+            assert!(text.contains("Lucene") && text.contains("Xapian"));
+            assert!(!text.contains(".schema"));
+        } else {
+            // Drawn from the other card of the subcategory
+            assert!(text.contains("FTS5"));
+        }
+        server.post("/quiz_answer").form(&[("card_key", card_key.as_str()), ("choice", "0")]).await;
+        asked.push(card_key);
+    }
+
+    // The Postgres card, alone in its subcategory, has no wrong option
+    assert_eq!(asked.len(), 2);
+    assert!(server.get("/quiz_results").await.text().contains("/2 correct"));
+}
+
+/// Tests statistics page shows retention per topic and charts.
+#[tokio::test]
+async fn test_stats_page_renders() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+    let keys = card_keys(&pool);
+
+    server.post("/grade").form(&[("card_key", keys[0].as_str()), ("grade", "good")]).await;
+    server.post("/grade").form(&[("card_key", keys[1].as_str()), ("grade", "again")]).await;
+
+    let response = server.get("/stats").await;
+
+    response.assert_status_ok();
+    let text = response.text();
+    assert!(text.contains("2 reviews - 50% retention"));
+    assert!(text.contains("Algebra"));
+    assert!(text.contains("Physics")); // Listed even without reviews
+    assert!(text.contains("Due - next 30 days"));
+}
+
+/// Tests search results list matching cards with highlighted terms.
+#[tokio::test]
+async fn test_search_page_lists_matches() {
+    let server = setup_test_server().await;
+
+    let response = server.get("/search").add_query_param("q", "what -gravity").await;
+
+    response.assert_status_ok();
+    let text = response.text();
+    assert!(text.contains("2 cards"));
+    assert!(text.contains("<mark>What</mark> is a triangle?"));
+    assert!(text.contains("<mark>What</mark> is 2+2?"));
+    assert!(!text.contains("gravity?"));
+    assert!(text.contains(r#"<span class="badge badge-primary">Math</span>"#));
+}
+
+/// Tests a malformed search is reported on the search page.
+#[tokio::test]
+async fn test_search_page_invalid_query() {
+    let server = setup_test_server().await;
+
+    let response = server.get("/search").add_query_param("q", "what OR").await;
+
+    response.assert_status_ok();
+    assert!(response.text().contains("Invalid search: OR must be placed between two search terms"));
+}
+
+/// Tests a page past the last one shows the last results page.
+#[tokio::test]
+async fn test_search_page_out_of_range_page() {
+    let server = setup_test_server().await;
+
+    let response = server.get("/search").add_query_param("q", "what").add_query_param("page", "7").await;
+
+    response.assert_status_ok();
+    let text = response.text();
+    assert!(text.contains("3 cards"));
+    assert!(!text.contains("Next"));
+}
+
+/// Tests a card is shown alone from its permalink.
+#[tokio::test]
+async fn test_card_permalink() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+    let keys = card_keys(&pool);
+
+    let response = server.get(&format!("/card/{}", keys[2])).await;
+
+    response.assert_status_ok();
+    let text = response.text();
+    assert!(text.contains("What is gravity?"));
+    assert!(text.contains("A fundamental force"));
+    assert!(text.contains(&format!(r#"data-permalink="/card/{}""#, keys[2])));
+    assert!(!text.contains(" due</small>"));
+}
+
+/// Tests an unknown card key is not found.
+#[tokio::test]
+async fn test_card_permalink_unknown_key() {
+    let server = setup_test_server().await;
+
+    let response = server.get("/card/no-such-card").expect_failure().await;
+
+    assert_eq!(response.status_code(), 404);
+}
+
+/// Tests practice pages and search results link to card permalinks.
+#[tokio::test]
+async fn test_practice_and_search_link_to_cards() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+    let keys = card_keys(&pool);
+
+    let practice = server.get("/practice").await.text();
+    assert!(keys.iter().any(|key| practice.contains(&format!(r#"data-permalink="/card/{}""#, key))));
+
+    let search = server.get("/search").add_query_param("q", "gravity").await.text();
+    assert!(search.contains(&format!(r#"<a href="/card/{}">"#, keys[2])));
+}
+
+/// Tests the deck listing groups cards by category and subcategory.
+#[tokio::test]
+async fn test_browse_lists_cards_by_group() {
+    let (server, pool, _temp_dir) = setup_test_server_with_pool().await;
+    let keys = card_keys(&pool);
+
+    let response = server.get("/browse").await;
+
+    response.assert_status_ok();
+    let text = response.text();
+    assert!(text.contains("3 cards"));
+    let algebra = text.find(r#"id="group-1">"#).unwrap();
+    let geometry = text.find(r#"id="group-2">"#).unwrap();
+    let physics = text.find(r#"id="group-3">"#).unwrap();
+    assert!(algebra < geometry && geometry < physics);
+    assert!(text[physics..].contains("Science - Physics"));
+    assert!(text.contains("<summary>Answer</summary>"));
+    assert!(text.contains(&format!(r#"<a href="/card/{}">Permalink</a>"#, keys[2])));
+}
+
+/// Tests the Browse button of the landing form lists the filtered cards.
+#[tokio::test]
+async fn test_browse_button_applies_filters() {
+    let mut server = setup_test_server().await;
+    // Filters are kept in the session
+    server.do_save_cookies();
+
+    let response = server
+        .post("/apply_filters")
+        .form(&[
+            ("categories", "Math"),
+            ("all_subcategories", "on"),
+            ("all_images", "on"),
+            ("action", "browse"),
+        ])
+        .await;
+    assert_eq!(response.header("location"), "/browse");
+
+    let text = server.get("/browse").add_query_param("sort", "question").await.text();
+    assert!(text.contains("2 cards"));
+    assert!(text.contains("What is a triangle?"));
+    assert!(!text.contains("What is gravity?"));
+}
+
+/// Tests live reload is off unless enabled (no event stream, no script).
+#[tokio::test]
+async fn test_live_reload_disabled_by_default() {
+    let server = setup_test_server().await;
+
+    let response = server.get("/events").expect_failure().await;
+    assert_eq!(response.status_code(), 404);
+
+    let landing = server.get("/").await;
+    assert!(!landing.text().contains("live_reload.js"));
 }